@@ -0,0 +1,29 @@
+-- Your SQL goes here
+CREATE TABLE "company_profile"(
+	"entity_id" UUID NOT NULL PRIMARY KEY,
+	"company_status" TEXT,
+	"company_type" TEXT,
+	"sic_codes" TEXT[] NOT NULL,
+	"date_of_creation" DATE,
+	"date_of_cessation" DATE,
+	"jurisdiction" TEXT,
+	"premises" TEXT,
+	"address_line_1" TEXT,
+	"address_line_2" TEXT,
+	"locality" TEXT,
+	"region" TEXT,
+	"postal_code" TEXT,
+	"country" TEXT,
+	"has_charges" BOOL,
+	"has_insolvency_history" BOOL,
+	"has_been_liquidated" BOOL,
+	"fetched_at" TIMESTAMP NOT NULL
+);
+
+CREATE TABLE "previous_name"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"entity_id" UUID NOT NULL,
+	"name" TEXT NOT NULL,
+	"effective_from" DATE,
+	"ceased_on" DATE
+);
@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "charge";
+DROP TABLE IF EXISTS "insolvency";
+DROP TABLE IF EXISTS "insolvency_date";
+DROP TABLE IF EXISTS "insolvency_practitioner";
+DROP TABLE IF EXISTS "recent_insolvency";
+DROP TABLE IF EXISTS "unusual_lender";
+
+-- enum values can't be dropped, so the type is recreated without 'charge_holder'
+DELETE FROM "relationship" WHERE "kind" = 'charge_holder';
+ALTER TYPE RELATIONSHIPKIND RENAME TO RELATIONSHIPKIND_OLD;
+CREATE TYPE RELATIONSHIPKIND AS ENUM ('shareholder', 'officer');
+ALTER TABLE "relationship" ALTER COLUMN "kind" TYPE RELATIONSHIPKIND USING "kind"::TEXT::RELATIONSHIPKIND;
+DROP TYPE RELATIONSHIPKIND_OLD;
@@ -0,0 +1,49 @@
+-- Your SQL goes here
+ALTER TYPE RELATIONSHIPKIND ADD VALUE 'charge_holder';
+
+CREATE TABLE "charge"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"entity_id" UUID NOT NULL,
+	"charge_code" TEXT,
+	"classification" TEXT,
+	"status" TEXT,
+	"created_on" DATE,
+	"delivered_on" DATE,
+	"satisfied_on" DATE
+);
+
+CREATE TABLE "insolvency"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"entity_id" UUID NOT NULL,
+	"case_number" TEXT,
+	"case_type" TEXT,
+	"notes" TEXT[] NOT NULL
+);
+
+CREATE TABLE "insolvency_date"(
+	"insolvency_id" UUID NOT NULL,
+	"kind" TEXT NOT NULL,
+	"date" DATE NOT NULL,
+	PRIMARY KEY("insolvency_id", "kind")
+);
+
+CREATE TABLE "insolvency_practitioner"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"insolvency_id" UUID NOT NULL,
+	"name" TEXT NOT NULL,
+	"role" TEXT,
+	"appointed_on" DATE,
+	"ceased_to_act_on" DATE,
+	"postal_code" TEXT,
+	"country" TEXT
+);
+
+CREATE TABLE "recent_insolvency"(
+	"entity_id" UUID NOT NULL PRIMARY KEY,
+	"recent" BOOL NOT NULL
+);
+
+CREATE TABLE "unusual_lender"(
+	"entity_id" UUID NOT NULL PRIMARY KEY,
+	"unusual" BOOL NOT NULL
+);
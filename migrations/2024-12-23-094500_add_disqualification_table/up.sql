@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE "disqualification"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"entity_id" UUID NOT NULL,
+	"case_identifier" TEXT,
+	"disqualification_type" TEXT,
+	"disqualified_from" DATE,
+	"disqualified_until" DATE,
+	"heard_on" DATE,
+	"undertaken_on" DATE,
+	"reason" TEXT,
+	"act" TEXT,
+	"section" TEXT,
+	"court_name" TEXT,
+	"company_names" TEXT[] NOT NULL
+);
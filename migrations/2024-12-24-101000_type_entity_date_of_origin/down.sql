@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "entity" ALTER COLUMN "date_of_origin" TYPE TEXT USING (
+	CASE
+		WHEN "date_of_origin_day_known" THEN TO_CHAR("date_of_origin", 'YYYY-MM-DD')
+		ELSE TO_CHAR("date_of_origin", '"0/"FMMM/YYYY')
+	END
+);
+ALTER TABLE "entity" DROP COLUMN "date_of_origin_day_known";
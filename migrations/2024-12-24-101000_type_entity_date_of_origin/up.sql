@@ -0,0 +1,20 @@
+-- Your SQL goes here
+-- individuals' dates of birth are only published to the month, so whether the day is
+-- known is stored next to the date (which falls on the first of the month otherwise)
+ALTER TABLE "entity" ADD COLUMN "date_of_origin_day_known" BOOL NOT NULL DEFAULT TRUE;
+
+-- shareholders were stored as 'day/month/year' with a day of 0 when unknown
+UPDATE "entity" SET "date_of_origin_day_known" = FALSE
+WHERE "date_of_origin" ~ '^0/\d{1,2}/\d{4}$';
+
+ALTER TABLE "entity" ALTER COLUMN "date_of_origin" TYPE DATE USING (
+	CASE
+		WHEN "date_of_origin" ~ '^\d{4}-\d{2}-\d{2}$' THEN "date_of_origin"::DATE
+		WHEN "date_of_origin" ~ '^\d{1,2}/([1-9]|1[0-2])/\d{4}$' THEN MAKE_DATE(
+			SPLIT_PART("date_of_origin", '/', 3)::INT,
+			SPLIT_PART("date_of_origin", '/', 2)::INT,
+			GREATEST(SPLIT_PART("date_of_origin", '/', 1)::INT, 1)
+		)
+		ELSE NULL
+	END
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "entity" DROP COLUMN "canonical_entity_id";
+DROP TABLE IF EXISTS "canonical_entity_key";
+DROP TABLE IF EXISTS "canonical_entity";
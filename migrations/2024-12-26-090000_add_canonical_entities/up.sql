@@ -0,0 +1,54 @@
+-- Your SQL goes here
+CREATE TABLE "canonical_entity"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"kind" ENTITYKIND NOT NULL,
+	"name" TEXT,
+	"created_at" TIMESTAMP NOT NULL
+);
+
+-- every identifier an entity has been seen with, e.g. ('officer_id', 'Abc123')
+CREATE TABLE "canonical_entity_key"(
+	"key_kind" TEXT NOT NULL,
+	"key_value" TEXT NOT NULL,
+	"canonical_entity_id" UUID NOT NULL REFERENCES "canonical_entity"("id"),
+	PRIMARY KEY("key_kind", "key_value")
+);
+
+CREATE INDEX "canonical_entity_key_canonical_entity_id_idx" ON "canonical_entity_key"("canonical_entity_id");
+
+ALTER TABLE "entity" ADD COLUMN "canonical_entity_id" UUID REFERENCES "canonical_entity"("id");
+
+CREATE INDEX "entity_canonical_entity_id_idx" ON "entity"("canonical_entity_id");
+
+-- existing entities are resolved by their entity number only. Each key is given its canonical
+-- entity's id up front, so the canonical entities can be inserted before the keys referencing them
+CREATE TEMPORARY TABLE "canonical_entity_backfill" ON COMMIT DROP AS
+SELECT
+	GEN_RANDOM_UUID() AS "canonical_entity_id",
+	"key_kind",
+	"key_value",
+	"kind",
+	"name"
+FROM (
+	SELECT
+		CASE "kind" WHEN 'individual' THEN 'person_number' ELSE 'registration_number' END AS "key_kind",
+		"company_house_number" AS "key_value",
+		MIN("kind"::TEXT)::ENTITYKIND AS "kind",
+		MAX("name") AS "name"
+	FROM "entity"
+	WHERE "company_house_number" <> ''
+	GROUP BY 1, 2
+) AS "keys";
+
+INSERT INTO "canonical_entity"("id", "kind", "name", "created_at")
+SELECT "canonical_entity_id", "kind", "name", NOW()
+FROM "canonical_entity_backfill";
+
+INSERT INTO "canonical_entity_key"("key_kind", "key_value", "canonical_entity_id")
+SELECT "key_kind", "key_value", "canonical_entity_id"
+FROM "canonical_entity_backfill";
+
+UPDATE "entity" SET "canonical_entity_id" = "canonical_entity_key"."canonical_entity_id"
+FROM "canonical_entity_key"
+WHERE "entity"."company_house_number" = "canonical_entity_key"."key_value"
+	AND ("entity"."kind" = 'individual') = ("canonical_entity_key"."key_kind" = 'person_number');
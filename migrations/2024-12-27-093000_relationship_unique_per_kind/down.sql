@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "relationship" DROP CONSTRAINT "relationship_pkey";
+DELETE FROM "relationship" AS "duplicate"
+USING "relationship" AS "kept"
+WHERE "duplicate"."parent_id" = "kept"."parent_id"
+	AND "duplicate"."child_id" = "kept"."child_id"
+	AND "duplicate"."kind" > "kept"."kind";
+ALTER TABLE "relationship" ADD PRIMARY KEY("parent_id", "child_id");
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- the same two entities can be related in more than one way, e.g. a director who is
+-- also a shareholder, so relationships are unique per kind
+ALTER TABLE "relationship" DROP CONSTRAINT "relationship_pkey";
+ALTER TABLE "relationship" ADD PRIMARY KEY("parent_id", "child_id", "kind");
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- every (entity, relation job kind) a check has queued, fetched_at is null while the
+-- job is still on the frontier
+CREATE TABLE "check_frontier"(
+	"check_id" UUID NOT NULL,
+	"entity_id" UUID NOT NULL,
+	"relation_job_kind" TEXT NOT NULL,
+	"remaining_depth" INT4 NOT NULL,
+	"enqueued_at" TIMESTAMP NOT NULL,
+	"fetched_at" TIMESTAMP,
+	PRIMARY KEY("check_id", "entity_id", "relation_job_kind")
+);
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE TABLE "check_traversal_policy"(
+	"check_id" UUID NOT NULL PRIMARY KEY,
+	"policy" JSONB NOT NULL
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "beneficial_owner_path";
+DROP TABLE IF EXISTS "beneficial_owner";
+ALTER TABLE "relationship" DROP COLUMN "ownership_band";
+DROP TYPE OWNERSHIPBAND;
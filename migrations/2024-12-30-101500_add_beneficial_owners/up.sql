@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TYPE OWNERSHIPBAND AS ENUM ('twenty_five_to_fifty', 'fifty_to_seventy_five', 'seventy_five_to_hundred');
+
+-- only set for shareholder relationships where the shareholder owns shares
+ALTER TABLE "relationship" ADD COLUMN "ownership_band" OWNERSHIPBAND;
+
+-- effective ownership of the check's root entity, summed over every ownership chain
+CREATE TABLE "beneficial_owner"(
+	"check_id" UUID NOT NULL,
+	"entity_id" UUID NOT NULL,
+	"min_ownership" FLOAT8 NOT NULL,
+	"max_ownership" FLOAT8 NOT NULL,
+	"is_ultimate" BOOL NOT NULL,
+	PRIMARY KEY("check_id", "entity_id")
+);
+
+-- each chain of shareholders from the root entity (excluded) up to the owner (included)
+CREATE TABLE "beneficial_owner_path"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"check_id" UUID NOT NULL,
+	"entity_id" UUID NOT NULL,
+	"path" UUID[] NOT NULL,
+	"min_ownership" FLOAT8 NOT NULL,
+	"max_ownership" FLOAT8 NOT NULL
+);
+
+CREATE INDEX "beneficial_owner_path_check_id_idx" ON "beneficial_owner_path"("check_id");
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "exposure";
+DROP TABLE IF EXISTS "check_propagation_policy";
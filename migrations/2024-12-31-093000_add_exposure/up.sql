@@ -0,0 +1,20 @@
+-- Your SQL goes here
+CREATE TABLE "check_propagation_policy"(
+	"check_id" UUID NOT NULL PRIMARY KEY,
+	"policy" JSONB NOT NULL
+);
+
+-- risk pushed onto an entity from a flag on one of its owners, officers or lenders
+CREATE TABLE "exposure"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"check_id" UUID NOT NULL,
+	"entity_id" UUID NOT NULL,
+	"origin_entity_id" UUID NOT NULL,
+	"origin_flag_id" UUID NOT NULL,
+	"flag_kind" FLAGKIND NOT NULL,
+	-- from the flagged entity (included) to the exposed entity (included)
+	"path" UUID[] NOT NULL,
+	"score" FLOAT8 NOT NULL
+);
+
+CREATE INDEX "exposure_check_id_idx" ON "exposure"("check_id");
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- rendered reports are kept exactly as served, so an audit can prove what was shown
+CREATE TABLE "check_report"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"check_id" UUID NOT NULL,
+	"format" TEXT NOT NULL,
+	"content" BYTEA NOT NULL,
+	-- hex encoded sha256 of the content
+	"content_hash" TEXT NOT NULL,
+	"generated_at" TIMESTAMP NOT NULL
+);
+
+CREATE INDEX "check_report_check_id_idx" ON "check_report"("check_id");
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "relationship_child_id_idx";
+DROP INDEX IF EXISTS "check_job_map_job_id_idx";
+DROP TABLE IF EXISTS "check_summary";
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+-- kept up to date as a check's jobs complete, so listing checks doesn't recompute each one
+CREATE TABLE "check_summary"(
+	"check_id" UUID NOT NULL PRIMARY KEY,
+	"root_entity_id" UUID,
+	"company_house_number" TEXT,
+	"name" TEXT,
+	"started_at" TIMESTAMP NOT NULL,
+	"completed_at" TIMESTAMP,
+	"has_error" BOOL NOT NULL,
+	"entity_count" INT8 NOT NULL,
+	"distinct_flags" FLAGKIND[] NOT NULL,
+	"updated_at" TIMESTAMP NOT NULL
+);
+
+-- completed jobs are mapped back to their check
+CREATE INDEX "check_job_map_job_id_idx" ON "check_job_map"("job_id");
+-- a check's relationships are loaded through their child entity
+CREATE INDEX "relationship_child_id_idx" ON "relationship"("child_id");
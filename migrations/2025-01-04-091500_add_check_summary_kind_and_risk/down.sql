@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "check_summary_started_at_idx";
+
+ALTER TABLE "check_summary"
+	DROP COLUMN "kind",
+	DROP COLUMN "risk_score",
+	DROP COLUMN "risk_level";
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- summaries are recomputed in the background when the web server starts, so existing rows can
+-- go rather than be backfilled
+DELETE FROM "check_summary";
+
+ALTER TABLE "check_summary"
+	ADD COLUMN "kind" CHECKKIND NOT NULL,
+	ADD COLUMN "risk_score" FLOAT8 NOT NULL,
+	ADD COLUMN "risk_level" TEXT NOT NULL;
+
+CREATE INDEX "check_summary_started_at_idx" ON "check_summary"("started_at", "check_id");
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "check_summary_tenant_id_idx";
+ALTER TABLE "check_summary" DROP COLUMN "tenant_id";
+DROP INDEX IF EXISTS "monitored_entity_tenant_id_idx";
+ALTER TABLE "monitored_entity" DROP COLUMN "tenant_id";
+DROP INDEX IF EXISTS "check_tenant_id_idx";
+ALTER TABLE "check" DROP COLUMN "tenant_id";
+DROP TABLE IF EXISTS "api_key";
+DROP TABLE IF EXISTS "tenant_user";
+DROP TABLE IF EXISTS "tenant";
@@ -0,0 +1,45 @@
+-- Your SQL goes here
+CREATE TABLE "tenant"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"name" TEXT NOT NULL,
+	"created_at" TIMESTAMP NOT NULL
+);
+
+CREATE TABLE "tenant_user"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"tenant_id" UUID NOT NULL REFERENCES "tenant"("id"),
+	"email" TEXT NOT NULL UNIQUE,
+	"name" TEXT,
+	"created_at" TIMESTAMP NOT NULL
+);
+
+-- only a hash of each key is stored, the key itself is shown once when it's created
+CREATE TABLE "api_key"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"tenant_id" UUID NOT NULL REFERENCES "tenant"("id"),
+	"user_id" UUID REFERENCES "tenant_user"("id"),
+	"name" TEXT NOT NULL,
+	"key_hash" TEXT NOT NULL UNIQUE,
+	"created_at" TIMESTAMP NOT NULL,
+	"last_used_at" TIMESTAMP,
+	"revoked_at" TIMESTAMP
+);
+
+-- everything created before tenants belongs to the default tenant
+INSERT INTO "tenant"("id", "name", "created_at")
+VALUES ('00000000-0000-0000-0000-000000000001', 'Default', NOW());
+
+ALTER TABLE "check" ADD COLUMN "tenant_id" UUID NOT NULL
+	DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES "tenant"("id");
+ALTER TABLE "check" ALTER COLUMN "tenant_id" DROP DEFAULT;
+CREATE INDEX "check_tenant_id_idx" ON "check"("tenant_id");
+
+ALTER TABLE "monitored_entity" ADD COLUMN "tenant_id" UUID NOT NULL
+	DEFAULT '00000000-0000-0000-0000-000000000001' REFERENCES "tenant"("id");
+ALTER TABLE "monitored_entity" ALTER COLUMN "tenant_id" DROP DEFAULT;
+CREATE INDEX "monitored_entity_tenant_id_idx" ON "monitored_entity"("tenant_id");
+
+ALTER TABLE "check_summary" ADD COLUMN "tenant_id" UUID NOT NULL
+	DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "check_summary" ALTER COLUMN "tenant_id" DROP DEFAULT;
+CREATE INDEX "check_summary_tenant_id_idx" ON "check_summary"("tenant_id", "started_at");
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "flag" DROP COLUMN "source_fetch_id";
+ALTER TABLE "relationship" DROP COLUMN "source_fetch_id";
+ALTER TABLE "entity" DROP COLUMN "source_fetch_id";
+DROP TABLE IF EXISTS "source_fetch";
+DROP TABLE IF EXISTS "raw_payload";
+DROP TRIGGER IF EXISTS "audit_log_append_only" ON "audit_log";
+DROP FUNCTION IF EXISTS "reject_audit_log_change"();
+DROP TABLE IF EXISTS "audit_log";
@@ -0,0 +1,44 @@
+-- Your SQL goes here
+-- every mutating api call, rows can only be inserted
+CREATE TABLE "audit_log"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"tenant_id" UUID NOT NULL REFERENCES "tenant"("id"),
+	"user_id" UUID REFERENCES "tenant_user"("id"),
+	"api_key_id" UUID REFERENCES "api_key"("id"),
+	"method" TEXT NOT NULL,
+	"path" TEXT NOT NULL,
+	"target_id" UUID,
+	"status" INT4 NOT NULL,
+	"occurred_at" TIMESTAMP NOT NULL
+);
+CREATE INDEX "audit_log_tenant_id_idx" ON "audit_log"("tenant_id", "occurred_at");
+
+CREATE FUNCTION "reject_audit_log_change"() RETURNS TRIGGER AS $$
+BEGIN
+	RAISE EXCEPTION 'audit_log is append only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER "audit_log_append_only"
+BEFORE UPDATE OR DELETE ON "audit_log"
+FOR EACH ROW EXECUTE FUNCTION "reject_audit_log_change"();
+
+-- identical responses are archived once
+CREATE TABLE "raw_payload"(
+	"response_hash" TEXT NOT NULL PRIMARY KEY,
+	"payload" JSONB NOT NULL
+);
+
+CREATE TABLE "source_fetch"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"check_id" UUID NOT NULL REFERENCES "check"("id"),
+	"source" TEXT NOT NULL,
+	"endpoint" TEXT NOT NULL,
+	"fetched_at" TIMESTAMP NOT NULL,
+	"response_hash" TEXT NOT NULL REFERENCES "raw_payload"("response_hash")
+);
+
+-- missing for anything recorded before provenance was kept
+ALTER TABLE "entity" ADD COLUMN "source_fetch_id" UUID REFERENCES "source_fetch"("id");
+ALTER TABLE "relationship" ADD COLUMN "source_fetch_id" UUID REFERENCES "source_fetch"("id");
+ALTER TABLE "flag" ADD COLUMN "source_fetch_id" UUID REFERENCES "source_fetch"("id");
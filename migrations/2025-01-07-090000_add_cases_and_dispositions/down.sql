@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "disposition";
+DROP TABLE IF EXISTS "sanctions_match";
+DROP TABLE IF EXISTS "check_case";
+
+DROP TYPE IF EXISTS VERDICT;
+DROP TYPE IF EXISTS CASESTATUS;
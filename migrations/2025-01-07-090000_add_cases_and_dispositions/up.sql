@@ -0,0 +1,41 @@
+-- Your SQL goes here
+CREATE TYPE CASESTATUS AS ENUM ('open', 'in_review', 'escalated', 'closed');
+CREATE TYPE VERDICT AS ENUM ('false_positive', 'true_match');
+
+-- the analyst workflow for a check, one per check
+CREATE TABLE "check_case"(
+	"check_id" UUID NOT NULL PRIMARY KEY REFERENCES "check"("id"),
+	"status" CASESTATUS NOT NULL,
+	"assignee_id" UUID REFERENCES "tenant_user"("id"),
+	"opened_at" TIMESTAMP NOT NULL,
+	"updated_at" TIMESTAMP NOT NULL
+);
+
+INSERT INTO "check_case"("check_id", "status", "assignee_id", "opened_at", "updated_at")
+SELECT "id", 'open', NULL, "started_at", "started_at" FROM "check";
+
+-- the open sanctions entity a screened individual was matched to
+CREATE TABLE "sanctions_match"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"entity_id" UUID NOT NULL REFERENCES "entity"("id"),
+	"open_sanctions_id" TEXT NOT NULL,
+	"caption" TEXT NOT NULL,
+	"source_fetch_id" UUID REFERENCES "source_fetch"("id")
+);
+CREATE INDEX "sanctions_match_entity_id_idx" ON "sanctions_match"("entity_id");
+
+-- an analyst's verdict on a flag or match, the latest for each is the one that applies
+CREATE TABLE "disposition"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"check_id" UUID NOT NULL REFERENCES "check_case"("check_id"),
+	"flag_id" UUID REFERENCES "flag"("id"),
+	"sanctions_match_id" UUID REFERENCES "sanctions_match"("id"),
+	"verdict" VERDICT NOT NULL,
+	"comment" TEXT,
+	"user_id" UUID REFERENCES "tenant_user"("id"),
+	"created_at" TIMESTAMP NOT NULL,
+	CHECK (("flag_id" IS NULL) <> ("sanctions_match_id" IS NULL))
+);
+CREATE INDEX "disposition_check_id_idx" ON "disposition"("check_id", "created_at");
+CREATE INDEX "disposition_flag_id_idx" ON "disposition"("flag_id");
+CREATE INDEX "disposition_sanctions_match_id_idx" ON "disposition"("sanctions_match_id");
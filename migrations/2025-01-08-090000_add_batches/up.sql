@@ -0,0 +1,27 @@
+-- Your SQL goes here
+-- checks submitted together from a CSV
+CREATE TABLE "batch"(
+	"id" UUID NOT NULL PRIMARY KEY,
+	"tenant_id" UUID NOT NULL REFERENCES "tenant"("id"),
+	"user_id" UUID REFERENCES "tenant_user"("id"),
+	-- usually the CSV's file name
+	"name" TEXT,
+	"submitted_at" TIMESTAMP NOT NULL
+);
+CREATE INDEX "batch_tenant_id_idx" ON "batch"("tenant_id", "submitted_at");
+
+-- each row is started as a check by the batch service, with either a check or an error
+-- once it has been
+CREATE TABLE "batch_row"(
+	"batch_id" UUID NOT NULL REFERENCES "batch"("id"),
+	"row_number" INT4 NOT NULL,
+	"company_number" TEXT,
+	"name" TEXT,
+	"postcode" TEXT,
+	"traversal_policy" JSONB NOT NULL,
+	"check_id" UUID REFERENCES "check"("id"),
+	"error" TEXT,
+	"started_at" TIMESTAMP,
+	PRIMARY KEY ("batch_id", "row_number")
+);
+CREATE INDEX "batch_row_check_id_idx" ON "batch_row"("check_id");
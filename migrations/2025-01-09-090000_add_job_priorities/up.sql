@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE TYPE PRIORITY AS ENUM ('interactive', 'batch', 'background');
+
+-- the priority the check's jobs are queued at
+ALTER TABLE "check" ADD COLUMN "priority" PRIORITY NOT NULL DEFAULT 'interactive';
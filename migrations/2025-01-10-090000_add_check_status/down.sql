@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "parked_job";
+
+ALTER TABLE "check_summary" DROP COLUMN "status";
+
+ALTER TABLE "check" DROP COLUMN "status_updated_at";
+ALTER TABLE "check" DROP COLUMN "status";
+
+DROP TYPE CHECKSTATUS;
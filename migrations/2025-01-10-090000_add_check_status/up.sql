@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TYPE CHECKSTATUS AS ENUM ('running', 'paused', 'cancelled');
+
+ALTER TABLE "check" ADD COLUMN "status" CHECKSTATUS NOT NULL DEFAULT 'running';
+ALTER TABLE "check" ADD COLUMN "status_updated_at" TIMESTAMP;
+
+ALTER TABLE "check_summary" ADD COLUMN "status" CHECKSTATUS NOT NULL DEFAULT 'running';
+
+-- jobs of a paused check, held until it's resumed
+CREATE TABLE "parked_job"(
+	"job_id" UUID NOT NULL PRIMARY KEY REFERENCES "job"("id"),
+	"check_id" UUID NOT NULL REFERENCES "check"("id"),
+	"topic" TEXT NOT NULL,
+	"job" JSONB NOT NULL,
+	"parked_at" TIMESTAMP NOT NULL
+);
+
+CREATE INDEX "parked_job_check_id_idx" ON "parked_job"("check_id");
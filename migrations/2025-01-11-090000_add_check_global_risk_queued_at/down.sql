@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "check" DROP COLUMN "global_risk_queued_at";
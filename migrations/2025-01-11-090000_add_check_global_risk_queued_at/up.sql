@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- set when the check's beneficial owner and exposure jobs are queued, so they're queued once
+ALTER TABLE "check" ADD COLUMN "global_risk_queued_at" TIMESTAMP;
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "flag" DROP COLUMN "sanctions_match_id";
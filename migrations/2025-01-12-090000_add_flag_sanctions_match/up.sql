@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- the sanctions match a flag was raised from, so a false positive is only kept from being
+-- raised again from the same open sanctions entity
+ALTER TABLE "flag" ADD COLUMN "sanctions_match_id" UUID REFERENCES "sanctions_match"("id");
+
+-- flags raised from open sanctions were archived with the fetch their match was found in
+UPDATE "flag" SET "sanctions_match_id" = "sanctions_match"."id"
+FROM "flags", "sanctions_match"
+WHERE "flags"."flag_id" = "flag"."id"
+	AND "sanctions_match"."entity_id" = "flags"."entity_id"
+	AND "sanctions_match"."source_fetch_id" = "flag"."source_fetch_id";
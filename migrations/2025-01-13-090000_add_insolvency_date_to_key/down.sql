@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM "insolvency_date" AS "later"
+USING "insolvency_date" AS "earlier"
+WHERE "later"."insolvency_id" = "earlier"."insolvency_id"
+	AND "later"."kind" = "earlier"."kind"
+	AND "later"."date" > "earlier"."date";
+ALTER TABLE "insolvency_date" DROP CONSTRAINT "insolvency_date_pkey";
+ALTER TABLE "insolvency_date" ADD PRIMARY KEY("insolvency_id", "kind");
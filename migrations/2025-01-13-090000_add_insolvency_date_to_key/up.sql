@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- a case can have several dates of the same kind, e.g. more than one petition
+ALTER TABLE "insolvency_date" DROP CONSTRAINT "insolvency_date_pkey";
+ALTER TABLE "insolvency_date" ADD PRIMARY KEY("insolvency_id", "kind", "date");
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "raw_payload" ADD COLUMN "payload" JSONB;
+UPDATE "raw_payload" SET "payload" = convert_from("body", 'UTF8')::JSONB;
+ALTER TABLE "raw_payload" ALTER COLUMN "payload" SET NOT NULL;
+ALTER TABLE "raw_payload" DROP COLUMN "body";
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- payloads are kept as the bytes received, so they can be checked against their hash.
+-- Those archived before were normalised as jsonb, so won't hash to their response_hash
+ALTER TABLE "raw_payload" ADD COLUMN "body" BYTEA;
+UPDATE "raw_payload" SET "body" = convert_to("payload"::TEXT, 'UTF8');
+ALTER TABLE "raw_payload" ALTER COLUMN "body" SET NOT NULL;
+ALTER TABLE "raw_payload" DROP COLUMN "payload";
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- set while a recompute of the check's exposures is queued, so flags found together share one
+ALTER TABLE "check" ADD COLUMN "exposure_requested_at" TIMESTAMP;
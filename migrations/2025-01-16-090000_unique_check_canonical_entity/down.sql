@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "check_entity_map_check_id_canonical_entity_id_idx";
+ALTER TABLE "check_entity_map" DROP COLUMN "canonical_entity_id";
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+-- a check holds one entity per canonical entity, even when two workers find it at once
+ALTER TABLE "check_entity_map" ADD COLUMN "canonical_entity_id" UUID REFERENCES "canonical_entity"("id");
+
+-- where a check already holds duplicates the first is kept as the check's entity
+UPDATE "check_entity_map" SET "canonical_entity_id" = "first"."canonical_entity_id"
+FROM (
+	SELECT DISTINCT ON ("check_entity_map"."check_id", "entity"."canonical_entity_id")
+		"check_entity_map"."check_id",
+		"check_entity_map"."entity_id",
+		"entity"."canonical_entity_id"
+	FROM "check_entity_map"
+	INNER JOIN "entity" ON "entity"."id" = "check_entity_map"."entity_id"
+	WHERE "entity"."canonical_entity_id" IS NOT NULL
+	ORDER BY "check_entity_map"."check_id", "entity"."canonical_entity_id", "check_entity_map"."entity_id"
+) AS "first"
+WHERE "check_entity_map"."check_id" = "first"."check_id"
+	AND "check_entity_map"."entity_id" = "first"."entity_id";
+
+CREATE UNIQUE INDEX "check_entity_map_check_id_canonical_entity_id_idx" ON "check_entity_map"("check_id", "canonical_entity_id");
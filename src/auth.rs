@@ -0,0 +1,118 @@
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// Prefixed so a leaked key is easy to recognise
+const API_KEY_PREFIX: &str = "ci_";
+
+// What a JWT asserts about its bearer, tokens are issued by the deployment's identity
+// provider and signed with the shared JWT_SECRET
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Claims {
+    // the tenant user's id
+    pub sub: Uuid,
+    pub tenant_id: Uuid,
+    // seconds since the epoch
+    pub exp: i64,
+}
+
+pub fn generate_api_key() -> String {
+    format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+// Only the hash of a key is stored
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn is_jwt(token: &str) -> bool {
+    token.matches('.').count() == 2
+}
+
+// Signs the claims as an HS256 JWT
+pub fn encode_jwt(claims: &Claims, secret: &[u8]) -> Result<String, failure::Error> {
+    Ok(jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        claims,
+        &EncodingKey::from_secret(secret),
+    )?)
+}
+
+// Verifies an HS256 JWT's signature and expiry. The algorithm is fixed rather than
+// trusted from the token's header, and tokens are expired from the second in exp
+pub fn decode_jwt(token: &str, secret: &[u8]) -> Result<Claims, failure::Error> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_required_spec_claims(&["exp", "sub"]);
+    validation.leeway = 0;
+
+    Ok(
+        jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(secret), &validation)?
+            .claims,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    #[test]
+    fn jwt_round_trips_and_rejects_tampering() {
+        let secret = b"secret";
+        let claims = Claims {
+            sub: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            exp: jsonwebtoken::get_current_timestamp() as i64 + 60,
+        };
+        let token = encode_jwt(&claims, secret).unwrap();
+
+        assert!(is_jwt(&token));
+        assert_eq!(decode_jwt(&token, secret).unwrap(), claims);
+        assert!(decode_jwt(&token, b"other secret").is_err());
+        assert!(decode_jwt(&format!("{}.extra", token), secret).is_err());
+
+        let other_claims = Claims {
+            tenant_id: Uuid::new_v4(),
+            ..claims
+        };
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&other_claims).unwrap());
+        let mut parts: Vec<&str> = token.split('.').collect();
+        parts[1] = &forged_payload;
+        assert!(decode_jwt(&parts.join("."), secret).is_err());
+
+        // unsigned tokens aren't accepted whatever their header says
+        let unsigned_header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#);
+        assert!(decode_jwt(&format!("{}.{}.", unsigned_header, parts[1]), secret).is_err());
+    }
+
+    #[test]
+    fn expired_jwts_are_rejected() {
+        let claims = Claims {
+            sub: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            exp: jsonwebtoken::get_current_timestamp() as i64 - 1,
+        };
+        let token = encode_jwt(&claims, b"secret").unwrap();
+
+        assert!(decode_jwt(&token, b"secret").is_err());
+    }
+
+    #[test]
+    fn api_keys_are_hashed() {
+        let key = generate_api_key();
+
+        assert!(!is_jwt(&key));
+        assert_ne!(generate_api_key(), key);
+        assert_eq!(hash_api_key(&key), hash_api_key(&key));
+        assert_eq!(hash_api_key(&key).len(), 64);
+    }
+}
@@ -0,0 +1,283 @@
+use chrono::NaiveDateTime;
+use failure::format_err;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    company_house::company_house_types::CompanyItem,
+    jobs::relation_jobs::TraversalPolicy,
+    models::{Batch, BatchRow},
+    postgres::Database,
+};
+
+// More rows than this are refused, large submissions should be split
+pub const MAX_BATCH_ROWS: usize = 5000;
+
+// The columns of a batch CSV, all optional. Rows give either a company number, or a name
+// and postcode. policy is a traversal policy as JSON, which depth overrides the max_depth of
+#[derive(Deserialize)]
+struct CsvRow {
+    company_number: Option<String>,
+    name: Option<String>,
+    postcode: Option<String>,
+    depth: Option<usize>,
+    policy: Option<String>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_row(batch_id: Uuid, row_number: i32, csv_row: CsvRow) -> Result<BatchRow, failure::Error> {
+    let company_number = non_empty(csv_row.company_number);
+    let name = non_empty(csv_row.name);
+    let postcode = non_empty(csv_row.postcode);
+    if company_number.is_none() && (name.is_none() || postcode.is_none()) {
+        return Err(format_err!(
+            "either company_number, or name and postcode, are required"
+        ));
+    }
+
+    let mut traversal_policy = match non_empty(csv_row.policy) {
+        Some(policy) => serde_json::from_str::<TraversalPolicy>(&policy)
+            .map_err(|e| format_err!("policy isn't a traversal policy: {}", e))?,
+        None => TraversalPolicy::default(),
+    };
+    if let Some(depth) = csv_row.depth {
+        traversal_policy.max_depth = depth;
+    }
+    traversal_policy.validate()?;
+
+    Ok(BatchRow {
+        batch_id,
+        row_number,
+        company_number,
+        name,
+        postcode,
+        traversal_policy: serde_json::to_value(&traversal_policy)?,
+        check_id: None,
+        error: None,
+        started_at: None,
+    })
+}
+
+// The whole CSV is refused if any row is invalid, naming the first which is
+pub fn parse_batch_csv(batch_id: Uuid, csv: &[u8]) -> Result<Vec<BatchRow>, failure::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv);
+    let mut rows = vec![];
+
+    for (i, csv_row) in reader.deserialize::<CsvRow>().enumerate() {
+        let row_number = i as i32 + 1;
+        let row = csv_row
+            .map_err(failure::Error::from)
+            .and_then(|csv_row| parse_row(batch_id, row_number, csv_row))
+            .map_err(|e| format_err!("Row {}: {}", row_number, e))?;
+        rows.push(row);
+
+        if rows.len() > MAX_BATCH_ROWS {
+            return Err(format_err!(
+                "A batch can have at most {} rows",
+                MAX_BATCH_ROWS
+            ));
+        }
+    }
+
+    if rows.is_empty() {
+        return Err(format_err!("The CSV has no rows"));
+    }
+
+    Ok(rows)
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchRowStatus {
+    // waiting for its turn to be started
+    Pending,
+    Running,
+    Completed,
+    // couldn't be started, or its check had a job fail
+    Failed,
+}
+
+impl BatchRowStatus {
+    fn of(row: &BatchRow, completed_at: Option<NaiveDateTime>, has_error: Option<bool>) -> Self {
+        match (row.check_id, &row.error, completed_at) {
+            (_, Some(_), _) => BatchRowStatus::Failed,
+            (None, None, _) => BatchRowStatus::Pending,
+            (Some(_), None, None) => BatchRowStatus::Running,
+            (Some(_), None, Some(_)) if has_error == Some(true) => BatchRowStatus::Failed,
+            (Some(_), None, Some(_)) => BatchRowStatus::Completed,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BatchRowProgress {
+    pub row_number: i32,
+    pub company_number: Option<String>,
+    pub name: Option<String>,
+    pub postcode: Option<String>,
+    pub status: BatchRowStatus,
+    pub check_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchProgress {
+    #[serde(flatten)]
+    pub batch: Batch,
+    pub total: usize,
+    pub pending: usize,
+    pub running: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub rows: Vec<BatchRowProgress>,
+}
+
+pub fn get_batch_progress(
+    database: &mut Database,
+    batch_id: Uuid,
+) -> Result<BatchProgress, failure::Error> {
+    let batch = database.get_batch(batch_id)?;
+    let rows: Vec<BatchRowProgress> = database
+        .get_batch_rows(batch_id)?
+        .into_iter()
+        .map(|(row, completed_at, has_error)| BatchRowProgress {
+            status: BatchRowStatus::of(&row, completed_at, has_error),
+            row_number: row.row_number,
+            company_number: row.company_number,
+            name: row.name,
+            postcode: row.postcode,
+            check_id: row.check_id,
+            error: row.error,
+        })
+        .collect();
+    let count = |status| rows.iter().filter(|row| row.status == status).count();
+
+    Ok(BatchProgress {
+        batch,
+        total: rows.len(),
+        pending: count(BatchRowStatus::Pending),
+        running: count(BatchRowStatus::Running),
+        completed: count(BatchRowStatus::Completed),
+        failed: count(BatchRowStatus::Failed),
+        rows,
+    })
+}
+
+fn normalise(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+// Of the companies found searching for the name, the one registered at the postcode. If
+// several are, the one with exactly the name
+pub fn find_company<'a>(
+    items: &'a [CompanyItem],
+    name: &str,
+    postcode: &str,
+) -> Result<&'a CompanyItem, failure::Error> {
+    let normalised_postcode = postcode.replace(' ', "").to_uppercase();
+    let at_postcode: Vec<&CompanyItem> = items
+        .iter()
+        .filter(|item| {
+            item.address
+                .postal_code
+                .as_ref()
+                .is_some_and(|postal_code| {
+                    postal_code.replace(' ', "").to_uppercase() == normalised_postcode
+                })
+        })
+        .collect();
+
+    let candidates = match at_postcode.len() {
+        0 => vec![],
+        1 => at_postcode,
+        _ => at_postcode
+            .into_iter()
+            .filter(|item| normalise(&item.title) == normalise(name))
+            .collect(),
+    };
+
+    match candidates.as_slice() {
+        [item] => Ok(item),
+        [] => Err(format_err!("No company named {} at {}", name, postcode)),
+        _ => Err(format_err!(
+            "Several companies named {} at {}",
+            name,
+            postcode
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rows_and_refuses_invalid_ones() {
+        let batch_id = Uuid::new_v4();
+        let csv = b"company_number,name,postcode,depth,policy
+123,,,2,
+,Acme Ltd,SW1A 1AA,,\"{\"\"include_resigned_officers\"\": false}\"
+";
+        let rows = parse_batch_csv(batch_id, csv).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].company_number.as_deref(), Some("123"));
+        assert_eq!(rows[0].traversal_policy["max_depth"], 2);
+        assert_eq!(rows[1].company_number, None);
+        assert_eq!(rows[1].postcode.as_deref(), Some("SW1A 1AA"));
+        assert_eq!(rows[1].traversal_policy["include_resigned_officers"], false);
+
+        let error = parse_batch_csv(batch_id, b"company_number,name,postcode\n123,,\n,Acme,\n")
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Row 2:"));
+    }
+
+    fn company(number: &str, title: &str, postal_code: &str) -> CompanyItem {
+        serde_json::from_value(serde_json::json!({
+            "address": { "postal_code": postal_code },
+            "address_snippet": "",
+            "company_number": number,
+            "company_status": "active",
+            "company_type": "ltd",
+            "date_of_creation": "2020-01-01",
+            "kind": "searchresults#company",
+            "title": title
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_company_by_postcode_then_name() {
+        let items = vec![
+            company("1", "ACME LIMITED", "SW1A 1AA"),
+            company("2", "ACME HOLDINGS LIMITED", "EC1A 1BB"),
+            company("3", "ACME  TRADING LIMITED", "EC1A 1BB"),
+        ];
+
+        assert_eq!(
+            find_company(&items, "Acme Ltd", "sw1a1aa")
+                .unwrap()
+                .company_number,
+            "1"
+        );
+        assert_eq!(
+            find_company(&items, "acme trading limited", "EC1A 1BB")
+                .unwrap()
+                .company_number,
+            "3"
+        );
+        assert!(find_company(&items, "Acme Ltd", "EC1A 1BB").is_err());
+        assert!(find_company(&items, "Acme Ltd", "N1 9GU").is_err());
+    }
+}
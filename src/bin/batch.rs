@@ -0,0 +1,80 @@
+use std::{env, fs, path::Path};
+
+use chrono::Utc;
+use dotenv::dotenv;
+use uuid::Uuid;
+use Company_Investigation::{
+    batch::{get_batch_progress, parse_batch_csv},
+    jobs::batch_jobs::submit_batch,
+    models::Batch,
+    postgres::Database,
+    pulsar::PulsarClient,
+    workers::batch_worker::BATCH_TOPIC,
+};
+
+const USAGE: &str = "Usage:
+    batch submit <tenant id> <csv file> [user id]
+    batch progress <batch id>";
+
+async fn run(args: &[String]) -> Result<(), failure::Error> {
+    let mut database = Database::connect()?;
+    let arg = |i: usize| {
+        args.get(i)
+            .cloned()
+            .ok_or_else(|| failure::format_err!("{}", USAGE))
+    };
+
+    match arg(0)?.as_str() {
+        "submit" => {
+            let csv_path = arg(2)?;
+            let batch = Batch {
+                id: Uuid::new_v4(),
+                tenant_id: arg(1)?.parse()?,
+                user_id: args.get(3).map(|user_id| user_id.parse()).transpose()?,
+                name: Path::new(&csv_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string()),
+                submitted_at: Utc::now().naive_utc(),
+            };
+            let rows = parse_batch_csv(batch.id, &fs::read(&csv_path)?)?;
+
+            let pulsar_client = PulsarClient::new().await;
+            let mut producer = pulsar_client.create_producer(BATCH_TOPIC, None, None).await;
+            submit_batch(&mut database, &mut producer, &batch, &rows).await?;
+            println!("Submitted batch {} of {} rows", batch.id, rows.len());
+        }
+        "progress" => {
+            let progress = get_batch_progress(&mut database, arg(1)?.parse()?)?;
+            println!(
+                "{} rows: {} pending, {} running, {} completed, {} failed",
+                progress.total,
+                progress.pending,
+                progress.running,
+                progress.completed,
+                progress.failed
+            );
+            for row in progress.rows.iter().filter(|row| row.error.is_some()) {
+                println!(
+                    "Row {}: {}",
+                    row.row_number,
+                    row.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        _ => return Err(failure::format_err!("{}", USAGE)),
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    env_logger::init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -1,12 +1,42 @@
+use std::env;
+
 use dotenv::dotenv;
-use Company_Investigation::workers::entity_relation_worker::EntityRelationWorker;
+use Company_Investigation::{
+    company_house::company_house_apis::CompanyHouseClient,
+    registry::{
+        fixture_client::{
+            FixtureRegistryClient, RecordingRegistryClient, REGISTRY_FIXTURE_DIR,
+            REGISTRY_RECORD_DIR,
+        },
+        registry_client::RegistryClient,
+    },
+    workers::entity_relation_worker::EntityRelationWorker,
+};
+
+async fn run<C: RegistryClient>(registry_client: C) {
+    let mut worker = EntityRelationWorker::new_worker(registry_client)
+        .await
+        .expect("Should be able to create worker");
+    worker.do_work().await;
+}
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
     env_logger::init();
-    let mut worker = EntityRelationWorker::new_worker()
-        .await
-        .expect("Should be able to create worker");
-    worker.do_work().await;
+
+    match (
+        env::var(REGISTRY_FIXTURE_DIR),
+        env::var(REGISTRY_RECORD_DIR),
+    ) {
+        (Ok(fixture_dir), _) => run(FixtureRegistryClient::new(fixture_dir)).await,
+        (_, Ok(record_dir)) => {
+            run(RecordingRegistryClient::new(
+                CompanyHouseClient::new(),
+                record_dir,
+            ))
+            .await
+        }
+        _ => run(CompanyHouseClient::new()).await,
+    }
 }
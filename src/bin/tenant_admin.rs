@@ -0,0 +1,71 @@
+use std::env;
+
+use chrono::Utc;
+use dotenv::dotenv;
+use uuid::Uuid;
+use Company_Investigation::{
+    auth::{generate_api_key, hash_api_key},
+    models::ApiKey,
+    postgres::Database,
+};
+
+const USAGE: &str = "Usage:
+    tenant_admin create-tenant <name>
+    tenant_admin create-user <tenant id> <email> [name]
+    tenant_admin create-api-key <tenant id> <key name> [user id]
+    tenant_admin revoke-api-key <api key id>";
+
+fn run(args: &[String]) -> Result<(), failure::Error> {
+    let mut database = Database::connect()?;
+    let arg = |i: usize| {
+        args.get(i)
+            .cloned()
+            .ok_or_else(|| failure::format_err!("{}", USAGE))
+    };
+
+    match arg(0)?.as_str() {
+        "create-tenant" => {
+            let tenant_id = database.insert_tenant(arg(1)?)?;
+            println!("Created tenant {}", tenant_id);
+        }
+        "create-user" => {
+            let user_id =
+                database.insert_tenant_user(arg(1)?.parse()?, arg(2)?, args.get(3).cloned())?;
+            println!("Created user {}", user_id);
+        }
+        "create-api-key" => {
+            let key = generate_api_key();
+            let api_key = ApiKey {
+                id: Uuid::new_v4(),
+                tenant_id: arg(1)?.parse()?,
+                user_id: args.get(3).map(|user_id| user_id.parse()).transpose()?,
+                name: arg(2)?,
+                key_hash: hash_api_key(&key),
+                created_at: Utc::now().naive_utc(),
+                last_used_at: None,
+                revoked_at: None,
+            };
+            database.insert_api_key(&api_key)?;
+            // only the hash is stored, so this is the one chance to copy the key
+            println!("Created API key {}: {}", api_key.id, key);
+        }
+        "revoke-api-key" => {
+            database.revoke_api_key(arg(1)?.parse()?)?;
+            println!("Revoked API key");
+        }
+        _ => return Err(failure::format_err!("{}", USAGE)),
+    }
+
+    Ok(())
+}
+
+fn main() {
+    dotenv().ok();
+    env_logger::init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
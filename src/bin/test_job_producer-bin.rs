@@ -3,10 +3,10 @@ use uuid::Uuid;
 use Company_Investigation::{
     jobs::{
         jobs::JobKind,
-        relation_jobs::{RelationJob, RelationJobKind},
+        relation_jobs::{RelationJob, RelationJobKind, TraversalPolicy},
     },
-    models::{Checkkind, Entity},
-    postgres::Database,
+    models::{Checkkind, Entity, Priority},
+    postgres::{Database, DEFAULT_TENANT_ID},
     pulsar::PulsarClient,
     workers::entity_relation_worker::ENTITY_RELATION_TOPIC,
 };
@@ -23,9 +23,11 @@ async fn simulate_find_shareholders_endpoint() {
 
     let company_house_number = format!("{:0>8}", company_house_number);
 
-    let mut conn = Database::connect().expect("Should be able to connect to db");
+    let mut conn = Database::connect()
+        .expect("Should be able to connect to db")
+        .for_tenant(DEFAULT_TENANT_ID);
     let check_id = conn
-        .insert_check(Checkkind::EntityRelation)
+        .insert_check(Checkkind::EntityRelation, Priority::Interactive)
         .expect("Should be able to insert check");
     let child_id = conn
         .insert_entity(
@@ -46,6 +48,10 @@ async fn simulate_find_shareholders_endpoint() {
         officer_id: None,
         remaining_depth: DEPTH,
         relation_job_kind: RelationJobKind::Officers,
+        traversal_policy: TraversalPolicy {
+            max_depth: DEPTH,
+            ..Default::default()
+        },
     });
 
     producer
@@ -60,6 +66,10 @@ async fn simulate_find_shareholders_endpoint() {
         officer_id: None,
         remaining_depth: DEPTH,
         relation_job_kind: RelationJobKind::Shareholders,
+        traversal_policy: TraversalPolicy {
+            max_depth: DEPTH,
+            ..Default::default()
+        },
     });
 
     producer
@@ -74,6 +84,10 @@ async fn simulate_find_shareholders_endpoint() {
         officer_id: None,
         remaining_depth: DEPTH,
         relation_job_kind: RelationJobKind::Appointments,
+        traversal_policy: TraversalPolicy {
+            max_depth: DEPTH,
+            ..Default::default()
+        },
     });
 
     producer
@@ -0,0 +1,83 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web, Error, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use log::error;
+use uuid::Uuid;
+use Company_Investigation::{
+    models::AuditLog,
+    postgres::{Database, DatabasePool},
+};
+
+use crate::{auth::Caller, error::ApiError};
+
+// Set on the response by handlers which create a check or report, which isn't in the path
+#[derive(Clone, Copy)]
+struct AuditTarget(Uuid);
+
+pub fn with_target(mut response: HttpResponse, target_id: Uuid) -> HttpResponse {
+    response.extensions_mut().insert(AuditTarget(target_id));
+    response
+}
+
+fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+// Otherwise the target is the first id in the path, e.g. the check being cancelled
+fn path_target(req: &HttpRequest) -> Option<Uuid> {
+    req.match_info()
+        .iter()
+        .find_map(|(_, value)| value.parse().ok())
+}
+
+// Records every mutating call by an authenticated caller, whether or not it succeeded.
+// Runs inside authenticate, so the caller is known
+pub async fn record_mutations(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let caller = req.extensions().get::<Caller>().copied();
+    let pool = req.app_data::<web::Data<DatabasePool>>().cloned();
+    let (caller, pool) = match (caller, pool) {
+        (Some(caller), Some(pool)) if is_mutating(req.method()) => (caller, pool),
+        _ => return next.call(req).await,
+    };
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+
+    let response = next.call(req).await?;
+
+    let audit_log = AuditLog {
+        id: Uuid::new_v4(),
+        tenant_id: caller.tenant_id,
+        user_id: caller.user_id,
+        api_key_id: caller.api_key_id,
+        method,
+        path,
+        target_id: response
+            .response()
+            .extensions()
+            .get::<AuditTarget>()
+            .map(|target| target.0)
+            .or_else(|| path_target(response.request())),
+        status: response.status().as_u16() as i32,
+        occurred_at: Utc::now().naive_utc(),
+    };
+    let recorded = web::block(move || Database::from_pool(&pool)?.insert_audit_log(&audit_log))
+        .await
+        .map_err(|e| failure::format_err!("Blocking database task failed: {}", e))
+        .and_then(|recorded| recorded);
+    // every call must be audited, so one that couldn't be is failed, even though what it
+    // did has already happened
+    if let Err(e) = recorded {
+        error!("Failed to record audit log: {}", e);
+        return Err(ApiError::from(e).into());
+    }
+
+    Ok(response)
+}
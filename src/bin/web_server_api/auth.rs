@@ -0,0 +1,176 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, FromRequest, HttpMessage, HttpRequest,
+};
+use uuid::Uuid;
+use Company_Investigation::{
+    auth::{decode_jwt, hash_api_key, is_jwt},
+    postgres::{Database, DatabasePool},
+};
+
+use crate::error::ApiError;
+
+// Served without credentials so clients can discover how to authenticate
+const PUBLIC_PATHS: [&str; 1] = ["/v1/openapi.json"];
+
+pub struct AuthConfig {
+    // JWTs are only accepted when JWT_SECRET is set
+    jwt_secret: Option<Vec<u8>>,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        Self {
+            jwt_secret: std::env::var("JWT_SECRET")
+                .ok()
+                .map(|secret| secret.into_bytes()),
+        }
+    }
+}
+
+// Who made the request, set by the authenticate middleware
+#[derive(Clone, Copy, Debug)]
+pub struct Caller {
+    pub tenant_id: Uuid,
+    // missing for API keys made for the tenant as a whole
+    pub user_id: Option<Uuid>,
+    pub api_key_id: Option<Uuid>,
+}
+
+enum Credentials {
+    ApiKey(String),
+    Jwt(String),
+}
+
+// Either "X-Api-Key: <key>" or "Authorization: Bearer <key or jwt>"
+fn credentials(req: &ServiceRequest) -> Result<Credentials, ApiError> {
+    if let Some(api_key) = req.headers().get("X-Api-Key") {
+        let api_key = api_key
+            .to_str()
+            .map_err(|_| ApiError::unauthorized("Malformed X-Api-Key header"))?;
+        return Ok(Credentials::ApiKey(api_key.to_string()));
+    }
+
+    let authorization = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| ApiError::unauthorized("Missing credentials"))?
+        .to_str()
+        .map_err(|_| ApiError::unauthorized("Malformed Authorization header"))?;
+    let token = authorization
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| ApiError::unauthorized("Authorization must be a Bearer token"))?
+        .trim();
+
+    Ok(match is_jwt(token) {
+        true => Credentials::Jwt(token.to_string()),
+        false => Credentials::ApiKey(token.to_string()),
+    })
+}
+
+fn resolve_caller(
+    database: &mut Database,
+    config: &AuthConfig,
+    credentials: Credentials,
+) -> Result<Caller, ApiError> {
+    match credentials {
+        Credentials::ApiKey(api_key) => {
+            let api_key = database
+                .use_api_key(&hash_api_key(&api_key))?
+                .ok_or_else(|| ApiError::unauthorized("Invalid API key"))?;
+            Ok(Caller {
+                tenant_id: api_key.tenant_id,
+                user_id: api_key.user_id,
+                api_key_id: Some(api_key.id),
+            })
+        }
+        Credentials::Jwt(token) => {
+            let secret = config
+                .jwt_secret
+                .as_ref()
+                .ok_or_else(|| ApiError::unauthorized("JWT authentication isn't enabled"))?;
+            let claims = decode_jwt(&token, secret).map_err(ApiError::unauthorized)?;
+
+            // the user must still exist, and belong to the tenant the token claims
+            match database.get_tenant_user(claims.sub)? {
+                Some(user) if user.tenant_id == claims.tenant_id => Ok(Caller {
+                    tenant_id: user.tenant_id,
+                    user_id: Some(user.id),
+                    api_key_id: None,
+                }),
+                _ => Err(ApiError::unauthorized("Unknown user")),
+            }
+        }
+    }
+}
+
+async fn authenticated_caller(req: &ServiceRequest) -> Result<Caller, ApiError> {
+    let credentials = credentials(req)?;
+    let pool = req
+        .app_data::<web::Data<DatabasePool>>()
+        .cloned()
+        .ok_or_else(|| ApiError::from(failure::format_err!("Database pool isn't configured")))?;
+    let config = req
+        .app_data::<web::Data<AuthConfig>>()
+        .cloned()
+        .ok_or_else(|| ApiError::from(failure::format_err!("Auth isn't configured")))?;
+
+    web::block(move || {
+        let mut database = Database::from_pool(&pool)?;
+        resolve_caller(&mut database, &config, credentials)
+    })
+    .await
+    .map_err(|e| failure::format_err!("Blocking database task failed: {}", e))?
+}
+
+// Rejects requests without valid credentials, and records the caller of the rest for
+// TenantPool to scope their queries with
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if !PUBLIC_PATHS.contains(&req.path()) {
+        match authenticated_caller(&req).await {
+            Ok(caller) => {
+                req.extensions_mut().insert(caller);
+            }
+            Err(e) => return Ok(req.error_response(e).map_into_right_body()),
+        }
+    }
+
+    Ok(next.call(req).await?.map_into_left_body())
+}
+
+// The database pool, with the connections it gives out scoped to the caller's tenant
+#[derive(Clone)]
+pub struct TenantPool {
+    pool: web::Data<DatabasePool>,
+    pub caller: Caller,
+}
+
+impl TenantPool {
+    // Blocks until a connection is free, so should be called off the async executor
+    pub fn database(&self) -> Result<Database, failure::Error> {
+        Ok(Database::from_pool(&self.pool)?.for_tenant(self.caller.tenant_id))
+    }
+}
+
+impl FromRequest for TenantPool {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, ApiError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let caller = req.extensions().get::<Caller>().copied();
+        let pool = req.app_data::<web::Data<DatabasePool>>().cloned();
+        ready(match (caller, pool) {
+            (Some(caller), Some(pool)) => Ok(TenantPool { pool, caller }),
+            (None, _) => Err(ApiError::unauthorized("Missing credentials")),
+            (_, None) => Err(failure::format_err!("Database pool isn't configured").into()),
+        })
+    }
+}
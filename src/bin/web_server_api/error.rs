@@ -0,0 +1,99 @@
+use std::fmt;
+
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use log::warn;
+use serde::Serialize;
+use Company_Investigation::postgres::{Conflict, NotFound};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // the path, query or body couldn't be parsed, or failed validation
+    InvalidRequest,
+    // missing, invalid or expired credentials
+    Unauthorized,
+    NotFound,
+    // the resource is in a state the request can't be applied to
+    Conflict,
+    Internal,
+}
+
+impl ErrorCode {
+    fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// Every v1 error is returned as {"error": {"code": ..., "message": ...}}
+#[derive(Serialize, Debug)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a ApiError,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_request(message: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message.to_string())
+    }
+
+    pub fn unauthorized(message: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::Unauthorized, message.to_string())
+    }
+
+    pub fn not_found(message: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::NotFound, message.to_string())
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody { error: self })
+    }
+}
+
+// Missing records become 404s and conflicting changes 409s, anything else is logged and
+// hidden behind a 500
+impl From<failure::Error> for ApiError {
+    fn from(e: failure::Error) -> Self {
+        if let Some(not_found) = e.downcast_ref::<NotFound>() {
+            return ApiError::not_found(not_found);
+        }
+        if let Some(conflict) = e.downcast_ref::<Conflict>() {
+            return ApiError::new(ErrorCode::Conflict, conflict.to_string());
+        }
+        if let Some(diesel::result::Error::NotFound) = e.downcast_ref::<diesel::result::Error>() {
+            return ApiError::not_found("Not found");
+        }
+
+        warn!("Request failed: {}", e);
+        ApiError::new(ErrorCode::Internal, "Internal server error")
+    }
+}
@@ -0,0 +1,935 @@
+use std::collections::HashMap;
+
+use actix_cors::Cors;
+use chrono::{NaiveDate, NaiveDateTime};
+use dotenv::dotenv;
+
+use actix_web::{
+    get,
+    http::header::{self, HeaderName},
+    middleware, post, web, App, HttpResponse, HttpServer, Responder,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use Company_Investigation::{
+    check_query::ChecksQuery,
+    check_response::get_entity_response,
+    graph::{
+        check_graph::CheckGraph,
+        export::ExportFormat,
+        history::{timeline, TimelineEvent},
+        query::{neighbourhood, shortest_paths, RelationshipFilter, MAX_PATH_DEPTH},
+    },
+    jobs::{
+        batch_jobs::prepare_batch,
+        exposure::PropagationPolicy,
+        jobs::{Job, JobKind},
+        relation_jobs::{prepare_check, TraversalPolicy},
+        risk_jobs::{GlobalRiskJob, RiskJob, RiskJobScope},
+    },
+    models::{
+        Batch, BatchRow, Check, CheckReport, CheckStatus, Checkkind, Flagkind, Priority,
+        Relationshipkind, Updatekind,
+    },
+    postgres::Database,
+    pulsar::{record_job, PulsarClient, PulsarProducer},
+    report::{content_hash, ReportFormat, ScreeningReport},
+    workers::{
+        batch_worker::BATCH_TOPIC, entity_relation_worker::ENTITY_RELATION_TOPIC,
+        risk_worker::RISK_TOPIC,
+    },
+};
+
+mod audit;
+mod auth;
+mod error;
+mod openapi;
+mod v1;
+
+use crate::{
+    audit::with_target,
+    auth::{AuthConfig, TenantPool},
+};
+
+#[derive(Serialize, Deserialize)]
+struct CheckInfo {
+    check_id: Uuid,
+    kind: Checkkind,
+    // missing until the check's root entity has been recorded
+    entity_number: Option<String>,
+    name: Option<String>,
+    instructed_on: NaiveDateTime,
+    // when it was cancelled for a cancelled check, none while it's paused
+    completed_on: Option<NaiveDateTime>,
+    status: CheckStatus,
+    has_error: bool,
+    entity_count: i64,
+    risk_level: String,
+    risk_score: f64,
+    distinct_flags: Vec<Flagkind>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChecksResponse {
+    checks: Vec<CheckInfo>,
+    // whether any check, not only those on this page, has a failed job
+    has_error: bool,
+    // pass as the cursor to get the next page, missing on the last page
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MonitoredEntityResponse {
+    check_id: Uuid,
+    company_house_id: String,
+    entity_kind: Updatekind,
+    update_last_recieved_at: Option<NaiveDateTime>,
+    monitoring_started_at: NaiveDateTime,
+    monitoring_ended_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MonitoredEntitiesResponse {
+    entities: Vec<MonitoredEntityResponse>,
+}
+
+#[derive(Deserialize)]
+struct StartRelationsCheckParams {
+    relations_depth: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct AsOfParams {
+    // view the check's structure as it stood on this date
+    as_of: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+struct ShortestPathsParams {
+    from: Uuid,
+    to: Uuid,
+    max_depth: Option<usize>,
+    // comma separated relationship kinds, e.g. "officer,shareholder"
+    kinds: Option<String>,
+    active_at: Option<NaiveDate>,
+    across_checks: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct NeighbourhoodParams {
+    hops: Option<usize>,
+    kinds: Option<String>,
+    active_at: Option<NaiveDate>,
+    across_checks: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntityAppearance {
+    check_id: Uuid,
+    entity_id: Uuid,
+    check_started_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntityAppearancesResponse {
+    canonical_entity_id: Uuid,
+    name: Option<String>,
+    appearances: Vec<EntityAppearance>,
+}
+
+#[derive(Serialize)]
+struct CheckReportInfo {
+    report_id: Uuid,
+    format: String,
+    // hex encoded sha256 of the report as stored
+    content_hash: String,
+    generated_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+struct TimelineEventResponse {
+    #[serde(flatten)]
+    event: TimelineEvent,
+    parent_name: Option<String>,
+    child_name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TimelineResponse {
+    check_id: Uuid,
+    events: Vec<TimelineEventResponse>,
+}
+
+fn start_monitoring_check(
+    database: &mut Database,
+    company_house_id: String,
+) -> Result<Uuid, failure::Error> {
+    let check_id = database.insert_check(Checkkind::MonitoredEntity, Priority::Background)?;
+    database.start_monitoring(check_id, company_house_id)?;
+    database.refresh_check_summary(check_id)?;
+    Ok(check_id)
+}
+
+// The check and its jobs are recorded before any are sent, so no connection is held
+// across the sends
+async fn start_relations_check(
+    pool: &TenantPool,
+    pulsar_client: &PulsarClient,
+    company_house_number: String,
+    traversal_policy: TraversalPolicy,
+) -> Result<Uuid, failure::Error> {
+    let (check_id, jobs) = with_database(pool, move |database| {
+        prepare_check(
+            database,
+            company_house_number,
+            traversal_policy,
+            Priority::Interactive,
+        )
+    })
+    .await?;
+
+    let mut producer = pulsar_client
+        .create_producer(ENTITY_RELATION_TOPIC, None, None)
+        .await;
+    for job in jobs {
+        producer.send_job(job).await?;
+    }
+
+    Ok(check_id)
+}
+
+// Resuming sends the jobs parked while the check was paused to their topics again. A job
+// that can't be sent is left parked, and the check can be resumed again to retry it
+async fn resume_check(
+    pool: &TenantPool,
+    pulsar_client: &PulsarClient,
+    check_id: Uuid,
+) -> Result<Check, failure::Error> {
+    let (check, parked_jobs) = with_database(pool, move |database| {
+        let check = database.get_check(check_id)?;
+        let parked_jobs = database.get_parked_jobs(check_id)?;
+        let check = match check.status {
+            // a resume that couldn't send every parked job is being retried
+            CheckStatus::Running if !parked_jobs.is_empty() => check,
+            _ => database.set_check_status(check_id, CheckStatus::Running)?,
+        };
+        Ok((check, parked_jobs))
+    })
+    .await?;
+
+    let mut producers: HashMap<String, PulsarProducer> = HashMap::new();
+    for parked_job in parked_jobs {
+        let job_id = parked_job.job_id;
+        // a worker may have taken the job back while it was being parked
+        if !with_database(pool, move |database| database.unpark_job(&job_id)).await? {
+            continue;
+        }
+        if !producers.contains_key(&parked_job.topic) {
+            let producer = pulsar_client
+                .create_producer(&parked_job.topic, None, None)
+                .await;
+            producers.insert(parked_job.topic.clone(), producer);
+        }
+
+        let job: Job = serde_json::from_value(parked_job.job.clone())?;
+        let producer = producers.get_mut(&parked_job.topic).unwrap();
+        if let Err(e) = producer.send_job(job).await {
+            with_database(pool, move |database| database.park_job(&parked_job)).await?;
+            return Err(e);
+        }
+    }
+
+    Ok(check)
+}
+
+// Rows are started by the batch service, a few at a time
+async fn submit_batch_rows(
+    pool: &TenantPool,
+    pulsar_client: &PulsarClient,
+    batch: Batch,
+    rows: Vec<BatchRow>,
+) -> Result<(), failure::Error> {
+    let jobs = with_database(pool, move |database| prepare_batch(database, &batch, &rows)).await?;
+
+    let mut producer = pulsar_client.create_producer(BATCH_TOPIC, None, None).await;
+    for job in jobs {
+        producer.send_job(job).await?;
+    }
+
+    Ok(())
+}
+
+fn get_checks(
+    database: &mut Database,
+    checks_query: &ChecksQuery,
+) -> Result<ChecksResponse, failure::Error> {
+    let (summaries, next_cursor) = database.query_check_summaries(checks_query)?;
+    let checks = summaries
+        .into_iter()
+        .map(|summary| CheckInfo {
+            check_id: summary.check_id,
+            kind: summary.kind,
+            entity_number: summary.company_house_number,
+            name: summary.name,
+            instructed_on: summary.started_at,
+            completed_on: summary.completed_at,
+            status: summary.status,
+            has_error: summary.has_error,
+            entity_count: summary.entity_count,
+            risk_level: summary.risk_level,
+            risk_score: summary.risk_score,
+            distinct_flags: summary.distinct_flags,
+        })
+        .collect();
+
+    Ok(ChecksResponse {
+        checks,
+        has_error: database.any_check_has_error()?,
+        next_cursor,
+    })
+}
+
+fn get_monitored_entities(
+    database: &mut Database,
+) -> Result<Vec<MonitoredEntityResponse>, failure::Error> {
+    let monitored_entities = database.get_monitored_entities()?;
+    let mut monitored_entities_response: Vec<MonitoredEntityResponse> = Vec::new();
+
+    for entity in monitored_entities {
+        let monitoring_span = database.get_monitoring_span(entity.monitoring_span_id)?;
+        let entity_response = MonitoredEntityResponse {
+            check_id: database.get_check_id(entity.id)?,
+            company_house_id: entity.company_house_id,
+            entity_kind: Updatekind::Company, // TODO
+            update_last_recieved_at: database.get_last_update(entity.id)?,
+            monitoring_started_at: monitoring_span.started_at,
+            monitoring_ended_at: monitoring_span.ended_at,
+        };
+
+        monitored_entities_response.push(entity_response);
+    }
+
+    Ok(monitored_entities_response)
+}
+
+// Every check the same person or company as the given entity has appeared in
+fn get_entity_appearances(
+    database: &mut Database,
+    entity_id: Uuid,
+) -> Result<EntityAppearancesResponse, failure::Error> {
+    let entity = database.get_entity(entity_id)?;
+    let canonical_entity_id = entity
+        .canonical_entity_id
+        .ok_or_else(|| failure::format_err!("Entity {} hasn't been resolved", entity_id))?;
+    let canonical_entity = database.get_canonical_entity(&canonical_entity_id)?;
+
+    let appearances = database
+        .get_canonical_entity_appearances(&canonical_entity_id)?
+        .into_iter()
+        .map(|(check, entity_id)| EntityAppearance {
+            check_id: check.id,
+            entity_id,
+            check_started_at: check.started_at,
+        })
+        .collect();
+
+    Ok(EntityAppearancesResponse {
+        canonical_entity_id,
+        name: canonical_entity.name,
+        appearances,
+    })
+}
+
+fn export_check(
+    database: &mut Database,
+    check_id: Uuid,
+    format: ExportFormat,
+    as_of: Option<NaiveDate>,
+) -> Result<String, failure::Error> {
+    database.get_check(check_id)?;
+    let mut graph = CheckGraph::load(database, check_id)?;
+    if let Some(date) = as_of {
+        graph = graph.as_of(date);
+    }
+
+    Ok(format.export(&graph))
+}
+
+// Stores the policy for the check and recomputes its exposures with it
+async fn propagate_exposure(
+    pool: &TenantPool,
+    pulsar_client: &PulsarClient,
+    check_id: Uuid,
+    propagation_policy: PropagationPolicy,
+) -> Result<(), failure::Error> {
+    let job = with_database(pool, move |database| {
+        database.get_check(check_id)?;
+        database.upsert_propagation_policy(check_id, serde_json::to_value(&propagation_policy)?)?;
+
+        let job_kind = JobKind::RiskJob(RiskJob {
+            scope: RiskJobScope::Global(GlobalRiskJob::Exposure { check_id }),
+        });
+        record_job(database, Some(check_id), job_kind)
+    })
+    .await?;
+
+    let mut producer = pulsar_client.create_producer(RISK_TOPIC, None, None).await;
+    producer.send_job(job).await
+}
+
+fn generate_report(
+    database: &mut Database,
+    check_id: Uuid,
+    format: ReportFormat,
+) -> Result<CheckReportInfo, failure::Error> {
+    database.get_check(check_id)?;
+    let report = ScreeningReport::load(database, check_id)?;
+    let content = format.render(&report);
+
+    let check_report = CheckReport {
+        id: Uuid::new_v4(),
+        check_id,
+        format: format.as_str().to_string(),
+        content_hash: content_hash(&content),
+        content,
+        generated_at: report.generated_at,
+    };
+    database.insert_check_report(&check_report)?;
+
+    Ok(CheckReportInfo {
+        report_id: check_report.id,
+        format: check_report.format,
+        content_hash: check_report.content_hash,
+        generated_at: check_report.generated_at,
+    })
+}
+
+fn get_check_reports(
+    database: &mut Database,
+    check_id: Uuid,
+) -> Result<Vec<CheckReportInfo>, failure::Error> {
+    database.get_check(check_id)?;
+    Ok(database
+        .get_check_reports(&check_id)?
+        .into_iter()
+        .map(
+            |(report_id, format, content_hash, generated_at)| CheckReportInfo {
+                report_id,
+                format,
+                content_hash,
+                generated_at,
+            },
+        )
+        .collect())
+}
+
+fn get_check_timeline(
+    database: &mut Database,
+    check_id: Uuid,
+) -> Result<TimelineResponse, failure::Error> {
+    database.get_check(check_id)?;
+    let names: HashMap<Uuid, Option<String>> = database
+        .get_entities(check_id)?
+        .into_iter()
+        .map(|entity| (entity.id, entity.name))
+        .collect();
+    let relationships = database.get_check_relationships(&check_id)?;
+
+    let events = timeline(&relationships)
+        .into_iter()
+        .map(|event| TimelineEventResponse {
+            parent_name: names.get(&event.relationship.parent_id).cloned().flatten(),
+            child_name: names.get(&event.relationship.child_id).cloned().flatten(),
+            event,
+        })
+        .collect();
+
+    Ok(TimelineResponse { check_id, events })
+}
+
+fn relationship_filter(
+    kinds: &Option<String>,
+    active_at: Option<NaiveDate>,
+    across_checks: Option<bool>,
+) -> Result<RelationshipFilter, failure::Error> {
+    let kinds = match kinds {
+        Some(kinds) => Some(
+            kinds
+                .split(',')
+                .map(|kind| kind.trim().parse())
+                .collect::<Result<Vec<Relationshipkind>, failure::Error>>()?,
+        ),
+        None => None,
+    };
+
+    Ok(RelationshipFilter {
+        kinds,
+        active_at,
+        across_checks: across_checks.unwrap_or(false),
+    })
+}
+
+fn cancel_monitoring_entity(database: &mut Database, check_id: Uuid) -> Result<(), failure::Error> {
+    database.cancel_monitoring(check_id)
+}
+
+fn export_response(check_id: Uuid, format: ExportFormat, export: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"check_{}.{}\"",
+                check_id,
+                format.file_extension()
+            ),
+        ))
+        .body(export)
+}
+
+// Serves the report exactly as stored, along with the hash it was stored with
+fn report_response(check_report: CheckReport) -> HttpResponse {
+    let content_type = match check_report.format.as_str() {
+        "pdf" => ReportFormat::Pdf.content_type(),
+        _ => ReportFormat::Html.content_type(),
+    };
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("X-Content-Hash", check_report.content_hash))
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "inline; filename=\"report_{}.{}\"",
+                check_report.id, check_report.format
+            ),
+        ))
+        .body(check_report.content)
+}
+
+// Runs blocking diesel queries on the blocking thread pool, with a connection borrowed
+// from the pool for their duration
+async fn with_database<T, F>(pool: &TenantPool, f: F) -> Result<T, failure::Error>
+where
+    F: FnOnce(&mut Database) -> Result<T, failure::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    web::block(move || {
+        let mut database = pool.database()?;
+        f(&mut database)
+    })
+    .await
+    .map_err(|e| failure::format_err!("Blocking database task failed: {}", e))?
+}
+
+#[post("/start_check/{company_house_number}")]
+async fn start_check_endpoint(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    path: web::Path<String>,
+    info: Option<web::Query<StartRelationsCheckParams>>,
+    traversal_policy: Option<web::Json<TraversalPolicy>>,
+) -> impl Responder {
+    let company_house_number = path.into_inner();
+
+    let mut traversal_policy = match traversal_policy {
+        Some(traversal_policy) => traversal_policy.into_inner(),
+        None => TraversalPolicy::default(),
+    };
+    // the query parameter takes precedence over the policy's depth
+    if let Some(relations_depth) = info.and_then(|info| info.relations_depth) {
+        traversal_policy.max_depth = relations_depth;
+    }
+
+    if let Err(e) = traversal_policy.validate() {
+        return HttpResponse::BadRequest().json(format!("Invalid traversal policy: {}", e));
+    }
+
+    match start_relations_check(
+        &pool,
+        &pulsar_client,
+        company_house_number.clone(),
+        traversal_policy,
+    )
+    .await
+    {
+        Ok(check_id) => with_target(HttpResponse::Ok().json(check_id), check_id),
+        Err(e) => {
+            warn!("Failed to get relations: {}", e);
+            HttpResponse::InternalServerError().json(format!(
+                "Failed to start relation check for entity with number {}",
+                company_house_number
+            ))
+        }
+    }
+}
+
+#[get("/get_check/{check_id}")]
+async fn get_check_endpoint(
+    pool: TenantPool,
+    params: web::Path<Uuid>,
+    query: web::Query<AsOfParams>,
+) -> impl Responder {
+    let check_id = params.into_inner();
+    let as_of = query.as_of;
+    let entity_response = with_database(&pool, move |database| {
+        get_entity_response(database, check_id, as_of)
+    })
+    .await;
+    match entity_response {
+        Ok(entity_response) => HttpResponse::Ok().json(entity_response),
+        Err(e) => {
+            warn!("Failed to get relations: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to get relations for check {}", check_id))
+        }
+    }
+}
+
+#[get("/export_check/{check_id}/{format}")]
+async fn export_check_endpoint(
+    pool: TenantPool,
+    params: web::Path<(Uuid, ExportFormat)>,
+    query: web::Query<AsOfParams>,
+) -> impl Responder {
+    let (check_id, format) = params.into_inner();
+    let as_of = query.as_of;
+    let export = with_database(&pool, move |database| {
+        export_check(database, check_id, format, as_of)
+    })
+    .await;
+    match export {
+        Ok(export) => export_response(check_id, format, export),
+        Err(e) => {
+            warn!("Failed to export check: {}", e);
+            HttpResponse::InternalServerError().json(format!("Failed to export check {}", check_id))
+        }
+    }
+}
+
+#[post("/generate_report/{check_id}/{format}")]
+async fn generate_report_endpoint(
+    pool: TenantPool,
+    params: web::Path<(Uuid, ReportFormat)>,
+) -> impl Responder {
+    let (check_id, format) = params.into_inner();
+    let report_info = with_database(&pool, move |database| {
+        generate_report(database, check_id, format)
+    })
+    .await;
+    match report_info {
+        Ok(report_info) => {
+            let report_id = report_info.report_id;
+            with_target(HttpResponse::Ok().json(report_info), report_id)
+        }
+        Err(e) => {
+            warn!("Failed to generate report: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to generate report for check {}", check_id))
+        }
+    }
+}
+
+#[get("/get_check_reports/{check_id}")]
+async fn get_check_reports_endpoint(pool: TenantPool, params: web::Path<Uuid>) -> impl Responder {
+    let check_id = params.into_inner();
+    match with_database(&pool, move |database| get_check_reports(database, check_id)).await {
+        Ok(reports) => HttpResponse::Ok().json(reports),
+        Err(e) => {
+            warn!("Failed to get reports: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to get reports for check {}", check_id))
+        }
+    }
+}
+
+#[get("/get_report/{report_id}")]
+async fn get_report_endpoint(pool: TenantPool, params: web::Path<Uuid>) -> impl Responder {
+    let report_id = params.into_inner();
+    let check_report =
+        with_database(&pool, move |database| database.get_check_report(&report_id)).await;
+    match check_report {
+        Ok(check_report) => report_response(check_report),
+        Err(e) => {
+            warn!("Failed to get report: {}", e);
+            HttpResponse::NotFound().json(format!("No report found with id {}", report_id))
+        }
+    }
+}
+
+#[post("/propagate_exposure/{check_id}")]
+async fn propagate_exposure_endpoint(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    path: web::Path<Uuid>,
+    propagation_policy: Option<web::Json<PropagationPolicy>>,
+) -> impl Responder {
+    let check_id = path.into_inner();
+    let propagation_policy = match propagation_policy {
+        Some(propagation_policy) => propagation_policy.into_inner(),
+        None => PropagationPolicy::default(),
+    };
+
+    if let Err(e) = propagation_policy.validate() {
+        return HttpResponse::BadRequest().json(format!("Invalid propagation policy: {}", e));
+    }
+
+    match propagate_exposure(&pool, &pulsar_client, check_id, propagation_policy).await {
+        Ok(()) => HttpResponse::Ok().json(check_id),
+        Err(e) => {
+            warn!("Failed to propagate exposure: {}", e);
+            HttpResponse::InternalServerError().json(format!(
+                "Failed to propagate exposure for check {}",
+                check_id
+            ))
+        }
+    }
+}
+
+#[get("/get_check_timeline/{check_id}")]
+async fn get_check_timeline_endpoint(pool: TenantPool, params: web::Path<Uuid>) -> impl Responder {
+    let check_id = params.into_inner();
+    match with_database(&pool, move |database| {
+        get_check_timeline(database, check_id)
+    })
+    .await
+    {
+        Ok(timeline) => HttpResponse::Ok().json(timeline),
+        Err(e) => {
+            warn!("Failed to get timeline: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to get timeline for check {}", check_id))
+        }
+    }
+}
+
+#[get("/shortest_paths")]
+async fn shortest_paths_endpoint(
+    pool: TenantPool,
+    params: web::Query<ShortestPathsParams>,
+) -> impl Responder {
+    let filter = match relationship_filter(&params.kinds, params.active_at, params.across_checks) {
+        Ok(filter) => filter,
+        Err(e) => return HttpResponse::BadRequest().json(format!("{}", e)),
+    };
+
+    let (from, to) = (params.from, params.to);
+    let max_depth = params.max_depth.unwrap_or(MAX_PATH_DEPTH);
+    let paths = with_database(&pool, move |database| {
+        database.get_entity(from)?;
+        database.get_entity(to)?;
+        shortest_paths(database, from, to, max_depth, &filter)
+    })
+    .await;
+    match paths {
+        Ok(paths) => HttpResponse::Ok().json(paths),
+        Err(e) => {
+            warn!("Failed to find shortest paths: {}", e);
+            HttpResponse::InternalServerError().json(format!(
+                "Failed to find paths between {} and {}",
+                params.from, params.to
+            ))
+        }
+    }
+}
+
+#[get("/neighbourhood/{entity_id}")]
+async fn neighbourhood_endpoint(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    params: web::Query<NeighbourhoodParams>,
+) -> impl Responder {
+    let entity_id = path.into_inner();
+    let filter = match relationship_filter(&params.kinds, params.active_at, params.across_checks) {
+        Ok(filter) => filter,
+        Err(e) => return HttpResponse::BadRequest().json(format!("{}", e)),
+    };
+
+    let hops = params.hops.unwrap_or(1);
+    let result = with_database(&pool, move |database| {
+        database.get_entity(entity_id)?;
+        neighbourhood(database, entity_id, hops, &filter)
+    })
+    .await;
+    match result {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => {
+            warn!("Failed to get neighbourhood: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to get neighbourhood of {}", entity_id))
+        }
+    }
+}
+
+#[get("/get_checks")]
+async fn get_checks_endpoint(pool: TenantPool, query: web::Query<ChecksQuery>) -> impl Responder {
+    let checks_query = query.into_inner();
+    if let Err(e) = checks_query.decode_cursor() {
+        return HttpResponse::BadRequest().json(format!("{}", e));
+    }
+
+    match with_database(&pool, move |database| get_checks(database, &checks_query)).await {
+        Ok(checks_response) => HttpResponse::Ok().json(checks_response),
+        Err(e) => {
+            warn!("Failed to get checks: {}", e);
+            HttpResponse::InternalServerError().json(format!("Failed to get checks"))
+        }
+    }
+}
+
+#[get("/get_entity_checks/{entity_id}")]
+async fn get_entity_checks_endpoint(pool: TenantPool, params: web::Path<Uuid>) -> impl Responder {
+    let entity_id = params.into_inner();
+    match with_database(&pool, move |database| {
+        get_entity_appearances(database, entity_id)
+    })
+    .await
+    {
+        Ok(appearances) => HttpResponse::Ok().json(appearances),
+        Err(e) => {
+            warn!("Failed to get entity appearances: {}", e);
+            HttpResponse::InternalServerError()
+                .json(format!("Failed to get checks for entity {}", entity_id))
+        }
+    }
+}
+
+#[post("/start_monitoring_entity_endpoint/{company_house_id}")]
+async fn start_monitoring_entity_endpoint(
+    pool: TenantPool,
+    path: web::Path<String>,
+) -> impl Responder {
+    let company_house_id = path.into_inner();
+    let monitored_company_house_id = company_house_id.clone();
+    let check_id = with_database(&pool, move |database| {
+        start_monitoring_check(database, monitored_company_house_id)
+    })
+    .await;
+    match check_id {
+        Ok(check_id) => with_target(HttpResponse::Ok().json(check_id), check_id),
+        Err(e) => {
+            warn!("Failed to start monitoring company: {}", e);
+            HttpResponse::InternalServerError().json(format!(
+                "Failed to start monitoring company with numer: {}",
+                company_house_id
+            ))
+        }
+    }
+}
+
+#[get("/get_monitored_entities")]
+async fn get_monitored_entities_endpoint(pool: TenantPool) -> impl Responder {
+    match with_database(&pool, get_monitored_entities).await {
+        Ok(monitored_entities) => HttpResponse::Ok().json(MonitoredEntitiesResponse {
+            entities: monitored_entities,
+        }),
+        Err(e) => {
+            warn!("Failed to get monitored entities: {}", e);
+            HttpResponse::InternalServerError().json("Failed to get monitored entities")
+        }
+    }
+}
+
+#[post("/cancel_monitoring_entity/{check_id}")]
+async fn cancel_monitoring_entity_endpoint(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let check_id = path.into_inner();
+    match with_database(&pool, move |database| {
+        cancel_monitoring_entity(database, check_id)
+    })
+    .await
+    {
+        Ok(_) => HttpResponse::Ok(),
+        Err(_) => {
+            warn!("Failed to cancel monitoring for check id: {:?}", check_id);
+            HttpResponse::InternalServerError()
+        }
+    }
+}
+
+// CORS_ALLOWED_ORIGINS is a comma separated list of origins, or * for any origin. When it
+// isn't set only same origin requests are allowed
+fn cors(allowed_origins: Option<&str>) -> Cors {
+    let cors = Cors::default()
+        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+        .allowed_headers(vec![
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static("x-api-key"),
+        ])
+        .expose_headers(vec![
+            header::LOCATION,
+            header::CONTENT_DISPOSITION,
+            HeaderName::from_static("x-content-hash"),
+        ])
+        .max_age(3600);
+
+    match allowed_origins.map(str::trim) {
+        Some("*") => cors.allow_any_origin().send_wildcard(),
+        Some(allowed_origins) => allowed_origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+        None => cors,
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenv().ok();
+    env_logger::init();
+
+    let pool = web::Data::new(Database::create_pool().expect("Should be able to create db pool"));
+    let pulsar_client = web::Data::new(PulsarClient::new().await);
+    let auth_config = web::Data::new(AuthConfig::from_env());
+    let allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS").ok();
+
+    // checks without a summary aren't listed, they're summarised in the background rather
+    // than by the first request to list them
+    let summary_pool = pool.clone();
+    std::thread::spawn(move || {
+        match Database::from_pool(&summary_pool)
+            .and_then(|mut database| database.summarise_unsummarised_checks())
+        {
+            Ok(summarised) => info!("Summarised {} checks", summarised),
+            Err(e) => warn!("Failed to summarise checks: {}", e),
+        }
+    });
+
+    HttpServer::new(move || {
+        App::new()
+            // the last middleware wrapped runs first, so preflight requests are answered
+            // before they're authenticated, and calls are only audited once they are
+            .wrap(middleware::from_fn(audit::record_mutations))
+            .wrap(middleware::from_fn(auth::authenticate))
+            .wrap(cors(allowed_origins.as_deref()))
+            .app_data(pool.clone())
+            .app_data(auth_config.clone())
+            .app_data(pulsar_client.clone())
+            .configure(v1::configure)
+            // the unversioned endpoints are kept for existing clients, new ones are added to v1
+            .service(start_check_endpoint)
+            .service(get_check_endpoint)
+            .service(get_checks_endpoint)
+            .service(export_check_endpoint)
+            .service(get_check_timeline_endpoint)
+            .service(propagate_exposure_endpoint)
+            .service(generate_report_endpoint)
+            .service(get_check_reports_endpoint)
+            .service(get_report_endpoint)
+            .service(shortest_paths_endpoint)
+            .service(neighbourhood_endpoint)
+            .service(get_entity_checks_endpoint)
+            .service(start_monitoring_entity_endpoint)
+            .service(get_monitored_entities_endpoint)
+            // .service(get_monitored_entity_endpoint)
+            .service(cancel_monitoring_entity_endpoint)
+    })
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
+}
@@ -0,0 +1,956 @@
+use serde_json::{json, Value};
+
+// Built by hand to match the routes in v1, ROUTES is checked against it in the tests
+pub fn document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Company Investigation API",
+            "version": "1.0.0",
+            "description": "Relation checks, monitoring and screening reports on companies \
+                            and officers registered with Companies House."
+        },
+        "servers": [{ "url": "/" }],
+        "security": [{ "apiKey": [] }, { "bearer": [] }],
+        "paths": {
+            "/v1/openapi.json": {
+                "get": public(operation(
+                    "getOpenApi",
+                    "This document",
+                    vec![],
+                    ok("OpenAPI document", json!({ "type": "object" })),
+                ))
+            },
+            "/v1/checks": {
+                "post": with_body(
+                    operation(
+                        "createCheck",
+                        "Start a relation check on a company",
+                        vec![],
+                        created("The check was started", schema("CheckCreated")),
+                    ),
+                    schema("CreateCheckRequest"),
+                ),
+                "get": operation(
+                    "listChecks",
+                    "List checks, a page at a time",
+                    vec![
+                        query_param("kind", string_enum(&["EntityRelation", "MonitoredEntity"])),
+                        query_param("started_from", date()),
+                        query_param("started_to", date()),
+                        query_param("completed", json!({ "type": "boolean" })),
+                        query_param("has_error", json!({ "type": "boolean" })),
+                        query_param("risk_level", string_enum(&["Low", "Medium", "High"])),
+                        query_param("flag", schema("Flagkind")),
+                        query_param(
+                            "company",
+                            described(string(), "Root company number, or part of its name"),
+                        ),
+                        query_param(
+                            "sort",
+                            string_enum(&["started_at", "name", "risk_score", "entity_count"]),
+                        ),
+                        query_param("direction", string_enum(&["asc", "desc"])),
+                        query_param("limit", integer(1, 500)),
+                        query_param(
+                            "cursor",
+                            described(string(), "next_cursor of the previous page"),
+                        ),
+                    ],
+                    ok("A page of checks", schema("ChecksResponse")),
+                )
+            },
+            "/v1/checks/{check_id}": {
+                "get": operation(
+                    "getCheck",
+                    "Everything found by a check",
+                    vec![path_param("check_id"), query_param("as_of", date())],
+                    ok("The check", schema("EntityCheckResponse")),
+                )
+            },
+            "/v1/checks/{check_id}/timeline": {
+                "get": operation(
+                    "getCheckTimeline",
+                    "Dated starts and ends of the check's relationships",
+                    vec![path_param("check_id")],
+                    ok("The timeline", schema("TimelineResponse")),
+                )
+            },
+            "/v1/checks/{check_id}/graph/{format}": {
+                "get": operation(
+                    "exportCheckGraph",
+                    "Export the check's graph",
+                    vec![
+                        path_param("check_id"),
+                        json!({
+                            "name": "format",
+                            "in": "path",
+                            "required": true,
+                            "schema": string_enum(&["graphml", "gexf", "cypher"])
+                        }),
+                        query_param("as_of", date()),
+                    ],
+                    json!({
+                        "description": "The exported graph",
+                        "content": { "application/octet-stream": { "schema": binary() } }
+                    }),
+                )
+            },
+            "/v1/checks/{check_id}/propagation-policy": {
+                "put": with_body(
+                    operation(
+                        "setPropagationPolicy",
+                        "Store the check's propagation policy and recompute its exposures",
+                        vec![path_param("check_id")],
+                        response("202", "Exposures will be recomputed", schema("CheckCreated")),
+                    ),
+                    schema("PropagationPolicy"),
+                )
+            },
+            "/v1/checks/{check_id}/reports": {
+                "post": with_body(
+                    operation(
+                        "createReport",
+                        "Generate and store a screening report",
+                        vec![path_param("check_id")],
+                        created("The stored report", schema("CheckReportInfo")),
+                    ),
+                    schema("CreateReportRequest"),
+                ),
+                "get": operation(
+                    "listReports",
+                    "Reports generated for the check, newest first",
+                    vec![path_param("check_id")],
+                    ok("The reports", array(schema("CheckReportInfo"))),
+                )
+            },
+            "/v1/checks/{check_id}/case": {
+                "get": operation(
+                    "getCase",
+                    "The check's case, with its flags, matches and dispositions",
+                    vec![path_param("check_id")],
+                    ok("The case", schema("Case")),
+                ),
+                "patch": with_body(
+                    operation(
+                        "updateCase",
+                        "Change the case's status or assignee",
+                        vec![path_param("check_id")],
+                        ok("The updated case", schema("CaseInfo")),
+                    ),
+                    schema("UpdateCaseRequest"),
+                )
+            },
+            "/v1/checks/{check_id}/dispositions": {
+                "post": with_body(
+                    operation(
+                        "createDisposition",
+                        "Record a verdict on a flag or sanctions match. False positives \
+                         aren't raised again when the same entity is re-screened",
+                        vec![path_param("check_id")],
+                        created("The disposition", schema("Disposition")),
+                    ),
+                    schema("CreateDispositionRequest"),
+                )
+            },
+            "/v1/checks/{check_id}/pause": {
+                "post": with_conflict(operation(
+                    "pauseCheck",
+                    "Stop working on the check's jobs, holding them until it's resumed",
+                    vec![path_param("check_id")],
+                    ok("The paused check", schema("CheckStatusInfo")),
+                ))
+            },
+            "/v1/checks/{check_id}/resume": {
+                "post": with_conflict(operation(
+                    "resumeCheck",
+                    "Carry on with a paused check, queueing the jobs held while it was paused",
+                    vec![path_param("check_id")],
+                    ok("The resumed check", schema("CheckStatusInfo")),
+                ))
+            },
+            "/v1/checks/{check_id}/cancel": {
+                "post": with_conflict(operation(
+                    "cancelCheck",
+                    "Stop the check for good, its remaining jobs are skipped",
+                    vec![path_param("check_id")],
+                    ok("The cancelled check", schema("CheckStatusInfo")),
+                ))
+            },
+            "/v1/reports/{report_id}": {
+                "get": operation(
+                    "getReport",
+                    "A stored report exactly as generated, its hash in X-Content-Hash",
+                    vec![path_param("report_id")],
+                    json!({
+                        "description": "The report",
+                        "content": {
+                            "text/html": { "schema": string() },
+                            "application/pdf": { "schema": binary() }
+                        }
+                    }),
+                )
+            },
+            "/v1/entities/{entity_id}/appearances": {
+                "get": operation(
+                    "getEntityAppearances",
+                    "Every check the same person or company has appeared in",
+                    vec![path_param("entity_id")],
+                    ok("The appearances", schema("EntityAppearancesResponse")),
+                )
+            },
+            "/v1/entities/{entity_id}/neighbourhood": {
+                "get": operation(
+                    "getNeighbourhood",
+                    "Entities within a number of hops of an entity",
+                    {
+                        let mut parameters = vec![
+                            path_param("entity_id"),
+                            query_param("hops", integer(0, 5)),
+                        ];
+                        parameters.extend(relationship_filter_params());
+                        parameters
+                    },
+                    ok("The neighbourhood", schema("Neighbourhood")),
+                )
+            },
+            "/v1/paths": {
+                "get": operation(
+                    "findPaths",
+                    "Shortest paths between two entities",
+                    {
+                        let mut parameters = vec![
+                            required(query_param("from", uuid())),
+                            required(query_param("to", uuid())),
+                            query_param("max_depth", integer(1, 10)),
+                        ];
+                        parameters.extend(relationship_filter_params());
+                        parameters
+                    },
+                    ok("The paths", array(schema("GraphPath"))),
+                )
+            },
+            "/v1/monitors": {
+                "post": with_body(
+                    operation(
+                        "createMonitor",
+                        "Start monitoring a company",
+                        vec![],
+                        created("The monitoring check", schema("CheckCreated")),
+                    ),
+                    schema("CreateMonitorRequest"),
+                ),
+                "get": operation(
+                    "listMonitors",
+                    "Monitored companies",
+                    vec![],
+                    ok("The monitored companies", schema("MonitoredEntitiesResponse")),
+                )
+            },
+            "/v1/monitors/{check_id}": {
+                "delete": operation(
+                    "cancelMonitor",
+                    "Stop monitoring a company",
+                    vec![path_param("check_id")],
+                    json!({ "description": "Monitoring was stopped" }),
+                )
+            },
+            "/v1/batches": {
+                "post": with_csv_body(
+                    operation(
+                        "createBatch",
+                        "Submit a CSV of companies to check, started a few at a time",
+                        vec![query_param(
+                            "name",
+                            described(string(), "e.g. the CSV's file name"),
+                        )],
+                        created("The batch", schema("BatchCreated")),
+                    ),
+                    "A header row, then a row per company with either company_number, or name \
+                     and postcode. depth and policy, a traversal policy as JSON, are optional",
+                )
+            },
+            "/v1/batches/{batch_id}": {
+                "get": operation(
+                    "getBatch",
+                    "The batch's progress, and the status of each row",
+                    vec![path_param("batch_id")],
+                    ok("The batch", schema("BatchProgress")),
+                )
+            },
+            "/v1/audit-log": {
+                "get": operation(
+                    "getAuditLog",
+                    "Mutating calls made by the tenant, newest first",
+                    vec![
+                        query_param(
+                            "target_id",
+                            described(uuid(), "The check, report or entity acted on"),
+                        ),
+                        query_param("limit", integer(1, 1000)),
+                    ],
+                    ok("The audit log", schema("AuditLogResponse")),
+                )
+            },
+            "/v1/source-fetches/{source_fetch_id}": {
+                "get": operation(
+                    "getSourceFetch",
+                    "Where recorded data came from, with the payload as the source returned it",
+                    vec![path_param("source_fetch_id")],
+                    ok("The source fetch", schema("SourceFetch")),
+                )
+            }
+        },
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "An API key, or an HS256 JWT signed with JWT_SECRET"
+                }
+            },
+            "responses": {
+                "Error": {
+                    "description": "The request failed",
+                    "content": { "application/json": { "schema": schema("Error") } }
+                }
+            }
+        }
+    })
+}
+
+fn schema(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn string_enum(values: &[&str]) -> Value {
+    json!({ "type": "string", "enum": values })
+}
+
+fn integer(minimum: usize, maximum: usize) -> Value {
+    json!({ "type": "integer", "minimum": minimum, "maximum": maximum })
+}
+
+fn binary() -> Value {
+    json!({ "type": "string", "format": "binary" })
+}
+
+fn described(mut value: Value, description: &str) -> Value {
+    value["description"] = json!(description);
+    value
+}
+
+fn uuid() -> Value {
+    json!({ "type": "string", "format": "uuid" })
+}
+
+fn date() -> Value {
+    json!({ "type": "string", "format": "date" })
+}
+
+fn date_time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn nullable(mut value: Value) -> Value {
+    value["nullable"] = json!(true);
+    value
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn path_param(name: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": uuid() })
+}
+
+fn query_param(name: &str, schema: Value) -> Value {
+    json!({ "name": name, "in": "query", "required": false, "schema": schema })
+}
+
+fn required(mut parameter: Value) -> Value {
+    parameter["required"] = json!(true);
+    parameter
+}
+
+fn relationship_filter_params() -> Vec<Value> {
+    vec![
+        query_param(
+            "kinds",
+            described(
+                string(),
+                "Comma separated relationship kinds, e.g. officer,shareholder",
+            ),
+        ),
+        query_param("active_at", date()),
+        query_param(
+            "across_checks",
+            json!({ "type": "boolean", "default": false }),
+        ),
+    ]
+}
+
+fn response(status: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "status": status,
+        "description": description,
+        "content": { "application/json": { "schema": schema } }
+    })
+}
+
+fn ok(description: &str, schema: Value) -> Value {
+    response("200", description, schema)
+}
+
+fn created(description: &str, schema: Value) -> Value {
+    response("201", description, schema)
+}
+
+// Every operation can fail with the error body
+fn operation(
+    operation_id: &str,
+    summary: &str,
+    parameters: Vec<Value>,
+    mut success: Value,
+) -> Value {
+    let status = success
+        .as_object_mut()
+        .and_then(|success| success.remove("status"))
+        .and_then(|status| status.as_str().map(str::to_string))
+        .unwrap_or_else(|| "200".to_string());
+    let error = json!({ "$ref": "#/components/responses/Error" });
+    let mut responses = json!({ "400": error, "401": error, "404": error, "500": error });
+    responses[status] = success;
+
+    json!({
+        "operationId": operation_id,
+        "summary": summary,
+        "parameters": parameters,
+        "responses": responses
+    })
+}
+
+// Fails with 409 when the resource is in the wrong state for it
+fn with_conflict(mut operation: Value) -> Value {
+    operation["responses"]["409"] = json!({ "$ref": "#/components/responses/Error" });
+    operation
+}
+
+// Served without credentials
+fn public(mut operation: Value) -> Value {
+    operation["security"] = json!([]);
+    if let Some(responses) = operation["responses"].as_object_mut() {
+        responses.remove("401");
+    }
+    operation
+}
+
+fn with_body(mut operation: Value, schema: Value) -> Value {
+    operation["requestBody"] = json!({
+        "required": true,
+        "content": { "application/json": { "schema": schema } }
+    });
+    operation
+}
+
+fn with_csv_body(mut operation: Value, description: &str) -> Value {
+    operation["requestBody"] = json!({
+        "required": true,
+        "description": description,
+        "content": { "text/csv": { "schema": string() } }
+    });
+    operation
+}
+
+// Built in parts, a single json! call is deeper than the macro recursion limit allows
+fn schemas() -> Value {
+    let mut schemas = json!({
+        "Error": {
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": {
+                    "type": "object",
+                    "required": ["code", "message"],
+                    "properties": {
+                        "code": string_enum(&[
+                            "invalid_request",
+                            "unauthorized",
+                            "not_found",
+                            "conflict",
+                            "internal",
+                        ]),
+                        "message": { "type": "string" }
+                    }
+                }
+            }
+        },
+        "Flagkind": {
+            "type": "string",
+            "description": "Kind of flag raised on an entity, e.g. SanctionedEntity"
+        },
+        "Relationshipkind": {
+            "type": "string",
+            "enum": ["Shareholder", "Officer", "ChargeHolder"]
+        },
+        "OwnershipBand": {
+            "type": "string",
+            "description": "Band of shares or voting rights held, e.g. FiftyToSeventyFive"
+        },
+        "RelationJobKind": {
+            "type": "string",
+            "description": "Kind of relation followed by a check, e.g. Shareholders"
+        },
+        "TraversalPolicy": {
+            "type": "object",
+            "properties": {
+                "max_depth": { "type": "integer", "minimum": 0 },
+                "company_relations": array(schema("RelationJobKind")),
+                "individual_relations": array(schema("RelationJobKind")),
+                "include_resigned_officers": { "type": "boolean" },
+                "include_ceased_shareholders": { "type": "boolean" },
+                "min_ownership_band": nullable(schema("OwnershipBand")),
+                "max_depth_per_kind": {
+                    "type": "object",
+                    "additionalProperties": { "type": "integer" }
+                }
+            }
+        },
+        "PropagationPolicy": {
+            "type": "object",
+            "properties": {
+                "origin_flags": array(schema("Flagkind")),
+                "kind_weights": { "type": "object", "additionalProperties": { "type": "number" } },
+                "hop_decay": { "type": "number", "minimum": 0, "maximum": 1 },
+                "scale_by_ownership": { "type": "boolean" },
+                "min_score": { "type": "number" },
+                "max_hops": { "type": "integer", "maximum": 10 }
+            }
+        },
+        "CreateCheckRequest": {
+            "type": "object",
+            "required": ["company_number"],
+            "properties": {
+                "company_number": { "type": "string" },
+                "traversal_policy": schema("TraversalPolicy")
+            }
+        },
+        "CreateReportRequest": {
+            "type": "object",
+            "required": ["format"],
+            "properties": { "format": { "type": "string", "enum": ["html", "pdf"] } }
+        },
+        "CreateMonitorRequest": {
+            "type": "object",
+            "required": ["company_number"],
+            "properties": { "company_number": { "type": "string" } }
+        },
+        "CheckCreated": {
+            "type": "object",
+            "required": ["check_id"],
+            "properties": { "check_id": uuid() }
+        },
+        "CheckInfo": {
+            "type": "object",
+            "properties": {
+                "check_id": uuid(),
+                "kind": { "type": "string", "enum": ["EntityRelation", "MonitoredEntity"] },
+                "entity_number": nullable(string()),
+                "name": nullable(string()),
+                "instructed_on": date_time(),
+                "completed_on": described(
+                    nullable(date_time()),
+                    "When the check was cancelled, for a cancelled check. Null while paused"
+                ),
+                "status": schema("CheckStatus"),
+                "has_error": { "type": "boolean" },
+                "entity_count": { "type": "integer" },
+                "risk_level": { "type": "string", "enum": ["Low", "Medium", "High"] },
+                "risk_score": { "type": "number" },
+                "distinct_flags": array(schema("Flagkind"))
+            }
+        },
+        "ChecksResponse": {
+            "type": "object",
+            "properties": {
+                "checks": array(schema("CheckInfo")),
+                "has_error": { "type": "boolean" },
+                "next_cursor": nullable(string())
+            }
+        },
+        "Entity": {
+            "type": "object",
+            "properties": {
+                "id": uuid(),
+                "company_house_number": { "type": "string" },
+                "name": nullable(string()),
+                "kind": { "type": "string", "enum": ["Company", "Individual"] },
+                "country": nullable(string()),
+                "postal_code": nullable(string()),
+                "date_of_origin": nullable(date()),
+                "is_root": { "type": "boolean" },
+                "officer_id": nullable(string()),
+                "date_of_origin_day_known": { "type": "boolean" },
+                "canonical_entity_id": nullable(uuid()),
+                "source_fetch_id": nullable(described(
+                    uuid(),
+                    "The source fetch the entity was recorded from",
+                ))
+            }
+        },
+        "Relation": {
+            "type": "object",
+            "properties": {
+                "entity_id": uuid(),
+                "started_on": nullable(date()),
+                "ended_on": nullable(date())
+            }
+        },
+        "Relationship": {
+            "type": "object",
+            "properties": {
+                "parent_id": uuid(),
+                "child_id": uuid(),
+                "kind": schema("Relationshipkind"),
+                "started_on": nullable(date()),
+                "ended_on": nullable(date()),
+                "ownership_band": nullable(schema("OwnershipBand"))
+            }
+        },
+        "EntityWithRelations": {
+            "type": "object",
+            "properties": {
+                "entity": schema("Entity"),
+                "officers": array(schema("Relation")),
+                "shareholders": array(schema("Relation")),
+                "charge_holders": array(schema("Relation")),
+                "flags": array(schema("Flagkind")),
+                "positions": array(string()),
+                "datasets": array(string()),
+                "profile": nullable(json!({ "type": "object" })),
+                "previous_names": array(json!({ "type": "object" })),
+                "charges": array(json!({ "type": "object" })),
+                "insolvencies": array(json!({ "type": "object" })),
+                "disqualifications": array(json!({ "type": "object" }))
+            }
+        },
+        "BeneficialOwner": {
+            "type": "object",
+            "properties": {
+                "entity_id": uuid(),
+                "name": nullable(string()),
+                "min_ownership": { "type": "number" },
+                "max_ownership": { "type": "number" },
+                "is_ultimate": { "type": "boolean" },
+                "paths": array(array(uuid()))
+            }
+        },
+        "Exposure": {
+            "type": "object",
+            "properties": {
+                "id": uuid(),
+                "check_id": uuid(),
+                "entity_id": uuid(),
+                "origin_entity_id": uuid(),
+                "origin_flag_id": uuid(),
+                "flag_kind": schema("Flagkind"),
+                "path": array(uuid()),
+                "score": { "type": "number" }
+            }
+        },
+        "EntityCheckResponse": {
+            "type": "object",
+            "properties": {
+                "entities": array(schema("EntityWithRelations")),
+                "ultimate_beneficial_owners": array(schema("BeneficialOwner")),
+                "other_beneficial_owners": array(schema("BeneficialOwner")),
+                "exposures": array(schema("Exposure")),
+                "traversal_policy": nullable(schema("TraversalPolicy")),
+                "as_of": nullable(date()),
+                "started_at": date_time(),
+                "completed_at": nullable(date_time()),
+                "status": schema("CheckStatus")
+            }
+        },
+        "TimelineResponse": {
+            "type": "object",
+            "properties": {
+                "check_id": uuid(),
+                "events": array(json!({
+                    "type": "object",
+                    "properties": {
+                        "date": date(),
+                        "change": { "type": "string", "enum": ["started", "ended"] },
+                        "relationship": schema("Relationship"),
+                        "parent_name": nullable(string()),
+                        "child_name": nullable(string())
+                    }
+                }))
+            }
+        },
+        "CheckReportInfo": {
+            "type": "object",
+            "properties": {
+                "report_id": uuid(),
+                "format": { "type": "string", "enum": ["html", "pdf"] },
+                "content_hash": { "type": "string" },
+                "generated_at": date_time()
+            }
+        },
+        "EntityAppearancesResponse": {
+            "type": "object",
+            "properties": {
+                "canonical_entity_id": uuid(),
+                "name": nullable(string()),
+                "appearances": array(json!({
+                    "type": "object",
+                    "properties": {
+                        "check_id": uuid(),
+                        "entity_id": uuid(),
+                        "check_started_at": date_time()
+                    }
+                }))
+            }
+        },
+        "GraphEdge": {
+            "type": "object",
+            "description": "A relationship, or kind same_entity linking the same canonical \
+                            entity in two checks",
+            "required": ["kind"],
+            "properties": {
+                "kind": { "type": "string", "enum": ["relationship", "same_entity"] }
+            },
+            "additionalProperties": true
+        },
+        "GraphPath": {
+            "type": "object",
+            "properties": {
+                "entity_ids": array(uuid()),
+                "edges": array(schema("GraphEdge"))
+            }
+        },
+        "Neighbourhood": {
+            "type": "object",
+            "properties": {
+                "entities": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "edges": array(schema("GraphEdge"))
+            }
+        },
+        "MonitoredEntitiesResponse": {
+            "type": "object",
+            "properties": {
+                "entities": array(json!({
+                    "type": "object",
+                    "properties": {
+                        "check_id": uuid(),
+                        "company_house_id": { "type": "string" },
+                        "entity_kind": { "type": "string" },
+                        "update_last_recieved_at": nullable(date_time()),
+                        "monitoring_started_at": date_time(),
+                        "monitoring_ended_at": nullable(date_time())
+                    }
+                }))
+            }
+        }
+    });
+    schemas["AuditLogResponse"] = json!({
+        "type": "object",
+        "properties": {
+            "entries": array(json!({
+                "type": "object",
+                "properties": {
+                    "id": uuid(),
+                    "tenant_id": uuid(),
+                    "user_id": nullable(uuid()),
+                    "api_key_id": nullable(uuid()),
+                    "method": { "type": "string" },
+                    "path": { "type": "string" },
+                    "target_id": nullable(uuid()),
+                    "status": { "type": "integer" },
+                    "occurred_at": date_time()
+                }
+            }))
+        }
+    });
+    schemas["CheckStatus"] = string_enum(&["running", "paused", "cancelled"]);
+    schemas["CheckStatusInfo"] = json!({
+        "type": "object",
+        "properties": {
+            "check_id": uuid(),
+            "status": schema("CheckStatus"),
+            "status_updated_at": nullable(date_time())
+        }
+    });
+    schemas["CaseStatus"] = string_enum(&["open", "in_review", "escalated", "closed"]);
+    schemas["Verdict"] = string_enum(&["false_positive", "true_match"]);
+    schemas["CaseInfo"] = json!({
+        "type": "object",
+        "properties": {
+            "check_id": uuid(),
+            "status": schema("CaseStatus"),
+            "assignee_id": nullable(uuid()),
+            "opened_at": date_time(),
+            "updated_at": date_time()
+        }
+    });
+    schemas["Case"] = json!({
+        "allOf": [
+            schema("CaseInfo"),
+            {
+                "type": "object",
+                "properties": {
+                    "flags": array(json!({
+                        "type": "object",
+                        "properties": {
+                            "flag_id": uuid(),
+                            "entity_id": uuid(),
+                            "kind": schema("Flagkind"),
+                            "verdict": nullable(schema("Verdict"))
+                        }
+                    })),
+                    "matches": array(json!({
+                        "type": "object",
+                        "properties": {
+                            "id": uuid(),
+                            "entity_id": uuid(),
+                            "open_sanctions_id": { "type": "string" },
+                            "caption": { "type": "string" },
+                            "source_fetch_id": nullable(uuid()),
+                            "verdict": nullable(schema("Verdict"))
+                        }
+                    })),
+                    "dispositions": array(schema("Disposition"))
+                }
+            }
+        ]
+    });
+    schemas["UpdateCaseRequest"] = json!({
+        "type": "object",
+        "properties": {
+            "status": schema("CaseStatus"),
+            "assignee_id": described(nullable(uuid()), "null unassigns the case")
+        }
+    });
+    schemas["CreateDispositionRequest"] = json!({
+        "type": "object",
+        "description": "Exactly one of flag_id and sanctions_match_id",
+        "required": ["verdict"],
+        "properties": {
+            "flag_id": uuid(),
+            "sanctions_match_id": uuid(),
+            "verdict": schema("Verdict"),
+            "comment": string()
+        }
+    });
+    schemas["Disposition"] = json!({
+        "type": "object",
+        "properties": {
+            "id": uuid(),
+            "check_id": uuid(),
+            "flag_id": nullable(uuid()),
+            "sanctions_match_id": nullable(uuid()),
+            "verdict": schema("Verdict"),
+            "comment": nullable(string()),
+            "user_id": nullable(uuid()),
+            "created_at": date_time()
+        }
+    });
+    schemas["BatchCreated"] = json!({
+        "type": "object",
+        "properties": {
+            "batch_id": uuid(),
+            "row_count": { "type": "integer" }
+        }
+    });
+    schemas["BatchProgress"] = json!({
+        "type": "object",
+        "properties": {
+            "id": uuid(),
+            "tenant_id": uuid(),
+            "user_id": nullable(uuid()),
+            "name": nullable(string()),
+            "submitted_at": date_time(),
+            "total": { "type": "integer" },
+            "pending": { "type": "integer" },
+            "running": { "type": "integer" },
+            "completed": { "type": "integer" },
+            "failed": { "type": "integer" },
+            "rows": array(json!({
+                "type": "object",
+                "properties": {
+                    "row_number": { "type": "integer" },
+                    "company_number": nullable(string()),
+                    "name": nullable(string()),
+                    "postcode": nullable(string()),
+                    "status": string_enum(&["pending", "running", "completed", "failed"]),
+                    "check_id": nullable(uuid()),
+                    "error": nullable(string())
+                }
+            }))
+        }
+    });
+    schemas["SourceFetch"] = json!({
+        "type": "object",
+        "properties": {
+            "id": uuid(),
+            "check_id": uuid(),
+            "source": { "type": "string", "enum": ["companies_house", "open_sanctions"] },
+            "endpoint": { "type": "string" },
+            "fetched_at": date_time(),
+            "response_hash": described(string(), "Hex encoded sha256 of the response body"),
+            "payload": { "type": "object", "additionalProperties": true }
+        }
+    });
+    schemas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::ROUTES;
+
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    found.push(reference.clone());
+                }
+                object.values().for_each(|value| refs(value, found));
+            }
+            Value::Array(array) => array.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn documents_every_route() {
+        let document = document();
+        for (method, path) in ROUTES {
+            let method = method.as_str().to_lowercase();
+            assert!(
+                document["paths"][path][&method].is_object(),
+                "{} {} isn't documented",
+                method,
+                path
+            );
+        }
+
+        let mut found = Vec::new();
+        refs(&document, &mut found);
+        for reference in found {
+            let pointer = reference.trim_start_matches('#');
+            assert!(
+                document.pointer(pointer).is_some(),
+                "{} is missing",
+                reference
+            );
+        }
+    }
+}
@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    guard,
+    http::{header, Method},
+    web, HttpResponse, Scope,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+use Company_Investigation::{
+    batch::{get_batch_progress, parse_batch_csv},
+    check_query::ChecksQuery,
+    check_response::get_entity_response,
+    graph::{
+        export::ExportFormat,
+        query::{neighbourhood, shortest_paths, MAX_PATH_DEPTH},
+    },
+    jobs::{exposure::PropagationPolicy, relation_jobs::TraversalPolicy},
+    models::{
+        AuditLog, Batch, CaseStatus, Check, CheckCase, CheckStatus, Disposition, Flagkind,
+        SanctionsMatch, SourceFetch, Verdict,
+    },
+    postgres::Database,
+    pulsar::PulsarClient,
+    report::ReportFormat,
+};
+
+use crate::{
+    audit::with_target, auth::TenantPool, error::ApiError, export_check, export_response,
+    generate_report, get_check_reports, get_check_timeline, get_checks, get_entity_appearances,
+    get_monitored_entities, openapi, propagate_exposure, relationship_filter, report_response,
+    resume_check, start_monitoring_check, start_relations_check, submit_batch_rows, with_database,
+    AsOfParams, MonitoredEntitiesResponse, NeighbourhoodParams, ShortestPathsParams,
+};
+
+type ApiResult = Result<HttpResponse, ApiError>;
+
+#[derive(Deserialize)]
+pub struct CreateCheckRequest {
+    pub company_number: String,
+    pub traversal_policy: Option<TraversalPolicy>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateReportRequest {
+    pub format: ReportFormat,
+}
+
+#[derive(Deserialize)]
+pub struct CreateMonitorRequest {
+    pub company_number: String,
+}
+
+#[derive(Serialize)]
+pub struct CheckCreated {
+    pub check_id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct AuditLogParams {
+    // only calls which acted on this check, report or entity
+    pub target_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCaseRequest {
+    pub status: Option<CaseStatus>,
+    // left out to keep the assignee, null to unassign the case
+    #[serde(default, deserialize_with = "present")]
+    pub assignee_id: Option<Option<Uuid>>,
+}
+
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+// Exactly one of flag_id and sanctions_match_id
+#[derive(Deserialize)]
+pub struct CreateDispositionRequest {
+    pub flag_id: Option<Uuid>,
+    pub sanctions_match_id: Option<Uuid>,
+    pub verdict: Verdict,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CaseFlag {
+    pub flag_id: Uuid,
+    pub entity_id: Uuid,
+    pub kind: Flagkind,
+    // the latest disposition's, if any
+    pub verdict: Option<Verdict>,
+}
+
+#[derive(Serialize)]
+pub struct CaseMatch {
+    #[serde(flatten)]
+    pub sanctions_match: SanctionsMatch,
+    pub verdict: Option<Verdict>,
+}
+
+// The case along with everything on it to be dispositioned, and the dispositions made so
+// far oldest first
+#[derive(Serialize)]
+pub struct CaseResponse {
+    #[serde(flatten)]
+    pub case: CheckCase,
+    pub flags: Vec<CaseFlag>,
+    pub matches: Vec<CaseMatch>,
+    pub dispositions: Vec<Disposition>,
+}
+
+#[derive(Serialize)]
+pub struct CheckStatusResponse {
+    pub check_id: Uuid,
+    pub status: CheckStatus,
+    pub status_updated_at: Option<NaiveDateTime>,
+}
+
+impl From<Check> for CheckStatusResponse {
+    fn from(check: Check) -> Self {
+        Self {
+            check_id: check.id,
+            status: check.status,
+            status_updated_at: check.status_updated_at,
+        }
+    }
+}
+
+// CSVs up to this size are accepted
+const MAX_BATCH_CSV_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct CreateBatchParams {
+    // e.g. the CSV's file name
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchCreated {
+    pub batch_id: Uuid,
+    pub row_count: usize,
+}
+
+const DEFAULT_AUDIT_LOG_LIMIT: i64 = 100;
+const MAX_AUDIT_LOG_LIMIT: i64 = 1000;
+
+#[derive(Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLog>,
+}
+
+// Where a recorded entity, relationship or flag came from, with the payload exactly as
+// the source returned it
+#[derive(Serialize)]
+pub struct SourceFetchResponse {
+    #[serde(flatten)]
+    pub source_fetch: SourceFetch,
+    pub payload: serde_json::Value,
+}
+
+// Every v1 route, as (method, path, handler) and any data only that route is given. They're
+// registered by configure and listed in ROUTES, which the openapi tests check are documented
+macro_rules! v1_routes {
+    ($(($method:ident, $path:literal, $handler:expr $(, $data:expr)?)),* $(,)?) => {
+        #[cfg(test)]
+        pub const ROUTES: &[(Method, &str)] = &[$((Method::$method, concat!("/v1", $path))),*];
+
+        // each route is a resource guarded by its method, so the paths with more than one
+        // method fall through to the next resource
+        fn routes(scope: Scope) -> Scope {
+            scope$(.service(
+                web::resource($path)
+                    .guard(guard::Method(Method::$method))
+                    $(.app_data($data))?
+                    .to($handler),
+            ))*
+        }
+    };
+}
+
+v1_routes![
+    (GET, "/openapi.json", openapi_document),
+    (POST, "/checks", create_check),
+    (GET, "/checks", list_checks),
+    (GET, "/checks/{check_id}", get_check),
+    (GET, "/checks/{check_id}/timeline", get_timeline),
+    (GET, "/checks/{check_id}/graph/{format}", export_graph),
+    (
+        PUT,
+        "/checks/{check_id}/propagation-policy",
+        set_propagation_policy
+    ),
+    (POST, "/checks/{check_id}/reports", create_report),
+    (GET, "/checks/{check_id}/reports", list_reports),
+    (GET, "/checks/{check_id}/case", get_case),
+    (PATCH, "/checks/{check_id}/case", update_case),
+    (POST, "/checks/{check_id}/dispositions", create_disposition),
+    (POST, "/checks/{check_id}/pause", pause_check),
+    (POST, "/checks/{check_id}/resume", resume),
+    (POST, "/checks/{check_id}/cancel", cancel_check),
+    (GET, "/reports/{report_id}", get_report),
+    (GET, "/entities/{entity_id}/appearances", get_appearances),
+    (
+        GET,
+        "/entities/{entity_id}/neighbourhood",
+        get_neighbourhood
+    ),
+    (GET, "/paths", find_paths),
+    (POST, "/monitors", create_monitor),
+    (GET, "/monitors", list_monitors),
+    (DELETE, "/monitors/{check_id}", cancel_monitor),
+    (
+        POST,
+        "/batches",
+        create_batch,
+        web::PayloadConfig::new(MAX_BATCH_CSV_BYTES)
+    ),
+    (GET, "/batches/{batch_id}", get_batch),
+    (GET, "/audit-log", get_audit_log),
+    (GET, "/source-fetches/{source_fetch_id}", get_source_fetch),
+];
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(routes(
+        web::scope("/v1")
+            // extractor failures get the same error body as everything else
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|e, _| ApiError::invalid_request(e).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|e, _| ApiError::invalid_request(e).into()),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|e, _| ApiError::invalid_request(e).into()),
+            ),
+    ));
+}
+
+// The created resource is recorded as the target of the call in the audit log
+fn created(location: String, target_id: Uuid, body: impl Serialize) -> HttpResponse {
+    with_target(
+        HttpResponse::Created()
+            .insert_header((header::LOCATION, location))
+            .json(body),
+        target_id,
+    )
+}
+
+async fn openapi_document() -> HttpResponse {
+    HttpResponse::Ok().json(openapi::document())
+}
+
+async fn create_check(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    request: web::Json<CreateCheckRequest>,
+) -> ApiResult {
+    let request = request.into_inner();
+    let traversal_policy = request.traversal_policy.unwrap_or_default();
+    traversal_policy
+        .validate()
+        .map_err(|e| ApiError::invalid_request(format!("Invalid traversal policy: {}", e)))?;
+
+    let check_id = start_relations_check(
+        &pool,
+        &pulsar_client,
+        request.company_number,
+        traversal_policy,
+    )
+    .await?;
+    Ok(created(
+        format!("/v1/checks/{}", check_id),
+        check_id,
+        CheckCreated { check_id },
+    ))
+}
+
+async fn list_checks(pool: TenantPool, query: web::Query<ChecksQuery>) -> ApiResult {
+    let checks_query = query.into_inner();
+    checks_query
+        .decode_cursor()
+        .map_err(ApiError::invalid_request)?;
+
+    let checks = with_database(&pool, move |database| get_checks(database, &checks_query)).await?;
+    Ok(HttpResponse::Ok().json(checks))
+}
+
+async fn get_check(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    query: web::Query<AsOfParams>,
+) -> ApiResult {
+    let check_id = path.into_inner();
+    let as_of = query.as_of;
+    let check = with_database(&pool, move |database| {
+        get_entity_response(database, check_id, as_of)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(check))
+}
+
+async fn get_timeline(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    let timeline = with_database(&pool, move |database| {
+        get_check_timeline(database, check_id)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(timeline))
+}
+
+async fn export_graph(
+    pool: TenantPool,
+    path: web::Path<(Uuid, ExportFormat)>,
+    query: web::Query<AsOfParams>,
+) -> ApiResult {
+    let (check_id, format) = path.into_inner();
+    let as_of = query.as_of;
+    let export = with_database(&pool, move |database| {
+        export_check(database, check_id, format, as_of)
+    })
+    .await?;
+    Ok(export_response(check_id, format, export))
+}
+
+async fn set_propagation_policy(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    path: web::Path<Uuid>,
+    propagation_policy: Option<web::Json<PropagationPolicy>>,
+) -> ApiResult {
+    let check_id = path.into_inner();
+    let propagation_policy = propagation_policy
+        .map(|propagation_policy| propagation_policy.into_inner())
+        .unwrap_or_default();
+    propagation_policy
+        .validate()
+        .map_err(|e| ApiError::invalid_request(format!("Invalid propagation policy: {}", e)))?;
+
+    propagate_exposure(&pool, &pulsar_client, check_id, propagation_policy).await?;
+    // exposures are recomputed by the risk service
+    Ok(HttpResponse::Accepted().json(CheckCreated { check_id }))
+}
+
+async fn create_report(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    request: web::Json<CreateReportRequest>,
+) -> ApiResult {
+    let check_id = path.into_inner();
+    let format = request.format;
+    let report_info = with_database(&pool, move |database| {
+        generate_report(database, check_id, format)
+    })
+    .await?;
+    Ok(created(
+        format!("/v1/reports/{}", report_info.report_id),
+        report_info.report_id,
+        report_info,
+    ))
+}
+
+async fn list_reports(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    let reports =
+        with_database(&pool, move |database| get_check_reports(database, check_id)).await?;
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+async fn get_report(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let report_id = path.into_inner();
+    let check_report =
+        with_database(&pool, move |database| database.get_check_report(&report_id)).await?;
+    Ok(report_response(check_report))
+}
+
+async fn get_appearances(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let entity_id = path.into_inner();
+    let appearances = with_database(&pool, move |database| {
+        get_entity_appearances(database, entity_id)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(appearances))
+}
+
+async fn get_neighbourhood(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    query: web::Query<NeighbourhoodParams>,
+) -> ApiResult {
+    let entity_id = path.into_inner();
+    let filter = relationship_filter(&query.kinds, query.active_at, query.across_checks)
+        .map_err(ApiError::invalid_request)?;
+    let hops = query.hops.unwrap_or(1);
+    let neighbourhood = with_database(&pool, move |database| {
+        database.get_entity(entity_id)?;
+        neighbourhood(database, entity_id, hops, &filter)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(neighbourhood))
+}
+
+async fn find_paths(pool: TenantPool, query: web::Query<ShortestPathsParams>) -> ApiResult {
+    let filter = relationship_filter(&query.kinds, query.active_at, query.across_checks)
+        .map_err(ApiError::invalid_request)?;
+    let (from, to) = (query.from, query.to);
+    let max_depth = query.max_depth.unwrap_or(MAX_PATH_DEPTH);
+    let paths = with_database(&pool, move |database| {
+        database.get_entity(from)?;
+        database.get_entity(to)?;
+        shortest_paths(database, from, to, max_depth, &filter)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(paths))
+}
+
+async fn create_monitor(pool: TenantPool, request: web::Json<CreateMonitorRequest>) -> ApiResult {
+    let company_number = request.into_inner().company_number;
+    let check_id = with_database(&pool, move |database| {
+        start_monitoring_check(database, company_number)
+    })
+    .await?;
+    Ok(created(
+        format!("/v1/checks/{}", check_id),
+        check_id,
+        CheckCreated { check_id },
+    ))
+}
+
+async fn list_monitors(pool: TenantPool) -> ApiResult {
+    let entities = with_database(&pool, get_monitored_entities).await?;
+    Ok(HttpResponse::Ok().json(MonitoredEntitiesResponse { entities }))
+}
+
+async fn cancel_monitor(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    with_database(&pool, move |database| {
+        database.get_check(check_id)?;
+        database.cancel_monitoring(check_id)
+    })
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn case_response(database: &mut Database, check_id: Uuid) -> Result<CaseResponse, failure::Error> {
+    let case = database.get_case(check_id)?;
+    let dispositions = database.get_dispositions(check_id)?;
+
+    let mut flag_verdicts = HashMap::new();
+    let mut match_verdicts = HashMap::new();
+    for disposition in &dispositions {
+        if let Some(flag_id) = disposition.flag_id {
+            flag_verdicts.insert(flag_id, disposition.verdict);
+        }
+        if let Some(sanctions_match_id) = disposition.sanctions_match_id {
+            match_verdicts.insert(sanctions_match_id, disposition.verdict);
+        }
+    }
+
+    let flags = database
+        .get_flags_for_check(&check_id)?
+        .into_iter()
+        .map(|(entity_id, flag_id, kind)| CaseFlag {
+            flag_id,
+            entity_id,
+            kind,
+            verdict: flag_verdicts.get(&flag_id).copied(),
+        })
+        .collect();
+    let matches = database
+        .get_check_sanctions_matches(&check_id)?
+        .into_iter()
+        .map(|sanctions_match| CaseMatch {
+            verdict: match_verdicts.get(&sanctions_match.id).copied(),
+            sanctions_match,
+        })
+        .collect();
+
+    Ok(CaseResponse {
+        case,
+        flags,
+        matches,
+        dispositions,
+    })
+}
+
+async fn get_case(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    let case = with_database(&pool, move |database| case_response(database, check_id)).await?;
+    Ok(HttpResponse::Ok().json(case))
+}
+
+async fn update_case(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateCaseRequest>,
+) -> ApiResult {
+    let check_id = path.into_inner();
+    let request = request.into_inner();
+    let case = with_database(&pool, move |database| {
+        database.update_case(check_id, request.status, request.assignee_id)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(case))
+}
+
+async fn create_disposition(
+    pool: TenantPool,
+    path: web::Path<Uuid>,
+    request: web::Json<CreateDispositionRequest>,
+) -> ApiResult {
+    let check_id = path.into_inner();
+    let request = request.into_inner();
+    if request.flag_id.is_some() == request.sanctions_match_id.is_some() {
+        return Err(ApiError::invalid_request(
+            "Exactly one of flag_id and sanctions_match_id must be given",
+        ));
+    }
+
+    let disposition = Disposition {
+        id: Uuid::new_v4(),
+        check_id,
+        flag_id: request.flag_id,
+        sanctions_match_id: request.sanctions_match_id,
+        verdict: request.verdict,
+        comment: request.comment,
+        user_id: pool.caller.user_id,
+        created_at: Utc::now().naive_utc(),
+    };
+    let disposition = with_database(&pool, move |database| {
+        database.insert_disposition(&disposition)?;
+        Ok(disposition)
+    })
+    .await?;
+    Ok(created(
+        format!("/v1/checks/{}/case", check_id),
+        check_id,
+        disposition,
+    ))
+}
+
+async fn pause_check(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    let check = with_database(&pool, move |database| {
+        database.set_check_status(check_id, CheckStatus::Paused)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(CheckStatusResponse::from(check)))
+}
+
+async fn resume(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    path: web::Path<Uuid>,
+) -> ApiResult {
+    let check = resume_check(&pool, &pulsar_client, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(CheckStatusResponse::from(check)))
+}
+
+async fn cancel_check(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let check_id = path.into_inner();
+    let check = with_database(&pool, move |database| {
+        database.set_check_status(check_id, CheckStatus::Cancelled)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(CheckStatusResponse::from(check)))
+}
+
+async fn create_batch(
+    pool: TenantPool,
+    pulsar_client: web::Data<PulsarClient>,
+    query: web::Query<CreateBatchParams>,
+    csv: web::Bytes,
+) -> ApiResult {
+    let batch = Batch {
+        id: Uuid::new_v4(),
+        tenant_id: pool.caller.tenant_id,
+        user_id: pool.caller.user_id,
+        name: query.into_inner().name,
+        submitted_at: Utc::now().naive_utc(),
+    };
+    let rows = parse_batch_csv(batch.id, &csv).map_err(ApiError::invalid_request)?;
+    let batch_id = batch.id;
+    let row_count = rows.len();
+
+    submit_batch_rows(&pool, &pulsar_client, batch, rows).await?;
+    Ok(created(
+        format!("/v1/batches/{}", batch_id),
+        batch_id,
+        BatchCreated {
+            batch_id,
+            row_count,
+        },
+    ))
+}
+
+async fn get_batch(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let batch_id = path.into_inner();
+    let progress = with_database(&pool, move |database| {
+        get_batch_progress(database, batch_id)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+async fn get_audit_log(pool: TenantPool, query: web::Query<AuditLogParams>) -> ApiResult {
+    let target_id = query.target_id;
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_LOG_LIMIT);
+    if !(1..=MAX_AUDIT_LOG_LIMIT).contains(&limit) {
+        return Err(ApiError::invalid_request(format!(
+            "limit must be between 1 and {}",
+            MAX_AUDIT_LOG_LIMIT
+        )));
+    }
+
+    let entries = with_database(&pool, move |database| {
+        database.get_audit_log(target_id, limit)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(AuditLogResponse { entries }))
+}
+
+async fn get_source_fetch(pool: TenantPool, path: web::Path<Uuid>) -> ApiResult {
+    let source_fetch_id = path.into_inner();
+    let (source_fetch, body) = with_database(&pool, move |database| {
+        database.get_source_fetch(source_fetch_id)
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(SourceFetchResponse {
+        source_fetch,
+        payload: serde_json::from_slice(&body).map_err(failure::Error::from)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn each_method_of_a_path_reaches_its_route() {
+        let app = test::init_service(App::new().configure(configure)).await;
+        let request = |method: Method, uri: &str| {
+            test::TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .to_request()
+        };
+
+        let response = test::call_service(&app, request(Method::GET, "/v1/openapi.json")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        // both routes of /checks are reached, and turn the caller away without credentials
+        for method in [Method::POST, Method::GET] {
+            let response = test::call_service(&app, request(method, "/v1/checks")).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = test::call_service(&app, request(Method::DELETE, "/v1/checks")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
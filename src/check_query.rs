@@ -0,0 +1,206 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use failure::format_err;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::models::{CheckSummary, Checkkind, Flagkind};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckSort {
+    #[default]
+    StartedAt,
+    // checks without a root entity name sort as an empty name
+    Name,
+    RiskScore,
+    EntityCount,
+}
+
+impl CheckSort {
+    const ALL: [CheckSort; 4] = [
+        CheckSort::StartedAt,
+        CheckSort::Name,
+        CheckSort::RiskScore,
+        CheckSort::EntityCount,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckSort::StartedAt => "started_at",
+            CheckSort::Name => "name",
+            CheckSort::RiskScore => "risk_score",
+            CheckSort::EntityCount => "entity_count",
+        }
+    }
+
+    // Whether a cursor value is one the sort's column could have
+    fn parses(&self, value: &str) -> bool {
+        match self {
+            CheckSort::StartedAt => {
+                NaiveDateTime::parse_from_str(value, STARTED_AT_CURSOR_FORMAT).is_ok()
+            }
+            CheckSort::Name => true,
+            CheckSort::RiskScore => value.parse::<f64>().is_ok(),
+            CheckSort::EntityCount => value.parse::<i64>().is_ok(),
+        }
+    }
+}
+
+// How started_at is written into a cursor, as NaiveDateTime displays
+pub const STARTED_AT_CURSOR_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Which checks to list and in what order. Every filter is optional, and they're combined
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ChecksQuery {
+    pub kind: Option<Checkkind>,
+    // inclusive of both days
+    pub started_from: Option<NaiveDate>,
+    pub started_to: Option<NaiveDate>,
+    pub completed: Option<bool>,
+    pub has_error: Option<bool>,
+    pub risk_level: Option<String>,
+    pub flag: Option<Flagkind>,
+    // the root company's number, or part of its name
+    pub company: Option<String>,
+    pub sort: CheckSort,
+    pub direction: SortDirection,
+    pub limit: Option<i64>,
+    // the next_cursor of the previous page
+    pub cursor: Option<String>,
+}
+
+impl ChecksQuery {
+    pub fn page_size(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    // A cursor is only valid for the sort of the query its page was listed with
+    pub fn decode_cursor(&self) -> Result<Option<ChecksCursor>, failure::Error> {
+        let cursor = match self.cursor.as_deref() {
+            Some(cursor) => ChecksCursor::decode(cursor)?,
+            None => return Ok(None),
+        };
+        if cursor.sort != self.sort {
+            return Err(format_err!("Cursor doesn't match the sort"));
+        }
+
+        Ok(Some(cursor))
+    }
+}
+
+// The sort value and id of the last check on a page, the next page starts after it. It's
+// hex encoded so it can be passed back in a query string as it is
+#[derive(Debug, PartialEq)]
+pub struct ChecksCursor {
+    pub sort: CheckSort,
+    pub value: String,
+    pub check_id: Uuid,
+}
+
+impl ChecksCursor {
+    pub fn after(summary: &CheckSummary, sort: CheckSort) -> Self {
+        let value = match sort {
+            CheckSort::StartedAt => summary.started_at.to_string(),
+            CheckSort::Name => summary.name.clone().unwrap_or_default(),
+            CheckSort::RiskScore => summary.risk_score.to_string(),
+            CheckSort::EntityCount => summary.entity_count.to_string(),
+        };
+        Self {
+            sort,
+            value,
+            check_id: summary.check_id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}|{}|{}", self.sort.as_str(), self.check_id, self.value)
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, failure::Error> {
+        let invalid = || format_err!("Invalid cursor: {}", cursor);
+        if cursor.len() % 2 != 0 {
+            return Err(invalid());
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let mut parts = decoded.splitn(3, '|');
+        let (sort, check_id, value) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(sort), Some(check_id), Some(value)) => (sort, check_id, value),
+            _ => return Err(invalid()),
+        };
+        let sort = CheckSort::ALL
+            .into_iter()
+            .find(|check_sort| check_sort.as_str() == sort)
+            .ok_or_else(invalid)?;
+        if !sort.parses(value) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            sort,
+            value: value.to_string(),
+            check_id: check_id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = ChecksCursor {
+            sort: CheckSort::Name,
+            value: "Acme | Holdings Ltd".to_string(),
+            check_id: Uuid::new_v4(),
+        };
+
+        assert_eq!(ChecksCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(ChecksCursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn cursor_must_match_the_sort() {
+        let cursor = ChecksCursor {
+            sort: CheckSort::RiskScore,
+            value: "12.5".to_string(),
+            check_id: Uuid::new_v4(),
+        };
+        let checks_query = |sort| ChecksQuery {
+            sort,
+            cursor: Some(cursor.encode()),
+            ..Default::default()
+        };
+
+        assert!(checks_query(CheckSort::RiskScore).decode_cursor().is_ok());
+        assert!(checks_query(CheckSort::Name).decode_cursor().is_err());
+
+        let forged = ChecksCursor {
+            value: "not a score".to_string(),
+            ..cursor
+        };
+        assert!(ChecksCursor::decode(&forged.encode()).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    graph::history::entities_connected_at,
+    jobs::{
+        exposure::{compute_exposures, PropagationPolicy},
+        relation_jobs::{RelationJobKind, TraversalPolicy},
+        risk_jobs::compute_beneficial_owners,
+    },
+    models::{
+        Charge, CheckStatus, CompanyProfile, Disqualification, Entity, Entitykind, Exposure,
+        Flagkind, Insolvency, OwnershipBand, PreviousName, Relationship, Relationshipkind,
+    },
+    postgres::Database,
+};
+
+// ownership above which a person has significant control of a company
+pub const SIGNIFICANT_CONTROL_THRESHOLD: f64 = 0.25;
+
+#[derive(Serialize, Deserialize)]
+pub struct Relation {
+    pub entity_id: Uuid,
+    pub started_on: Option<NaiveDate>,
+    pub ended_on: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EntityWithRelations {
+    pub entity: Entity,
+    pub officers: Vec<Relation>,
+    pub shareholders: Vec<Relation>,
+    pub charge_holders: Vec<Relation>,
+    pub flags: Vec<Flagkind>,
+    pub positions: Vec<String>,
+    pub datasets: Vec<String>,
+    pub profile: Option<CompanyProfile>,
+    pub previous_names: Vec<PreviousName>,
+    pub charges: Vec<Charge>,
+    pub insolvencies: Vec<Insolvency>,
+    pub disqualifications: Vec<Disqualification>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BeneficialOwnerResponse {
+    pub entity_id: Uuid,
+    pub name: Option<String>,
+    pub min_ownership: f64,
+    pub max_ownership: f64,
+    pub is_ultimate: bool,
+    // each chain of shareholders from the root entity up to the owner
+    pub paths: Vec<Vec<Uuid>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EntityCheckResponse {
+    pub entities: Vec<EntityWithRelations>,
+    // owners who may own more than the significant control threshold of the root entity
+    pub ultimate_beneficial_owners: Vec<BeneficialOwnerResponse>,
+    // intermediate companies, and owners below the threshold
+    pub other_beneficial_owners: Vec<BeneficialOwnerResponse>,
+    // companies exposed to flags on their owners, officers or lenders, highest score first
+    pub exposures: Vec<Exposure>,
+    pub traversal_policy: Option<TraversalPolicy>,
+    pub as_of: Option<NaiveDate>,
+    pub started_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+    pub status: CheckStatus,
+}
+
+pub fn get_beneficial_owners(
+    database: &mut Database,
+    check_id: &Uuid,
+    check_entities: &[Entity],
+    as_of_relationships: Option<&[Relationship]>,
+) -> Result<(Vec<BeneficialOwnerResponse>, Vec<BeneficialOwnerResponse>), failure::Error> {
+    let names: HashMap<Uuid, Option<String>> = check_entities
+        .iter()
+        .map(|entity| (entity.id, entity.name.clone()))
+        .collect();
+
+    // the stored owners are for the current structure, past structures are computed here
+    let (owners, owner_paths) = match as_of_relationships {
+        Some(relationships) => {
+            let ownership_relations: Vec<(Uuid, Uuid, OwnershipBand)> = relationships
+                .iter()
+                .filter(|relationship| relationship.kind == Relationshipkind::Shareholder)
+                .filter_map(|relationship| {
+                    Some((
+                        relationship.parent_id,
+                        relationship.child_id,
+                        relationship.ownership_band?,
+                    ))
+                })
+                .collect();
+            let individuals: HashSet<Uuid> = check_entities
+                .iter()
+                .filter(|entity| entity.kind == Entitykind::Individual)
+                .map(|entity| entity.id)
+                .collect();
+            match check_entities.iter().find(|entity| entity.is_root) {
+                Some(root_entity) => compute_beneficial_owners(
+                    *check_id,
+                    root_entity.id,
+                    &ownership_relations,
+                    &individuals,
+                    &database
+                        .get_fetched_entities(check_id, RelationJobKind::Shareholders.as_str())?,
+                ),
+                None => (vec![], vec![]),
+            }
+        }
+        None => (
+            database.get_beneficial_owners(check_id)?,
+            database.get_beneficial_owner_paths(check_id)?,
+        ),
+    };
+
+    let mut paths: HashMap<Uuid, Vec<Vec<Uuid>>> = HashMap::new();
+    for path in owner_paths {
+        paths.entry(path.entity_id).or_default().push(path.path);
+    }
+
+    let beneficial_owners = owners.into_iter().map(|owner| BeneficialOwnerResponse {
+        entity_id: owner.entity_id,
+        name: names.get(&owner.entity_id).cloned().flatten(),
+        min_ownership: owner.min_ownership,
+        max_ownership: owner.max_ownership,
+        is_ultimate: owner.is_ultimate,
+        paths: paths.remove(&owner.entity_id).unwrap_or_default(),
+    });
+
+    Ok(beneficial_owners.partition(|owner| {
+        owner.is_ultimate && owner.max_ownership > SIGNIFICANT_CONTROL_THRESHOLD
+    }))
+}
+
+// Highest scoring first
+fn get_exposures(
+    database: &mut Database,
+    check_id: &Uuid,
+    check_entities: &[Entity],
+    as_of_relationships: Option<&[Relationship]>,
+) -> Result<Vec<Exposure>, failure::Error> {
+    // the stored exposures are for the current structure, past structures are computed here
+    let relationships = match as_of_relationships {
+        Some(relationships) => relationships,
+        None => return database.get_exposures(check_id),
+    };
+
+    let policy: PropagationPolicy = database
+        .get_propagation_policy(check_id)?
+        .map(serde_json::from_value)
+        .transpose()?
+        .unwrap_or_default();
+    let entity_ids: HashSet<Uuid> = check_entities.iter().map(|entity| entity.id).collect();
+    let flags: Vec<(Uuid, Uuid, Flagkind)> = database
+        .get_flags_for_check(check_id)?
+        .into_iter()
+        .filter(|(entity_id, _, _)| entity_ids.contains(entity_id))
+        .collect();
+
+    let mut exposures = compute_exposures(*check_id, &flags, relationships, &policy);
+    exposures.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(exposures)
+}
+
+pub(crate) fn group_by_entity<T>(
+    rows: impl IntoIterator<Item = (Uuid, T)>,
+) -> HashMap<Uuid, Vec<T>> {
+    let mut grouped: HashMap<Uuid, Vec<T>> = HashMap::new();
+    for (entity_id, row) in rows {
+        grouped.entry(entity_id).or_default().push(row);
+    }
+    grouped
+}
+
+// Everything known about a check's entities, as of a date when given. Each kind of record
+// is loaded for the whole check at once, rather than entity by entity
+pub fn get_entity_response(
+    database: &mut Database,
+    check_id: Uuid,
+    as_of: Option<NaiveDate>,
+) -> Result<EntityCheckResponse, failure::Error> {
+    let check = database.get_check(check_id)?;
+    let mut check_entities = database.get_entities(check_id)?;
+    let mut relationships = database.get_check_relationships(&check_id)?;
+
+    let as_of_relationships = match as_of {
+        Some(date) => {
+            relationships.retain(|relationship| relationship.is_active_at(date));
+            if let Some(root_entity) = check_entities.iter().find(|entity| entity.is_root) {
+                let connected = entities_connected_at(root_entity.id, &relationships, date);
+                check_entities.retain(|entity| connected.contains(&entity.id));
+            }
+            Some(relationships.as_slice())
+        }
+        None => None,
+    };
+
+    let (ultimate_beneficial_owners, other_beneficial_owners) =
+        get_beneficial_owners(database, &check_id, &check_entities, as_of_relationships)?;
+    let exposures = get_exposures(database, &check_id, &check_entities, as_of_relationships)?;
+    let traversal_policy = database
+        .get_traversal_policy(&check_id)?
+        .map(serde_json::from_value)
+        .transpose()?;
+
+    let mut relations: HashMap<(Uuid, Relationshipkind), Vec<Relation>> = HashMap::new();
+    for relationship in &relationships {
+        relations
+            .entry((relationship.child_id, relationship.kind))
+            .or_default()
+            .push(Relation {
+                entity_id: relationship.parent_id,
+                started_on: relationship.started_on,
+                ended_on: relationship.ended_on,
+            });
+    }
+    let mut flags = group_by_entity(
+        database
+            .get_flags_for_check(&check_id)?
+            .into_iter()
+            .map(|(entity_id, _, flag_kind)| (entity_id, flag_kind)),
+    );
+    let mut positions = group_by_entity(database.get_check_positions(&check_id)?);
+    let mut datasets = group_by_entity(database.get_check_datasets(&check_id)?);
+    let mut profiles: HashMap<Uuid, CompanyProfile> = database
+        .get_check_company_profiles(&check_id)?
+        .into_iter()
+        .map(|profile| (profile.entity_id, profile))
+        .collect();
+    let mut previous_names = group_by_entity(
+        database
+            .get_check_previous_names(&check_id)?
+            .into_iter()
+            .map(|previous_name| (previous_name.entity_id, previous_name)),
+    );
+    let mut charges = group_by_entity(
+        database
+            .get_check_charges(&check_id)?
+            .into_iter()
+            .map(|charge| (charge.entity_id, charge)),
+    );
+    let mut insolvencies = group_by_entity(
+        database
+            .get_check_insolvencies(&check_id)?
+            .into_iter()
+            .map(|insolvency| (insolvency.entity_id, insolvency)),
+    );
+    let mut disqualifications = group_by_entity(
+        database
+            .get_check_disqualifications(&check_id)?
+            .into_iter()
+            .map(|disqualification| (disqualification.entity_id, disqualification)),
+    );
+
+    let entities = check_entities
+        .into_iter()
+        .map(|entity| {
+            let mut relations_of = |kind| relations.remove(&(entity.id, kind)).unwrap_or_default();
+            EntityWithRelations {
+                officers: relations_of(Relationshipkind::Officer),
+                shareholders: relations_of(Relationshipkind::Shareholder),
+                charge_holders: relations_of(Relationshipkind::ChargeHolder),
+                flags: flags.remove(&entity.id).unwrap_or_default(),
+                positions: positions.remove(&entity.id).unwrap_or_default(),
+                datasets: datasets.remove(&entity.id).unwrap_or_default(),
+                profile: profiles.remove(&entity.id),
+                previous_names: previous_names.remove(&entity.id).unwrap_or_default(),
+                charges: charges.remove(&entity.id).unwrap_or_default(),
+                insolvencies: insolvencies.remove(&entity.id).unwrap_or_default(),
+                disqualifications: disqualifications.remove(&entity.id).unwrap_or_default(),
+                entity,
+            }
+        })
+        .collect();
+
+    Ok(EntityCheckResponse {
+        entities,
+        ultimate_beneficial_owners,
+        other_beneficial_owners,
+        exposures,
+        traversal_policy,
+        as_of,
+        started_at: check.started_at,
+        completed_at: database.check_completed_at(check_id)?,
+        status: check.status,
+    })
+}
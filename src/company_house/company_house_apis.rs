@@ -1,19 +1,50 @@
-use failure::format_err;
+use failure::{format_err, Fail};
 use lazy_static::lazy_static;
-use reqwest::{self, header, Client};
-use std::{collections::HashMap, env};
+use reqwest::{self, header, Client, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{env, fmt};
 
-use super::company_house_types::{
-    AppointmentsResponse, CompanySearchResponse, FilingHistoryResponse, OfficerListResponse,
-    ShareholderList,
+use crate::registry::{
+    fetched::{DataSource, Fetched},
+    registry_client::RegistryClient,
 };
 
-const COMPANY_SEARCH_URL: &str = "https://api.company-information.service.gov.uk/search/companies";
+use super::{
+    company_house_streaming_types::CompanyData,
+    company_house_types::{
+        AppointmentsResponse, ChargeList, CompanyInsolvency, CompanySearchResponse,
+        DisqualifiedOfficer, DisqualifiedOfficerSearch, FilingHistoryResponse, OfficerListResponse,
+        ShareholderList,
+    },
+};
+
+const API_URL: &str = "https://api.company-information.service.gov.uk";
+
+// Returned when the api answers with an unsuccessful status other than not found, e.g. when
+// rate limited or unauthorised
+#[derive(Debug)]
+pub struct UnexpectedStatus {
+    pub status: u16,
+    pub endpoint: String,
+}
+
+impl fmt::Display for UnexpectedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Companies House responded {} to {}",
+            self.status, self.endpoint
+        )
+    }
+}
+
+impl Fail for UnexpectedStatus {}
 
 lazy_static! {
     static ref API_KEY: String = env::var("COMPANY_HOUSE_API_KEY").expect("API KEY should be set");
 }
 
+#[derive(Default)]
 pub struct CompanyHouseClient {
     client: Client,
 }
@@ -25,112 +56,146 @@ impl CompanyHouseClient {
         }
     }
 
-    pub async fn get_company(
+    // A resource the api doesn't have is None, any other unsuccessful response is an error
+    async fn get<T: DeserializeOwned>(
         &self,
-        name: &String,
-    ) -> Result<CompanySearchResponse, failure::Error> {
-        let mut params = HashMap::new();
-        params.insert("q", name);
-
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Option<Fetched<T>>, failure::Error> {
         let mut headers = header::HeaderMap::new();
         headers.insert(
             "Authorization",
-            header::HeaderValue::from_str(&format!("{}", API_KEY.as_str()))?,
+            header::HeaderValue::from_str(API_KEY.as_str())?,
         );
 
         let response = self
             .client
-            .get(COMPANY_SEARCH_URL)
+            .get(format!("{}{}", API_URL, path))
             .headers(headers)
-            .query(&params)
+            .query(params)
             .send()
-            .await
-            .unwrap();
-        let company_search_response: CompanySearchResponse = response.json().await?;
-        Ok(company_search_response)
+            .await?;
+
+        let endpoint = match params.is_empty() {
+            true => path.to_string(),
+            false => format!(
+                "{}?{}",
+                path,
+                params
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<String>>()
+                    .join("&")
+            ),
+        };
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(None),
+            status if !status.is_success() => {
+                return Err(UnexpectedStatus {
+                    status: status.as_u16(),
+                    endpoint,
+                }
+                .into())
+            }
+            _ => {}
+        }
+
+        Fetched::parse(
+            DataSource::CompaniesHouse,
+            endpoint,
+            &response.bytes().await?,
+        )
+        .map(Some)
     }
+}
 
-    pub async fn get_officers(
+impl RegistryClient for CompanyHouseClient {
+    async fn get_company(
         &self,
-        company_number: &String,
-    ) -> Result<OfficerListResponse, failure::Error> {
-        let url = format!(
-            "https://api.company-information.service.gov.uk/company/{}/officers",
-            company_number
-        );
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            header::HeaderValue::from_str(&format!("{}", API_KEY.as_str()))?,
-        );
-
-        let response = self.client.get(url).headers(headers).send().await.unwrap();
-
-        let officer_search_response: OfficerListResponse = response.json().await?;
-        Ok(officer_search_response)
+        name: &str,
+    ) -> Result<Option<Fetched<CompanySearchResponse>>, failure::Error> {
+        self.get("/search/companies", &[("q", name)]).await
     }
 
-    pub async fn get_shareholders(
+    async fn get_company_profile(
         &self,
-        company_number: &String,
-    ) -> Result<ShareholderList, failure::Error> {
-        let url = format!("https://api.company-information.service.gov.uk/company/{}/persons-with-significant-control", company_number);
-        // TODO: could stop duplication and do this once somewhere
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            header::HeaderValue::from_str(&format!("{}", API_KEY.as_str()))?,
-        );
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyData>>, failure::Error> {
+        self.get(&format!("/company/{}", company_number), &[]).await
+    }
 
-        let response = self.client.get(url).headers(headers).send().await?;
+    async fn get_officers(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<OfficerListResponse>>, failure::Error> {
+        self.get(&format!("/company/{}/officers", company_number), &[])
+            .await
+    }
 
-        let shareholder_list: ShareholderList = response.json().await?;
-        Ok(shareholder_list)
+    async fn get_shareholders(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<ShareholderList>>, failure::Error> {
+        self.get(
+            &format!(
+                "/company/{}/persons-with-significant-control",
+                company_number
+            ),
+            &[],
+        )
+        .await
     }
 
-    pub async fn get_appointments(
+    async fn get_appointments(
         &self,
         officer_id: &Option<String>,
-    ) -> Result<AppointmentsResponse, failure::Error> {
+    ) -> Result<Option<Fetched<AppointmentsResponse>>, failure::Error> {
         let officer_id = match officer_id {
             Some(officer_id) => officer_id,
             None => return Err(format_err!("Officer id doesn't exist")),
         };
-        let url = format!(
-            "https://api.company-information.service.gov.uk/officers/{}/appointments",
-            officer_id
-        );
 
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            header::HeaderValue::from_str(&format!("{}", API_KEY.as_str()))?,
-        );
+        self.get(&format!("/officers/{}/appointments", officer_id), &[])
+            .await
+    }
 
-        let response = self.client.get(url).headers(headers).send().await?;
-        let appointments: AppointmentsResponse = response.json().await?;
-        Ok(appointments)
+    async fn get_filing_history(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<FilingHistoryResponse>>, failure::Error> {
+        self.get(&format!("/company/{}/filing-history", company_number), &[])
+            .await
     }
 
-    pub async fn get_filing_history(
+    async fn get_charges(
         &self,
-        company_number: &String,
-    ) -> Result<FilingHistoryResponse, failure::Error> {
-        let url = format!(
-            "https://api.company-information.service.gov.uk/company/{}/filing-history",
-            company_number
-        );
+        company_number: &str,
+    ) -> Result<Option<Fetched<ChargeList>>, failure::Error> {
+        self.get(&format!("/company/{}/charges", company_number), &[])
+            .await
+    }
 
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            header::HeaderValue::from_str(&format!("{}", API_KEY.as_str()))?,
-        );
+    async fn get_insolvency(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyInsolvency>>, failure::Error> {
+        self.get(&format!("/company/{}/insolvency", company_number), &[])
+            .await
+    }
 
-        let response = self.client.get(url).headers(headers).send().await?;
+    async fn search_disqualified_officers(
+        &self,
+        name: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficerSearch>>, failure::Error> {
+        self.get("/search/disqualified-officers", &[("q", name)])
+            .await
+    }
 
-        let filing_history: FilingHistoryResponse = response.json().await?;
-        Ok(filing_history)
+    async fn get_disqualifications(
+        &self,
+        disqualified_officer_link: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficer>>, failure::Error> {
+        self.get(disqualified_officer_link, &[]).await
     }
 }
 
@@ -29,7 +29,7 @@ pub struct CompanyItem {
     pub title: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Address {
     pub address_line_1: Option<String>,
     pub address_line_2: Option<String>,
@@ -335,3 +335,138 @@ pub struct Resolution {
     pub subcategory: Option<String>,
     pub r#type: Option<String>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChargeList {
+    pub etag: Option<String>,
+    pub items: Option<Vec<ChargeDetails>>,
+    pub part_satisfied_count: Option<i32>,
+    pub satisfied_count: Option<i32>,
+    pub total_count: Option<i32>,
+    pub unfiltered_count: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChargeDetails {
+    pub acquired_on: Option<NaiveDate>,
+    pub charge_code: Option<String>,
+    pub charge_number: Option<i32>,
+    pub classification: Option<ChargeClassification>,
+    pub created_on: Option<NaiveDate>,
+    pub delivered_on: Option<NaiveDate>,
+    pub etag: Option<String>,
+    pub id: Option<String>,
+    pub particulars: Option<ChargeParticulars>,
+    pub persons_entitled: Option<Vec<PersonEntitled>>,
+    pub satisfied_on: Option<NaiveDate>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChargeClassification {
+    pub description: Option<String>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChargeParticulars {
+    pub contains_fixed_charge: Option<bool>,
+    pub contains_floating_charge: Option<bool>,
+    pub contains_negative_pledge: Option<bool>,
+    pub description: Option<String>,
+    pub floating_charge_covers_all: Option<bool>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PersonEntitled {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompanyInsolvency {
+    pub cases: Option<Vec<InsolvencyCase>>,
+    pub etag: Option<String>,
+    pub status: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsolvencyCase {
+    pub dates: Option<Vec<InsolvencyCaseDate>>,
+    pub notes: Option<Vec<String>>,
+    pub number: Option<String>,
+    pub practitioners: Option<Vec<Practitioner>>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsolvencyCaseDate {
+    pub date: Option<NaiveDate>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Practitioner {
+    pub address: Option<Address>,
+    pub appointed_on: Option<NaiveDate>,
+    pub ceased_to_act_on: Option<NaiveDate>,
+    pub name: Option<String>,
+    pub role: Option<String>,
+}
+
+// Disqualified officers have their own ids, unrelated to the officer ids of appointments,
+// so are found by searching for the officer's name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisqualifiedOfficerSearch {
+    pub items: Option<Vec<DisqualifiedOfficerSearchItem>>,
+    pub total_results: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisqualifiedOfficerSearchItem {
+    pub title: Option<String>,
+    pub date_of_birth: Option<NaiveDate>,
+    pub links: Option<DisqualifiedOfficerSearchLinks>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisqualifiedOfficerSearchLinks {
+    // the path of the disqualified officer, e.g. /disqualified-officers/natural/{id}
+    #[serde(rename = "self")]
+    pub self_: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisqualifiedOfficer {
+    pub date_of_birth: Option<NaiveDate>,
+    pub disqualifications: Option<Vec<DisqualificationItem>>,
+    pub etag: Option<String>,
+    pub forename: Option<String>,
+    pub kind: Option<String>,
+    pub nationality: Option<String>,
+    pub other_forenames: Option<String>,
+    pub surname: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisqualificationItem {
+    pub address: Option<Address>,
+    pub case_identifier: Option<String>,
+    pub company_names: Option<Vec<String>>,
+    pub court_name: Option<String>,
+    pub disqualification_type: Option<String>,
+    pub disqualified_from: Option<NaiveDate>,
+    pub disqualified_until: Option<NaiveDate>,
+    pub heard_on: Option<NaiveDate>,
+    pub reason: Option<DisqualificationReason>,
+    pub undertaken_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisqualificationReason {
+    pub act: Option<String>,
+    pub article: Option<String>,
+    pub description_identifier: Option<String>,
+    pub section: Option<String>,
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::history::entities_connected_at;
+use crate::check_response::group_by_entity;
+use crate::models::{Entity, Flagkind, Relationship};
+use crate::postgres::Database;
+
+pub struct GraphEntity {
+    pub entity: Entity,
+    pub flags: Vec<Flagkind>,
+}
+
+// All of a check's entities and the relationships between them
+pub struct CheckGraph {
+    pub check_id: Uuid,
+    pub entities: Vec<GraphEntity>,
+    pub relationships: Vec<Relationship>,
+}
+
+impl CheckGraph {
+    // The flags of every entity are loaded at once and grouped by entity
+    pub fn load(database: &mut Database, check_id: Uuid) -> Result<Self, failure::Error> {
+        let mut flags = group_by_entity(
+            database
+                .get_flags_for_check(&check_id)?
+                .into_iter()
+                .map(|(entity_id, _, flag_kind)| (entity_id, flag_kind)),
+        );
+        let entities = database
+            .get_entities(check_id)?
+            .into_iter()
+            .map(|entity| GraphEntity {
+                flags: flags.remove(&entity.id).unwrap_or_default(),
+                entity,
+            })
+            .collect();
+        let relationships = database.get_check_relationships(&check_id)?;
+
+        Ok(Self {
+            check_id,
+            entities,
+            relationships,
+        })
+    }
+
+    // The graph as it stood on the date, with only the relationships active then and the
+    // entities they connected to the root entity
+    pub fn as_of(self, date: NaiveDate) -> Self {
+        let relationships: Vec<Relationship> = self
+            .relationships
+            .into_iter()
+            .filter(|relationship| relationship.is_active_at(date))
+            .collect();
+        let connected = match self
+            .entities
+            .iter()
+            .find(|graph_entity| graph_entity.entity.is_root)
+        {
+            Some(root) => entities_connected_at(root.entity.id, &relationships, date),
+            None => Default::default(),
+        };
+
+        Self {
+            check_id: self.check_id,
+            entities: self
+                .entities
+                .into_iter()
+                .filter(|graph_entity| connected.contains(&graph_entity.entity.id))
+                .collect(),
+            relationships,
+        }
+    }
+}
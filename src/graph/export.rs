@@ -0,0 +1,361 @@
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use super::check_graph::{CheckGraph, GraphEntity};
+use crate::models::{Entitykind, OwnershipBand, Relationship};
+
+// Formats a check's graph can be exported in, for loading into external tools
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    // yEd, Cytoscape, networkx
+    GraphML,
+    // Gephi
+    Gexf,
+    // Neo4j, as a script of MERGE statements
+    Cypher,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "application/graphml+xml",
+            ExportFormat::Gexf => "application/gexf+xml",
+            ExportFormat::Cypher => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "graphml",
+            ExportFormat::Gexf => "gexf",
+            ExportFormat::Cypher => "cypher",
+        }
+    }
+
+    pub fn export(&self, graph: &CheckGraph) -> String {
+        match self {
+            ExportFormat::GraphML => to_graphml(graph),
+            ExportFormat::Gexf => to_gexf(graph),
+            ExportFormat::Cypher => to_cypher(graph),
+        }
+    }
+}
+
+const NODE_ATTRIBUTES: [&str; 8] = [
+    "kind",
+    "entity_number",
+    "name",
+    "country",
+    "postal_code",
+    "date_of_origin",
+    "is_root",
+    "flags",
+];
+const EDGE_ATTRIBUTES: [&str; 4] = ["kind", "started_on", "ended_on", "ownership_band"];
+
+// Values in the same order as NODE_ATTRIBUTES, None when unknown
+fn node_values(graph_entity: &GraphEntity) -> [Option<String>; 8] {
+    let entity = &graph_entity.entity;
+    let flags: Vec<String> = graph_entity
+        .flags
+        .iter()
+        .map(|flag| format!("{:?}", flag))
+        .collect();
+
+    [
+        Some(entity.kind.as_str().to_string()),
+        Some(entity.company_house_number.clone()),
+        entity.name.clone(),
+        entity.country.clone(),
+        entity.postal_code.clone(),
+        entity.date_of_origin.map(|date| date.to_string()),
+        Some(entity.is_root.to_string()),
+        (!flags.is_empty()).then(|| flags.join(",")),
+    ]
+}
+
+// Values in the same order as EDGE_ATTRIBUTES, None when unknown
+fn edge_values(relationship: &Relationship) -> [Option<String>; 4] {
+    [
+        Some(relationship.kind.as_str().to_string()),
+        relationship.started_on.map(|date| date.to_string()),
+        relationship.ended_on.map(|date| date.to_string()),
+        relationship.ownership_band.map(ownership_band_str),
+    ]
+}
+
+fn ownership_band_str(ownership_band: OwnershipBand) -> String {
+    let (min, max) = ownership_band.range();
+    format!("{}-{}%", min * 100.0, max * 100.0)
+}
+
+pub fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_cypher(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn edge_label(relationship: &Relationship) -> String {
+    relationship.kind.as_str().to_string()
+}
+
+pub fn to_graphml(graph: &CheckGraph) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for attribute in NODE_ATTRIBUTES {
+        let _ = writeln!(
+            out,
+            "  <key id=\"node_{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"string\"/>",
+            attribute
+        );
+    }
+    for attribute in EDGE_ATTRIBUTES {
+        let _ = writeln!(
+            out,
+            "  <key id=\"edge_{0}\" for=\"edge\" attr.name=\"{0}\" attr.type=\"string\"/>",
+            attribute
+        );
+    }
+    let _ = writeln!(
+        out,
+        "  <graph id=\"{}\" edgedefault=\"directed\">",
+        graph.check_id
+    );
+
+    for graph_entity in &graph.entities {
+        let _ = writeln!(out, "    <node id=\"{}\">", graph_entity.entity.id);
+        for (attribute, value) in NODE_ATTRIBUTES.iter().zip(node_values(graph_entity)) {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"node_{}\">{}</data>",
+                    attribute,
+                    escape_xml(&value)
+                );
+            }
+        }
+        out.push_str("    </node>\n");
+    }
+
+    for relationship in &graph.relationships {
+        let _ = writeln!(
+            out,
+            "    <edge source=\"{}\" target=\"{}\">",
+            relationship.parent_id, relationship.child_id
+        );
+        for (attribute, value) in EDGE_ATTRIBUTES.iter().zip(edge_values(relationship)) {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"edge_{}\">{}</data>",
+                    attribute,
+                    escape_xml(&value)
+                );
+            }
+        }
+        out.push_str("    </edge>\n");
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn to_gexf(graph: &CheckGraph) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    let _ = writeln!(
+        out,
+        "  <meta>\n    <description>Check {}</description>\n  </meta>",
+        graph.check_id
+    );
+    out.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+
+    out.push_str("    <attributes class=\"node\">\n");
+    for (id, attribute) in NODE_ATTRIBUTES.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <attribute id=\"{}\" title=\"{}\" type=\"string\"/>",
+            id, attribute
+        );
+    }
+    out.push_str("    </attributes>\n");
+    out.push_str("    <attributes class=\"edge\">\n");
+    for (id, attribute) in EDGE_ATTRIBUTES.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <attribute id=\"{}\" title=\"{}\" type=\"string\"/>",
+            id, attribute
+        );
+    }
+    out.push_str("    </attributes>\n");
+
+    out.push_str("    <nodes>\n");
+    for graph_entity in &graph.entities {
+        let label = graph_entity
+            .entity
+            .name
+            .as_ref()
+            .unwrap_or(&graph_entity.entity.company_house_number);
+        let _ = writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\">\n        <attvalues>",
+            graph_entity.entity.id,
+            escape_xml(label)
+        );
+        for (id, value) in node_values(graph_entity).into_iter().enumerate() {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "          <attvalue for=\"{}\" value=\"{}\"/>",
+                    id,
+                    escape_xml(&value)
+                );
+            }
+        }
+        out.push_str("        </attvalues>\n      </node>\n");
+    }
+    out.push_str("    </nodes>\n");
+
+    out.push_str("    <edges>\n");
+    for (edge_id, relationship) in graph.relationships.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\">\n        <attvalues>",
+            edge_id,
+            relationship.parent_id,
+            relationship.child_id,
+            edge_label(relationship)
+        );
+        for (id, value) in edge_values(relationship).into_iter().enumerate() {
+            if let Some(value) = value {
+                let _ = writeln!(
+                    out,
+                    "          <attvalue for=\"{}\" value=\"{}\"/>",
+                    id,
+                    escape_xml(&value)
+                );
+            }
+        }
+        out.push_str("        </attvalues>\n      </edge>\n");
+    }
+    out.push_str("    </edges>\n");
+
+    out.push_str("  </graph>\n</gexf>\n");
+    out
+}
+
+// Entities are merged on their id so the script can be re-run, or run for several
+// checks into the same database
+pub fn to_cypher(graph: &CheckGraph) -> String {
+    let mut out = String::new();
+
+    for graph_entity in &graph.entities {
+        let label = match graph_entity.entity.kind {
+            Entitykind::Company => "Company",
+            Entitykind::Individual => "Individual",
+        };
+        let mut properties = vec![format!("e.check_id = '{}'", graph.check_id)];
+        for (attribute, value) in NODE_ATTRIBUTES.iter().zip(node_values(graph_entity)) {
+            if let Some(value) = value {
+                properties.push(format!("e.{} = '{}'", attribute, escape_cypher(&value)));
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "MERGE (e:Entity {{id: '{}'}}) SET e:{}, {};",
+            graph_entity.entity.id,
+            label,
+            properties.join(", ")
+        );
+    }
+
+    for relationship in &graph.relationships {
+        let mut properties = Vec::new();
+        for (attribute, value) in EDGE_ATTRIBUTES.iter().zip(edge_values(relationship)) {
+            if let Some(value) = value {
+                properties.push(format!("r.{} = '{}'", attribute, escape_cypher(&value)));
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "MATCH (p:Entity {{id: '{}'}}), (c:Entity {{id: '{}'}}) MERGE (p)-[r:{}]->(c) SET {};",
+            relationship.parent_id,
+            relationship.child_id,
+            edge_label(relationship).to_uppercase(),
+            properties.join(", ")
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Entity, Relationshipkind};
+    use uuid::Uuid;
+
+    fn graph() -> CheckGraph {
+        let company = Entity {
+            id: Uuid::new_v4(),
+            company_house_number: "00000001".to_string(),
+            name: Some("Smith & Sons <Holdings>".to_string()),
+            is_root: true,
+            ..Default::default()
+        };
+        let officer = Entity {
+            id: Uuid::new_v4(),
+            company_house_number: "123456780001".to_string(),
+            name: Some("O'Brien, Jane".to_string()),
+            kind: Entitykind::Individual,
+            ..Default::default()
+        };
+        let relationship = Relationship {
+            parent_id: officer.id,
+            child_id: company.id,
+            kind: Relationshipkind::Officer,
+            started_on: None,
+            ended_on: None,
+            ownership_band: None,
+        };
+
+        CheckGraph {
+            check_id: Uuid::new_v4(),
+            entities: vec![
+                GraphEntity {
+                    entity: company,
+                    flags: vec![],
+                },
+                GraphEntity {
+                    entity: officer,
+                    flags: vec![],
+                },
+            ],
+            relationships: vec![relationship],
+        }
+    }
+
+    #[test]
+    fn exports_escape_names() {
+        let graph = graph();
+
+        assert!(to_graphml(&graph).contains("Smith &amp; Sons &lt;Holdings&gt;"));
+        assert!(to_gexf(&graph).contains("label=\"O&apos;Brien, Jane\""));
+        let cypher = to_cypher(&graph);
+        assert!(cypher.contains("e.name = 'O\\'Brien, Jane'"));
+        assert!(cypher.contains("MERGE (p)-[r:OFFICER]->(c)"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::Relationship;
+
+// Entities connected to the root entity through relationships active on the date. Entities
+// only linked through relationships which had ended, or not yet started, are left out
+pub fn entities_connected_at(
+    root_id: Uuid,
+    relationships: &[Relationship],
+    date: NaiveDate,
+) -> HashSet<Uuid> {
+    let mut adjacent: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for relationship in relationships
+        .iter()
+        .filter(|relationship| relationship.is_active_at(date))
+    {
+        adjacent
+            .entry(relationship.parent_id)
+            .or_default()
+            .push(relationship.child_id);
+        adjacent
+            .entry(relationship.child_id)
+            .or_default()
+            .push(relationship.parent_id);
+    }
+
+    let mut connected = HashSet::from([root_id]);
+    let mut stack = vec![root_id];
+    while let Some(entity_id) = stack.pop() {
+        for neighbour_id in adjacent.get(&entity_id).into_iter().flatten() {
+            if connected.insert(*neighbour_id) {
+                stack.push(*neighbour_id);
+            }
+        }
+    }
+
+    connected
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuralChange {
+    // an officer was appointed, a shareholder acquired control, a charge was created
+    Started,
+    // an officer resigned, a shareholder ceased, a charge was satisfied
+    Ended,
+}
+
+#[derive(Serialize)]
+pub struct TimelineEvent {
+    pub date: NaiveDate,
+    pub change: StructuralChange,
+    pub relationship: Relationship,
+}
+
+// Every dated start and end of a relationship, oldest first. Relationships without dates
+// can't be placed on the timeline and are left out
+pub fn timeline(relationships: &[Relationship]) -> Vec<TimelineEvent> {
+    let mut events = Vec::new();
+    for relationship in relationships {
+        if let Some(started_on) = relationship.started_on {
+            events.push(TimelineEvent {
+                date: started_on,
+                change: StructuralChange::Started,
+                relationship: relationship.clone(),
+            });
+        }
+        if let Some(ended_on) = relationship.ended_on {
+            events.push(TimelineEvent {
+                date: ended_on,
+                change: StructuralChange::Ended,
+                relationship: relationship.clone(),
+            });
+        }
+    }
+
+    // on the same day, endings are listed before the changes that replaced them
+    events.sort_by_key(|event| (event.date, event.change == StructuralChange::Started));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Relationshipkind;
+
+    fn relationship(
+        parent_id: Uuid,
+        child_id: Uuid,
+        started_on: Option<NaiveDate>,
+        ended_on: Option<NaiveDate>,
+    ) -> Relationship {
+        Relationship {
+            parent_id,
+            child_id,
+            kind: Relationshipkind::Shareholder,
+            started_on,
+            ended_on,
+            ownership_band: None,
+        }
+    }
+
+    #[test]
+    fn reconstructs_structure_at_date() {
+        let [root, holding, former_owner, holding_owner] = [(); 4].map(|_| Uuid::new_v4());
+        let relationships = vec![
+            relationship(holding, root, NaiveDate::from_ymd_opt(2018, 3, 1), None),
+            relationship(
+                former_owner,
+                root,
+                NaiveDate::from_ymd_opt(2010, 1, 1),
+                NaiveDate::from_ymd_opt(2018, 3, 1),
+            ),
+            relationship(holding_owner, holding, None, None),
+        ];
+
+        let in_2015 = entities_connected_at(
+            root,
+            &relationships,
+            NaiveDate::from_ymd_opt(2015, 1, 1).unwrap(),
+        );
+        assert_eq!(in_2015, HashSet::from([root, former_owner]));
+
+        let in_2020 = entities_connected_at(
+            root,
+            &relationships,
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+        );
+        assert_eq!(in_2020, HashSet::from([root, holding, holding_owner]));
+
+        let events = timeline(&relationships);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].relationship.parent_id, former_owner);
+        assert_eq!(events[1].change, StructuralChange::Ended);
+        assert_eq!(events[2].relationship.parent_id, holding);
+    }
+}
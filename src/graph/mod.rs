@@ -0,0 +1,4 @@
+pub mod check_graph;
+pub mod export;
+pub mod history;
+pub mod query;
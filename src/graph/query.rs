@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::{Relationship, Relationshipkind};
+use crate::postgres::Database;
+
+// Limits so a query over a densely connected graph stays bounded
+pub const MAX_PATH_DEPTH: usize = 10;
+pub const MAX_NEIGHBOURHOOD_HOPS: usize = 5;
+const MAX_PATHS: usize = 20;
+
+// How one entity is connected to the next, relationships are followed in either direction
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GraphEdge {
+    Relationship(Relationship),
+    // the same canonical entity appearing in two checks
+    SameEntity {
+        entity_id: Uuid,
+        same_entity_id: Uuid,
+    },
+}
+
+#[derive(Default)]
+pub struct RelationshipFilter {
+    // only follow relationships of these kinds, all kinds when None
+    pub kinds: Option<Vec<Relationshipkind>>,
+    // only follow relationships which were active on this date
+    pub active_at: Option<NaiveDate>,
+    // follow entities resolved to the same canonical entity into other checks
+    pub across_checks: bool,
+}
+
+impl RelationshipFilter {
+    pub fn matches(&self, relationship: &Relationship) -> bool {
+        let kind_matches = match &self.kinds {
+            Some(kinds) => kinds.contains(&relationship.kind),
+            None => true,
+        };
+
+        let active_matches = match self.active_at {
+            Some(date) => relationship.is_active_at(date),
+            None => true,
+        };
+
+        kind_matches && active_matches
+    }
+}
+
+// Every entity connected to one of the given entities, with the edge connecting them
+fn neighbours(
+    database: &mut Database,
+    entity_ids: &[Uuid],
+    filter: &RelationshipFilter,
+) -> Result<HashMap<Uuid, Vec<(Uuid, GraphEdge)>>, failure::Error> {
+    let entity_ids_set: HashSet<&Uuid> = entity_ids.iter().collect();
+    let mut neighbours: HashMap<Uuid, Vec<(Uuid, GraphEdge)>> = HashMap::new();
+
+    for relationship in database.get_relationships_of(entity_ids)? {
+        if !filter.matches(&relationship) {
+            continue;
+        }
+
+        for (from, to) in [
+            (relationship.parent_id, relationship.child_id),
+            (relationship.child_id, relationship.parent_id),
+        ] {
+            if entity_ids_set.contains(&from) {
+                neighbours
+                    .entry(from)
+                    .or_default()
+                    .push((to, GraphEdge::Relationship(relationship.clone())));
+            }
+        }
+    }
+
+    if filter.across_checks {
+        for (entity_id, same_entity_id) in database.get_same_entities(entity_ids)? {
+            neighbours.entry(entity_id).or_default().push((
+                same_entity_id,
+                GraphEdge::SameEntity {
+                    entity_id,
+                    same_entity_id,
+                },
+            ));
+        }
+    }
+
+    Ok(neighbours)
+}
+
+#[derive(Serialize)]
+pub struct GraphPath {
+    // from the start entity to the end entity
+    pub entity_ids: Vec<Uuid>,
+    // edges[i] connects entity_ids[i] and entity_ids[i + 1]
+    pub edges: Vec<GraphEdge>,
+}
+
+// All of the shortest paths between two entities, up to max_depth edges long
+pub fn shortest_paths(
+    database: &mut Database,
+    from: Uuid,
+    to: Uuid,
+    max_depth: usize,
+    filter: &RelationshipFilter,
+) -> Result<Vec<GraphPath>, failure::Error> {
+    shortest_paths_with(from, to, max_depth.min(MAX_PATH_DEPTH), |entity_ids| {
+        neighbours(database, entity_ids, filter)
+    })
+}
+
+// Breadth first search, recording every predecessor on a shortest path to each entity
+fn shortest_paths_with(
+    from: Uuid,
+    to: Uuid,
+    max_depth: usize,
+    mut get_neighbours: impl FnMut(
+        &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<(Uuid, GraphEdge)>>, failure::Error>,
+) -> Result<Vec<GraphPath>, failure::Error> {
+    if from == to {
+        return Ok(vec![GraphPath {
+            entity_ids: vec![from],
+            edges: vec![],
+        }]);
+    }
+
+    let mut depths: HashMap<Uuid, usize> = HashMap::from([(from, 0)]);
+    let mut predecessors: HashMap<Uuid, Vec<(Uuid, GraphEdge)>> = HashMap::new();
+    let mut layer = vec![from];
+
+    for depth in 1..=max_depth {
+        if layer.is_empty() || depths.contains_key(&to) {
+            break;
+        }
+
+        let mut next_layer = Vec::new();
+        for (entity_id, entity_neighbours) in get_neighbours(&layer)? {
+            for (neighbour_id, edge) in entity_neighbours {
+                match depths.get(&neighbour_id) {
+                    Some(neighbour_depth) if *neighbour_depth < depth => continue,
+                    Some(_) => {}
+                    None => {
+                        depths.insert(neighbour_id, depth);
+                        next_layer.push(neighbour_id);
+                    }
+                }
+                predecessors
+                    .entry(neighbour_id)
+                    .or_default()
+                    .push((entity_id, edge));
+            }
+        }
+        layer = next_layer;
+    }
+
+    if !depths.contains_key(&to) {
+        return Ok(vec![]);
+    }
+
+    // walk the predecessors back from the end entity
+    let mut paths = Vec::new();
+    let mut partial_paths = vec![(vec![to], vec![])];
+    while let Some((entity_ids, edges)) = partial_paths.pop() {
+        if paths.len() >= MAX_PATHS {
+            break;
+        }
+
+        let first = entity_ids[0];
+        if first == from {
+            paths.push(GraphPath { entity_ids, edges });
+            continue;
+        }
+
+        for (predecessor_id, edge) in predecessors.get(&first).into_iter().flatten() {
+            let mut entity_ids = entity_ids.clone();
+            entity_ids.insert(0, *predecessor_id);
+            let mut edges = edges.clone();
+            edges.insert(0, edge.clone());
+            partial_paths.push((entity_ids, edges));
+        }
+    }
+
+    Ok(paths)
+}
+
+#[derive(Serialize)]
+pub struct Neighbourhood {
+    // each entity reached and how many hops it is from the centre
+    pub entities: HashMap<Uuid, usize>,
+    pub edges: Vec<GraphEdge>,
+}
+
+// Every entity within the given number of hops of an entity, and the edges between them
+pub fn neighbourhood(
+    database: &mut Database,
+    entity_id: Uuid,
+    hops: usize,
+    filter: &RelationshipFilter,
+) -> Result<Neighbourhood, failure::Error> {
+    let mut entities: HashMap<Uuid, usize> = HashMap::from([(entity_id, 0)]);
+    let mut edges = Vec::new();
+    let mut seen_relationships: HashSet<(Uuid, Uuid, &'static str)> = HashSet::new();
+    let mut layer = vec![entity_id];
+
+    for hop in 1..=hops.min(MAX_NEIGHBOURHOOD_HOPS) {
+        if layer.is_empty() {
+            break;
+        }
+
+        let mut next_layer = Vec::new();
+        for (_, entity_neighbours) in neighbours(database, &layer, filter)? {
+            for (neighbour_id, edge) in entity_neighbours {
+                if !entities.contains_key(&neighbour_id) {
+                    entities.insert(neighbour_id, hop);
+                    next_layer.push(neighbour_id);
+                }
+
+                // relationships between two entities of the same layer are seen from both ends
+                let is_new_edge = match &edge {
+                    GraphEdge::Relationship(relationship) => seen_relationships.insert((
+                        relationship.parent_id,
+                        relationship.child_id,
+                        relationship.kind.as_str(),
+                    )),
+                    GraphEdge::SameEntity { .. } => true,
+                };
+                if is_new_edge {
+                    edges.push(edge);
+                }
+            }
+        }
+        layer = next_layer;
+    }
+
+    Ok(Neighbourhood { entities, edges })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(parent_id: Uuid, child_id: Uuid) -> GraphEdge {
+        GraphEdge::Relationship(Relationship {
+            parent_id,
+            child_id,
+            kind: Relationshipkind::Officer,
+            started_on: None,
+            ended_on: None,
+            ownership_band: None,
+        })
+    }
+
+    #[test]
+    fn finds_every_shortest_path() {
+        // a diamond from a to d, plus a longer route through e
+        let [a, b, c, d, e] = [(); 5].map(|_| Uuid::new_v4());
+        let graph: HashMap<Uuid, Vec<(Uuid, GraphEdge)>> = HashMap::from([
+            (a, vec![(b, edge(a, b)), (c, edge(a, c)), (e, edge(a, e))]),
+            (b, vec![(d, edge(b, d))]),
+            (c, vec![(d, edge(c, d))]),
+            (e, vec![(b, edge(e, b))]),
+        ]);
+
+        let paths = shortest_paths_with(a, d, 5, |entity_ids| {
+            Ok(entity_ids
+                .iter()
+                .filter_map(|entity_id| Some((*entity_id, graph.get(entity_id)?.clone())))
+                .collect())
+        })
+        .unwrap();
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| path.entity_ids.len() == 3));
+        assert!(paths.iter().all(|path| path.edges.len() == 2));
+    }
+
+    #[test]
+    fn filters_relationships_inactive_at_date() {
+        let filter = RelationshipFilter {
+            active_at: NaiveDate::from_ymd_opt(2020, 1, 1),
+            ..Default::default()
+        };
+        let mut relationship = Relationship {
+            parent_id: Uuid::new_v4(),
+            child_id: Uuid::new_v4(),
+            kind: Relationshipkind::Officer,
+            started_on: NaiveDate::from_ymd_opt(2015, 1, 1),
+            ended_on: None,
+            ownership_band: None,
+        };
+
+        assert!(filter.matches(&relationship));
+        relationship.ended_on = NaiveDate::from_ymd_opt(2019, 1, 1);
+        assert!(!filter.matches(&relationship));
+    }
+}
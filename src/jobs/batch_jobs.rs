@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use failure::format_err;
+use log::info;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    batch::find_company,
+    jobs::{
+        jobs::{Job, JobKind},
+        relation_jobs::{prepare_check, TraversalPolicy},
+    },
+    models::{Batch, BatchRow, Priority},
+    postgres::Database,
+    pulsar::{record_job, PulsarProducer},
+    registry::registry_client::RegistryClient,
+    workers::batch_worker::BatchWorker,
+};
+
+// How long a row waits before it's tried again, when its tenant has as many batch checks
+// running as it can
+pub const DEFERRED_ROW_DELAY: Duration = Duration::from_secs(30);
+
+// Starts one row of a batch as a check
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchJob {
+    pub batch_id: Uuid,
+    pub row_number: i32,
+}
+
+// What became of a batch job's row
+#[derive(Debug, PartialEq)]
+pub enum RowStart {
+    // started as a check, or recorded as failing to start
+    Done,
+    // already taken by another delivery of the job
+    AlreadyStarted,
+    // its tenant has as many batch checks running as it can, so it's to be tried again later
+    Deferred,
+}
+
+// Stores the batch, and queues a job to start each of its rows for the batch service to
+// work through
+pub async fn submit_batch(
+    database: &mut Database,
+    producer: &mut PulsarProducer,
+    batch: &Batch,
+    rows: &[BatchRow],
+) -> Result<(), failure::Error> {
+    for job in prepare_batch(database, batch, rows)? {
+        producer.send_job(job).await?;
+    }
+
+    Ok(())
+}
+
+// Records the batch and the jobs to start its rows, which are left to the caller to send
+pub fn prepare_batch(
+    database: &mut Database,
+    batch: &Batch,
+    rows: &[BatchRow],
+) -> Result<Vec<Job>, failure::Error> {
+    database.insert_batch(batch, rows)?;
+
+    rows.iter()
+        .map(|row| {
+            let job_kind = JobKind::BatchJob(BatchJob {
+                batch_id: batch.id,
+                row_number: row.row_number,
+            });
+            record_job(database, None, job_kind)
+        })
+        .collect()
+}
+
+impl BatchJob {
+    pub async fn do_job<C: RegistryClient>(
+        &self,
+        worker: &mut BatchWorker<C>,
+    ) -> Result<RowStart, failure::Error> {
+        let row = worker
+            .database
+            .get_batch_row(self.batch_id, self.row_number)?;
+        if row.started_at.is_some() {
+            return Ok(RowStart::AlreadyStarted);
+        }
+
+        // a tenant only has so many batch checks running at once, so its batches can't
+        // crowd out interactive checks. The row waits its turn without holding up the others
+        let tenant_id = worker.database.get_batch(self.batch_id)?.tenant_id;
+        if worker.database.count_running_batch_checks(tenant_id)? >= worker.max_running_checks {
+            return Ok(RowStart::Deferred);
+        }
+
+        // redelivered jobs, or more than one batch service, may try to start the row at once
+        if !worker
+            .database
+            .claim_batch_row(self.batch_id, self.row_number)?
+        {
+            return Ok(RowStart::AlreadyStarted);
+        }
+
+        let started = self
+            .start_row(row, tenant_id, worker)
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(e) = &started {
+            info!(
+                "Batch {} row {} couldn't be started: {}",
+                self.batch_id, self.row_number, e
+            );
+        }
+        worker
+            .database
+            .record_batch_row_start(self.batch_id, self.row_number, started)?;
+
+        Ok(RowStart::Done)
+    }
+
+    async fn start_row<C: RegistryClient>(
+        &self,
+        row: BatchRow,
+        tenant_id: Uuid,
+        worker: &mut BatchWorker<C>,
+    ) -> Result<Uuid, failure::Error> {
+        let company_number = match (row.company_number, row.name, row.postcode) {
+            (Some(company_number), _, _) => company_number,
+            (None, Some(name), Some(postcode)) => {
+                let items = match worker.registry_client.get_company(&name).await? {
+                    Some(search) => search.response.items.unwrap_or_default(),
+                    None => vec![],
+                };
+                find_company(&items, &name, &postcode)?
+                    .company_number
+                    .clone()
+            }
+            _ => {
+                return Err(format_err!(
+                    "Row has no company number, or name and postcode"
+                ))
+            }
+        };
+        let traversal_policy: TraversalPolicy = serde_json::from_value(row.traversal_policy)?;
+
+        // the check belongs to the batch's tenant
+        let (check_id, jobs) = worker.database.with_tenant(tenant_id, |database| {
+            prepare_check(database, company_number, traversal_policy, Priority::Batch)
+        })?;
+        for job in jobs {
+            worker.entity_relation_producer.send_job(job).await?;
+        }
+
+        Ok(check_id)
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use failure::format_err;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{Exposure, Flagkind, Relationship, Relationshipkind, ADVERSE_FLAGS};
+
+const MAX_PROPAGATION_HOPS: usize = 10;
+
+// How flags on an entity are pushed onto the companies it owns, runs or lends to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PropagationPolicy {
+    // flags which expose the companies related to the flagged entity
+    pub origin_flags: Vec<Flagkind>,
+    // how much of the exposure crosses a relationship of each kind, kinds not listed
+    // aren't followed
+    pub kind_weights: HashMap<Relationshipkind, f64>,
+    // applied once for every relationship after the first
+    pub hop_decay: f64,
+    // shareholdings further scale the exposure by the upper bound of the ownership band
+    pub scale_by_ownership: bool,
+    // exposures with a lower score are dropped, and not propagated any further
+    pub min_score: f64,
+    pub max_hops: usize,
+}
+
+impl Default for PropagationPolicy {
+    fn default() -> Self {
+        Self {
+            origin_flags: ADVERSE_FLAGS.to_vec(),
+            kind_weights: HashMap::from([
+                (Relationshipkind::Shareholder, 1.0),
+                (Relationshipkind::Officer, 0.7),
+                (Relationshipkind::ChargeHolder, 0.3),
+            ]),
+            hop_decay: 0.5,
+            scale_by_ownership: true,
+            min_score: 0.05,
+            max_hops: 4,
+        }
+    }
+}
+
+impl PropagationPolicy {
+    pub fn validate(&self) -> Result<(), failure::Error> {
+        if !(0.0..=1.0).contains(&self.hop_decay) {
+            return Err(format_err!("hop_decay must be between 0 and 1"));
+        }
+        for (kind, weight) in &self.kind_weights {
+            if !(0.0..=1.0).contains(weight) {
+                return Err(format_err!("Weight of {:?} must be between 0 and 1", kind));
+            }
+        }
+        if self.max_hops > MAX_PROPAGATION_HOPS {
+            return Err(format_err!(
+                "max_hops can't be more than {}",
+                MAX_PROPAGATION_HOPS
+            ));
+        }
+
+        Ok(())
+    }
+
+    // The share of exposure which crosses the relationship from the parent to the child
+    fn weight(&self, relationship: &Relationship) -> Option<f64> {
+        let weight = self.kind_weights.get(&relationship.kind)?;
+        match relationship.ownership_band {
+            Some(ownership_band) if self.scale_by_ownership => {
+                Some(weight * ownership_band.range().1)
+            }
+            _ => Some(*weight),
+        }
+    }
+}
+
+// Pushes each flag from the flagged entity down to the companies it's a parent of, and on
+// to their own children. Only the highest scoring path from a flag to each company is kept
+pub fn compute_exposures(
+    check_id: Uuid,
+    flags: &[(Uuid, Uuid, Flagkind)],
+    relationships: &[Relationship],
+    policy: &PropagationPolicy,
+) -> Vec<Exposure> {
+    let mut children: HashMap<Uuid, Vec<(Uuid, f64)>> = HashMap::new();
+    for relationship in relationships {
+        if let Some(weight) = policy.weight(relationship) {
+            children
+                .entry(relationship.parent_id)
+                .or_default()
+                .push((relationship.child_id, weight));
+        }
+    }
+
+    // keyed by (exposed entity, origin flag)
+    let mut exposures: HashMap<(Uuid, Uuid), Exposure> = HashMap::new();
+    for (origin_entity_id, origin_flag_id, flag_kind) in flags {
+        if !policy.origin_flags.contains(flag_kind) {
+            continue;
+        }
+
+        let mut stack = vec![(vec![*origin_entity_id], 1.0)];
+        while let Some((path, score)) = stack.pop() {
+            let entity_id = path[path.len() - 1];
+            for (child_id, weight) in children.get(&entity_id).into_iter().flatten() {
+                // circular relationships are only followed once around
+                if path.contains(child_id) {
+                    continue;
+                }
+
+                let decay = if path.len() > 1 {
+                    policy.hop_decay
+                } else {
+                    1.0
+                };
+                let child_score = score * weight * decay;
+                if child_score < policy.min_score {
+                    continue;
+                }
+
+                let mut child_path = path.clone();
+                child_path.push(*child_id);
+
+                let is_best = exposures
+                    .get(&(*child_id, *origin_flag_id))
+                    .map_or(true, |exposure| exposure.score < child_score);
+                if is_best {
+                    exposures.insert(
+                        (*child_id, *origin_flag_id),
+                        Exposure {
+                            id: Uuid::new_v4(),
+                            check_id,
+                            entity_id: *child_id,
+                            origin_entity_id: *origin_entity_id,
+                            origin_flag_id: *origin_flag_id,
+                            flag_kind: *flag_kind,
+                            path: child_path.clone(),
+                            score: child_score,
+                        },
+                    );
+                }
+
+                if child_path.len() <= policy.max_hops {
+                    stack.push((child_path, child_score));
+                }
+            }
+        }
+    }
+
+    exposures.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OwnershipBand;
+
+    fn relationship(
+        parent_id: Uuid,
+        child_id: Uuid,
+        kind: Relationshipkind,
+        ownership_band: Option<OwnershipBand>,
+    ) -> Relationship {
+        Relationship {
+            parent_id,
+            child_id,
+            kind,
+            started_on: None,
+            ended_on: None,
+            ownership_band,
+        }
+    }
+
+    #[test]
+    fn exposure_decays_along_ownership_chain() {
+        let [sanctioned, company, subsidiary, flag_id] = [(); 4].map(|_| Uuid::new_v4());
+        let relationships = vec![
+            relationship(
+                sanctioned,
+                company,
+                Relationshipkind::Shareholder,
+                Some(OwnershipBand::FiftyToSeventyFive),
+            ),
+            relationship(
+                company,
+                subsidiary,
+                Relationshipkind::Shareholder,
+                Some(OwnershipBand::SeventyFiveToHundred),
+            ),
+        ];
+        let flags = vec![(sanctioned, flag_id, Flagkind::SanctionedEntity)];
+
+        let exposures = compute_exposures(
+            Uuid::new_v4(),
+            &flags,
+            &relationships,
+            &PropagationPolicy::default(),
+        );
+
+        assert_eq!(exposures.len(), 2);
+        let company_exposure = exposures
+            .iter()
+            .find(|exposure| exposure.entity_id == company)
+            .unwrap();
+        assert_eq!(company_exposure.score, 0.75);
+        assert_eq!(company_exposure.path, vec![sanctioned, company]);
+        let subsidiary_exposure = exposures
+            .iter()
+            .find(|exposure| exposure.entity_id == subsidiary)
+            .unwrap();
+        assert_eq!(subsidiary_exposure.score, 0.375);
+        assert_eq!(subsidiary_exposure.origin_flag_id, flag_id);
+    }
+
+    #[test]
+    fn unlisted_flags_are_not_propagated() {
+        let [listed, company] = [(); 2].map(|_| Uuid::new_v4());
+        let relationships = vec![relationship(
+            listed,
+            company,
+            Relationshipkind::Officer,
+            None,
+        )];
+        let flags = vec![(listed, Uuid::new_v4(), Flagkind::PublicListedCompany)];
+
+        let exposures = compute_exposures(
+            Uuid::new_v4(),
+            &flags,
+            &relationships,
+            &PropagationPolicy::default(),
+        );
+
+        assert!(exposures.is_empty());
+    }
+}
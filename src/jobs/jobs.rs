@@ -2,14 +2,17 @@ use pulsar::{producer, DeserializeMessage, Error as PulsarError, SerializeMessag
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::jobs::relation_jobs::RelationJob;
+use crate::{jobs::relation_jobs::RelationJob, models::Priority};
 
-use super::{risk_jobs::RiskJob, streaming_update_jobs::StreamingUpdateJob};
+use super::{batch_jobs::BatchJob, risk_jobs::RiskJob, streaming_update_jobs::StreamingUpdateJob};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Job {
     pub id: Uuid,
     pub job_kind: JobKind,
+    // jobs queued before priorities were added are interactive
+    #[serde(default)]
+    pub priority: Priority,
 }
 
 impl SerializeMessage for Job {
@@ -35,4 +38,16 @@ pub enum JobKind {
     RelationJob(RelationJob),
     RiskJob(RiskJob),
     StreamingUpdateJob(StreamingUpdateJob),
+    BatchJob(BatchJob),
+}
+
+impl JobKind {
+    // The priority of a job that isn't part of a check
+    pub fn default_priority(&self) -> Priority {
+        match self {
+            JobKind::BatchJob(_) => Priority::Batch,
+            JobKind::StreamingUpdateJob(_) => Priority::Background,
+            JobKind::RelationJob(_) | JobKind::RiskJob(_) => Priority::Interactive,
+        }
+    }
 }
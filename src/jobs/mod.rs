@@ -1,3 +1,5 @@
+pub mod batch_jobs;
+pub mod exposure;
 pub mod jobs;
 pub mod relation_jobs;
 pub mod risk_jobs;
@@ -1,13 +1,29 @@
+use std::{cmp::min, collections::HashMap};
+
+use failure::format_err;
 use log::warn;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::jobs::jobs::JobKind;
-use crate::jobs::risk_jobs::LocalRiskJobKind::{Dormancy, Flags, OutlierAge};
-use crate::models::{Entity, EntityRelation, Entitykind, Relationship, Relationshipkind};
+use crate::company_house::company_house_streaming_types::CompanyData;
+use crate::jobs::jobs::{Job, JobKind};
+use crate::jobs::risk_jobs::LocalRiskJobKind::{
+    Disqualification, Dormancy, Flags, OutlierAge, RecentInsolvency, UnusualLenders,
+};
+use crate::models::{
+    Charge, Checkkind, CompanyProfile, Entity, EntityDetails, EntityRelation, Entitykind,
+    InsolvencyCaseRecord, OwnershipBand, PartialDate, PreviousName, Priority, Relationship,
+    Relationshipkind,
+};
+use crate::postgres::Database;
+use crate::pulsar::{record_job, PulsarProducer};
+use crate::registry::registry_client::RegistryClient;
 use crate::workers::entity_relation_worker::EntityRelationWorker;
 
-use super::risk_jobs::{LocalRiskJob, RiskJob, RiskJobScope};
+use super::risk_jobs::{GlobalRiskJob, LocalRiskJob, RiskJob, RiskJobScope};
+
+// The deepest a check can expand, whatever its traversal policy asks for
+pub const MAX_DEPTH: usize = 3;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RelationJob {
@@ -17,132 +33,707 @@ pub struct RelationJob {
     pub officer_id: Option<String>,
     pub remaining_depth: usize,
     pub relation_job_kind: RelationJobKind,
+    #[serde(default)]
+    pub traversal_policy: TraversalPolicy,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RelationJobKind {
     Shareholders,
     Officers,
     Appointments,
+    // Fetches the full company profile for an entity, doesn't discover any relations
+    CompanyProfile,
+    // Records a company's charges, and their holders as related entities
+    Charges,
+    // Records a company's insolvency cases and practitioners, doesn't discover any relations
+    Insolvency,
+}
+
+impl RelationJobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationJobKind::Shareholders => "shareholders",
+            RelationJobKind::Officers => "officers",
+            RelationJobKind::Appointments => "appointments",
+            RelationJobKind::CompanyProfile => "company_profile",
+            RelationJobKind::Charges => "charges",
+            RelationJobKind::Insolvency => "insolvency",
+        }
+    }
+
+    // Relationships always run from the officer, shareholder or lender (parent) to the
+    // company (child). Appointments start from the officer so discover children, every
+    // other job starts from the company so discovers parents
+    fn discovers_parents(&self) -> bool {
+        match self {
+            RelationJobKind::Appointments => false,
+            RelationJobKind::Shareholders
+            | RelationJobKind::Officers
+            | RelationJobKind::CompanyProfile
+            | RelationJobKind::Charges
+            | RelationJobKind::Insolvency => true,
+        }
+    }
+}
+
+// Which relations a check follows, chosen when the check is started
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TraversalPolicy {
+    // how many relations away from the root entity the check expands
+    pub max_depth: usize,
+    // relation jobs followed from each company found
+    pub company_relations: Vec<RelationJobKind>,
+    // relation jobs followed from each individual found
+    pub individual_relations: Vec<RelationJobKind>,
+    // officers who have resigned, and individuals' appointments which have ended
+    pub include_resigned_officers: bool,
+    // persons with significant control who have ceased to be one
+    pub include_ceased_shareholders: bool,
+    // shareholders below this band, or with no share ownership at all, are ignored
+    pub min_ownership_band: Option<OwnershipBand>,
+    // how many relations away from the root each kind of relation is followed,
+    // kinds without a limit are followed up to max_depth
+    pub max_depth_per_kind: HashMap<RelationJobKind, usize>,
+}
+
+impl Default for TraversalPolicy {
+    fn default() -> Self {
+        Self {
+            max_depth: 0,
+            company_relations: vec![RelationJobKind::Shareholders, RelationJobKind::Officers],
+            individual_relations: vec![RelationJobKind::Appointments],
+            include_resigned_officers: true,
+            include_ceased_shareholders: true,
+            min_ownership_band: None,
+            max_depth_per_kind: HashMap::new(),
+        }
+    }
+}
+
+impl TraversalPolicy {
+    pub fn validate(&self) -> Result<(), failure::Error> {
+        for relation_job_kind in &self.company_relations {
+            if !matches!(
+                relation_job_kind,
+                RelationJobKind::Shareholders | RelationJobKind::Officers
+            ) {
+                return Err(format_err!(
+                    "{:?} can't be followed from a company",
+                    relation_job_kind
+                ));
+            }
+        }
+
+        for relation_job_kind in &self.individual_relations {
+            if *relation_job_kind != RelationJobKind::Appointments {
+                return Err(format_err!(
+                    "{:?} can't be followed from an individual",
+                    relation_job_kind
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Whether a job of the given kind should be queued for an entity, remaining_depth
+    // being the depth the new job would have
+    pub fn follows(
+        &self,
+        entity_kind: &Entitykind,
+        relation_job_kind: RelationJobKind,
+        remaining_depth: usize,
+    ) -> bool {
+        let relations = match entity_kind {
+            Entitykind::Company => &self.company_relations,
+            Entitykind::Individual => &self.individual_relations,
+        };
+        if !relations.contains(&relation_job_kind) {
+            return false;
+        }
+
+        // a job with the full remaining depth finds entities one relation from the root
+        let distance_from_root = self.max_depth.saturating_sub(remaining_depth) + 1;
+        match self.max_depth_per_kind.get(&relation_job_kind) {
+            Some(max_depth) => distance_from_root <= *max_depth,
+            None => true,
+        }
+    }
+
+    // Whether a relation found by a job should be recorded and expanded
+    pub fn includes(
+        &self,
+        relation_job_kind: RelationJobKind,
+        entity_relation: &EntityRelation,
+    ) -> bool {
+        let has_ended = entity_relation.ended_on.is_some();
+
+        match relation_job_kind {
+            RelationJobKind::Officers | RelationJobKind::Appointments => {
+                !has_ended || self.include_resigned_officers
+            }
+            RelationJobKind::Shareholders => {
+                let meets_ownership_band = match self.min_ownership_band {
+                    Some(min_ownership_band) => entity_relation
+                        .ownership_band
+                        .is_some_and(|ownership_band| ownership_band >= min_ownership_band),
+                    None => true,
+                };
+
+                (!has_ended || self.include_ceased_shareholders) && meets_ownership_band
+            }
+            RelationJobKind::CompanyProfile
+            | RelationJobKind::Charges
+            | RelationJobKind::Insolvency => true,
+        }
+    }
+}
+
+// Starts a check of the company, the database must be scoped to the tenant the check is
+// for. Returns the check's id
+// Records the check, its root entity and the root's jobs, which are left to the caller to
+// send. Lets the database work be done without holding a connection across the sends
+pub fn prepare_check(
+    database: &mut Database,
+    company_house_number: String,
+    mut traversal_policy: TraversalPolicy,
+    priority: Priority,
+) -> Result<(Uuid, Vec<Job>), failure::Error> {
+    let company_house_number = format!("{:0>8}", company_house_number);
+
+    traversal_policy.max_depth = min(traversal_policy.max_depth, MAX_DEPTH);
+
+    let check_id = database.insert_check(Checkkind::EntityRelation, priority)?;
+    database.insert_traversal_policy(check_id, serde_json::to_value(&traversal_policy)?)?;
+    let root_entity = Entity::create_root(company_house_number.clone());
+    let entity_id = database.insert_entity(&root_entity, check_id)?;
+    database.refresh_check_summary(check_id)?;
+
+    let mut jobs = vec![];
+    for relation_job in RelationJob::root_jobs(
+        entity_id,
+        check_id,
+        &company_house_number,
+        &traversal_policy,
+    ) {
+        if let Some(job) = relation_job.record(database)? {
+            jobs.push(job);
+        }
+    }
+
+    Ok((check_id, jobs))
+}
+
+// The jobs queued for an entity a relation job found
+#[derive(Debug, PartialEq)]
+enum EntityJobs {
+    // its enrichment, risk and relation jobs
+    All,
+    // its relation jobs only
+    Relations,
+    None,
+}
+
+// Entities already in the check have had their enrichment and risk jobs queued when they
+// were first found. Their relations are queued again, the frontier only takes them if they
+// now have more depth left
+fn entity_jobs(expand_relations: bool, is_new_entity: bool) -> EntityJobs {
+    match (expand_relations, is_new_entity) {
+        (false, _) => EntityJobs::None,
+        (true, true) => EntityJobs::All,
+        (true, false) => EntityJobs::Relations,
+    }
 }
 
 impl RelationJob {
-    pub async fn do_work(&self, worker: &mut EntityRelationWorker) -> Result<(), failure::Error> {
-        let entities: Vec<EntityRelation> = match self.relation_job_kind {
-            RelationJobKind::Shareholders => worker
-                .company_house_client
-                .get_shareholders(&self.company_house_number)
-                .await?
-                .into(),
-            RelationJobKind::Officers => worker
-                .company_house_client
-                .get_officers(&self.company_house_number)
-                .await?
-                .into(),
-            RelationJobKind::Appointments => worker
-                .company_house_client
-                .get_appointments(&self.officer_id)
-                .await?
-                .into(),
+    // Jobs which fill in the details of a company entity, run for every company in a check.
+    // Its charges and insolvency cases are fetched once its profile shows it has any
+    pub fn company_enrichment_jobs(
+        entity_id: Uuid,
+        check_id: Uuid,
+        company_house_number: &str,
+        traversal_policy: &TraversalPolicy,
+    ) -> Vec<RelationJob> {
+        vec![RelationJob {
+            child_id: entity_id,
+            check_id,
+            company_house_number: company_house_number.to_string(),
+            officer_id: None,
+            remaining_depth: 0,
+            relation_job_kind: RelationJobKind::CompanyProfile,
+            traversal_policy: traversal_policy.clone(),
+        }]
+    }
+
+    // The jobs a check starts with, from its root company
+    fn root_jobs(
+        entity_id: Uuid,
+        check_id: Uuid,
+        company_house_number: &str,
+        traversal_policy: &TraversalPolicy,
+    ) -> Vec<RelationJob> {
+        let mut jobs = RelationJob::company_enrichment_jobs(
+            entity_id,
+            check_id,
+            company_house_number,
+            traversal_policy,
+        );
+        if traversal_policy.max_depth == 0 {
+            return jobs;
+        }
+
+        for relation_job_kind in &traversal_policy.company_relations {
+            if !traversal_policy.follows(
+                &Entitykind::Company,
+                *relation_job_kind,
+                traversal_policy.max_depth,
+            ) {
+                continue;
+            }
+
+            jobs.push(RelationJob {
+                child_id: entity_id,
+                check_id,
+                company_house_number: company_house_number.to_string(),
+                officer_id: None,
+                remaining_depth: traversal_policy.max_depth,
+                relation_job_kind: *relation_job_kind,
+                traversal_policy: traversal_policy.clone(),
+            });
+        }
+
+        jobs
+    }
+
+    // The charges and insolvency jobs of the profile job's company, unless its profile shows
+    // it has none
+    fn profile_jobs(&self, company_data: &CompanyData) -> Vec<RelationJob> {
+        [
+            (RelationJobKind::Charges, company_data.has_charges),
+            (
+                RelationJobKind::Insolvency,
+                company_data.has_insolvency_history,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, has_any)| *has_any != Some(false))
+        .map(|(relation_job_kind, _)| RelationJob {
+            child_id: self.child_id,
+            check_id: self.check_id,
+            company_house_number: self.company_house_number.clone(),
+            officer_id: None,
+            remaining_depth: 0,
+            relation_job_kind,
+            traversal_policy: self.traversal_policy.clone(),
+        })
+        .collect()
+    }
+
+    // Records the job unless its (entity, kind) is already on the check's frontier or
+    // has already been fetched
+    fn record(self, database: &mut Database) -> Result<Option<Job>, failure::Error> {
+        if !self.claim(database)? {
+            return Ok(None);
+        }
+
+        record_job(database, Some(self.check_id), JobKind::RelationJob(self)).map(Some)
+    }
+
+    fn claim(&self, database: &mut Database) -> Result<bool, failure::Error> {
+        database.claim_relation_job(
+            self.check_id,
+            self.child_id,
+            self.relation_job_kind.as_str(),
+            self.remaining_depth as i32,
+        )
+    }
+
+    // Enqueues the job unless its (entity, kind) is already on the check's frontier or
+    // has already been fetched
+    pub async fn enqueue(
+        self,
+        producer: &mut PulsarProducer,
+        database: &mut Database,
+    ) -> Result<(), failure::Error> {
+        if !self.claim(database)? {
+            return Ok(());
+        }
+
+        let (check_id, entity_id, relation_job_kind) =
+            (self.check_id, self.child_id, self.relation_job_kind);
+        let is_enqueued = producer
+            .enqueue_job(database, Some(check_id), JobKind::RelationJob(self))
+            .await?;
+        // past the check's job limit, taken back off the frontier so the check can finish
+        if !is_enqueued {
+            database.drop_relation_job(check_id, entity_id, relation_job_kind.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn do_work<C: RegistryClient>(
+        mut self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        // the depth on the frontier is the shortest path the entity has been found by
+        self.remaining_depth = match worker.database.visit_relation_job(
+            self.check_id,
+            self.child_id,
+            self.relation_job_kind.as_str(),
+            self.remaining_depth as i32,
+        )? {
+            Some(remaining_depth) => remaining_depth as usize,
+            None => return Ok(()),
         };
 
-        let relationship_kind = match self.relation_job_kind {
-            RelationJobKind::Shareholders => Relationshipkind::Shareholder,
-            RelationJobKind::Officers => Relationshipkind::Officer,
-            RelationJobKind::Appointments => Relationshipkind::Officer,
+        loop {
+            self.fetch_relations(worker).await?;
+
+            match worker.database.mark_relation_job_fetched(
+                self.check_id,
+                self.child_id,
+                self.relation_job_kind.as_str(),
+                self.remaining_depth as i32,
+            )? {
+                // found by a shorter path while it was being fetched
+                Some(raised_depth) => self.remaining_depth = raised_depth as usize,
+                None => break,
+            }
+        }
+
+        // once nothing is left on the frontier all of the check's relations are known
+        if !worker.database.has_pending_relation_jobs(&self.check_id)?
+            && worker.database.claim_global_risk_jobs(&self.check_id)?
+        {
+            // released so the redelivered job can try again
+            if let Err(e) = self.enqueue_global_risk_jobs(worker).await {
+                worker.database.release_global_risk_jobs(&self.check_id)?;
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn enqueue_global_risk_jobs<C: RegistryClient>(
+        &self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        for global_risk_job in [
+            GlobalRiskJob::BeneficialOwners {
+                check_id: self.check_id,
+            },
+            GlobalRiskJob::Exposure {
+                check_id: self.check_id,
+            },
+        ] {
+            let risk_job = JobKind::RiskJob(RiskJob {
+                scope: RiskJobScope::Global(global_risk_job),
+            });
+
+            worker
+                .risk_producer
+                .enqueue_job(&mut worker.database, Some(self.check_id), risk_job)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_relations<C: RegistryClient>(
+        &self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let (entities, relationship_kind, source_fetch_id): (
+            Vec<EntityRelation>,
+            Relationshipkind,
+            Uuid,
+        ) = match self.relation_job_kind {
+            // a resource the registry doesn't have, e.g. the psc list of a company without
+            // any, has no relations to record
+            RelationJobKind::Shareholders => {
+                let shareholders = match worker
+                    .registry_client
+                    .get_shareholders(&self.company_house_number)
+                    .await?
+                {
+                    Some(shareholders) => shareholders,
+                    None => return Ok(()),
+                };
+                let source_fetch_id = worker
+                    .database
+                    .archive_fetch(self.check_id, &shareholders)?;
+                (
+                    shareholders.response.into(),
+                    Relationshipkind::Shareholder,
+                    source_fetch_id,
+                )
+            }
+            RelationJobKind::Officers => {
+                let officers = match worker
+                    .registry_client
+                    .get_officers(&self.company_house_number)
+                    .await?
+                {
+                    Some(officers) => officers,
+                    None => return Ok(()),
+                };
+                let source_fetch_id = worker.database.archive_fetch(self.check_id, &officers)?;
+                (
+                    officers.response.into(),
+                    Relationshipkind::Officer,
+                    source_fetch_id,
+                )
+            }
+            RelationJobKind::Appointments => {
+                let appointments = match worker
+                    .registry_client
+                    .get_appointments(&self.officer_id)
+                    .await?
+                {
+                    Some(appointments) => appointments,
+                    None => return Ok(()),
+                };
+                let source_fetch_id = worker
+                    .database
+                    .archive_fetch(self.check_id, &appointments)?;
+                let appointments = appointments.response;
+
+                // officers' list entries don't always carry a date of birth
+                let date_of_birth = appointments
+                    .date_of_birth
+                    .as_ref()
+                    .and_then(|dob| PartialDate::new(dob.year, dob.month, None));
+                worker.database.update_entity_details(
+                    self.child_id,
+                    EntityDetails::from_date_of_origin(date_of_birth),
+                )?;
+
+                (
+                    appointments.into(),
+                    Relationshipkind::Officer,
+                    source_fetch_id,
+                )
+            }
+            RelationJobKind::CompanyProfile => return self.do_profile_job(worker).await,
+            RelationJobKind::Charges => return self.do_charges_job(worker).await,
+            RelationJobKind::Insolvency => return self.do_insolvency_job(worker).await,
+        };
+
+        self.do_job(entities, relationship_kind, source_fetch_id, true, worker)
+            .await
+    }
+
+    async fn do_profile_job<C: RegistryClient>(
+        &self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let company_data = match worker
+            .registry_client
+            .get_company_profile(&self.company_house_number)
+            .await?
+        {
+            Some(company_data) => company_data,
+            None => return Ok(()),
+        };
+        worker
+            .database
+            .archive_fetch(self.check_id, &company_data)?;
+        let company_data = company_data.response;
+
+        worker.database.insert_company_profile(
+            CompanyProfile::from((self.child_id, &company_data)),
+            PreviousName::from_company_data(self.child_id, &company_data),
+            (&company_data).into(),
+        )?;
+
+        for profile_job in self.profile_jobs(&company_data) {
+            profile_job
+                .enqueue(&mut worker.entity_relation_producer, &mut worker.database)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn do_charges_job<C: RegistryClient>(
+        &self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let charges = match worker
+            .registry_client
+            .get_charges(&self.company_house_number)
+            .await?
+        {
+            Some(charges) => charges,
+            None => return Ok(()),
         };
+        let source_fetch_id = worker.database.archive_fetch(self.check_id, &charges)?;
+        let charges = charges.response;
 
+        worker.database.insert_charges(
+            self.child_id,
+            Charge::from_charge_list(self.child_id, &charges),
+        )?;
+
+        // charge holders have no registry number, so can't be expanded any further
         self.do_job(
-            entities,
-            relationship_kind,
-            self.relation_job_kind == RelationJobKind::Appointments,
+            charges.into(),
+            Relationshipkind::ChargeHolder,
+            source_fetch_id,
+            false,
             worker,
         )
-        .await
+        .await?;
+
+        let unusual_lenders_job = JobKind::RiskJob(RiskJob {
+            scope: RiskJobScope::Local(LocalRiskJob {
+                entity_id: self.child_id,
+                kind: UnusualLenders,
+            }),
+        });
+
+        worker
+            .risk_producer
+            .enqueue_job(
+                &mut worker.database,
+                Some(self.check_id),
+                unusual_lenders_job,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn do_insolvency_job<C: RegistryClient>(
+        &self,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let insolvency = match worker
+            .registry_client
+            .get_insolvency(&self.company_house_number)
+            .await?
+        {
+            Some(insolvency) => insolvency,
+            None => return Ok(()),
+        };
+        worker.database.archive_fetch(self.check_id, &insolvency)?;
+        let insolvency = insolvency.response;
+
+        worker.database.insert_insolvencies(
+            self.child_id,
+            InsolvencyCaseRecord::from_company_insolvency(self.child_id, &insolvency),
+        )?;
+
+        let recent_insolvency_job = JobKind::RiskJob(RiskJob {
+            scope: RiskJobScope::Local(LocalRiskJob {
+                entity_id: self.child_id,
+                kind: RecentInsolvency,
+            }),
+        });
+
+        worker
+            .risk_producer
+            .enqueue_job(
+                &mut worker.database,
+                Some(self.check_id),
+                recent_insolvency_job,
+            )
+            .await?;
+        Ok(())
     }
 
-    async fn do_job(
+    async fn do_job<C: RegistryClient>(
         &self,
         entity_relations: Vec<EntityRelation>,
         relationship_kind: Relationshipkind,
-        reverse_relation: bool,
-        worker: &mut EntityRelationWorker,
+        source_fetch_id: Uuid,
+        expand_relations: bool,
+        worker: &mut EntityRelationWorker<C>,
     ) -> Result<(), failure::Error> {
         for entity_relation in entity_relations {
-            let parent_id = worker
-                .database
-                .insert_entity(&entity_relation.entity, self.check_id)?;
-
-            let insert_relationship_result = match reverse_relation {
-                true => worker.database.insert_relationship(Relationship {
-                    parent_id: self.child_id,
-                    child_id: parent_id,
-                    kind: relationship_kind,
-                    started_on: entity_relation.started_on,
-                    ended_on: entity_relation.ended_on,
-                }),
-                false => worker.database.insert_relationship(Relationship {
-                    parent_id,
-                    child_id: self.child_id,
-                    kind: relationship_kind,
-                    started_on: entity_relation.started_on,
-                    ended_on: entity_relation.ended_on,
-                }),
-            };
+            if !self
+                .traversal_policy
+                .includes(self.relation_job_kind, &entity_relation)
+            {
+                continue;
+            }
+
+            let (entity_id, is_new_entity) = worker.database.insert_or_get_entity(
+                &entity_relation.entity,
+                self.check_id,
+                Some(source_fetch_id),
+            )?;
+
+            let insert_relationship_result = worker.database.insert_relationship(
+                self.relationship(entity_id, relationship_kind, &entity_relation),
+                Some(source_fetch_id),
+            );
 
             match insert_relationship_result {
-                Ok(_) => {
-                    self.queue_further_jobs(&entity_relation.entity, worker)
-                        .await?
-                }
+                Ok(_) => match entity_jobs(expand_relations, is_new_entity) {
+                    EntityJobs::All => {
+                        self.queue_further_jobs(&entity_relation.entity, worker)
+                            .await?
+                    }
+                    EntityJobs::Relations => {
+                        let entity = worker.database.get_entity(entity_id)?;
+                        self.queue_followed_jobs(&entity, worker).await?
+                    }
+                    EntityJobs::None => {}
+                },
                 // log error and continue
                 Err(e) => warn!(
                     "Inserting relation failed for {:?}, error: {:?}",
                     relationship_kind, e
                 ),
             }
-            self.queue_further_jobs(&entity_relation.entity, worker)
-                .await?;
         }
 
         Ok(())
     }
 
-    async fn queue_further_jobs(
+    // The relationship between the job's entity and an entity it found
+    fn relationship(
+        &self,
+        entity_id: Uuid,
+        relationship_kind: Relationshipkind,
+        entity_relation: &EntityRelation,
+    ) -> Relationship {
+        let (parent_id, child_id) = match self.relation_job_kind.discovers_parents() {
+            true => (entity_id, self.child_id),
+            false => (self.child_id, entity_id),
+        };
+
+        Relationship {
+            parent_id,
+            child_id,
+            kind: relationship_kind,
+            started_on: entity_relation.started_on,
+            ended_on: entity_relation.ended_on,
+            ownership_band: entity_relation.ownership_band,
+        }
+    }
+
+    async fn queue_further_jobs<C: RegistryClient>(
         &self,
         entity: &Entity,
-        worker: &mut EntityRelationWorker,
+        worker: &mut EntityRelationWorker<C>,
     ) -> Result<(), failure::Error> {
         match entity.kind {
             Entitykind::Company => {
-                if self.remaining_depth > 0 {
-                    let job_kind = JobKind::RelationJob(RelationJob {
-                        child_id: entity.id,
-                        check_id: self.check_id,
-                        company_house_number: entity.company_house_number.clone(),
-                        officer_id: entity.officer_id.clone(),
-                        remaining_depth: self.remaining_depth - 1,
-                        relation_job_kind: RelationJobKind::Shareholders,
-                    });
-
-                    worker
-                        .entity_relation_producer
-                        .enqueue_job(&mut worker.database, Some(self.check_id), job_kind)
-                        .await?;
-                }
-                if self.remaining_depth > 0 {
-                    let job_kind = JobKind::RelationJob(RelationJob {
-                        child_id: entity.id,
-                        check_id: self.check_id,
-                        company_house_number: entity.company_house_number.clone(),
-                        officer_id: entity.officer_id.clone(),
-                        remaining_depth: self.remaining_depth - 1,
-                        relation_job_kind: RelationJobKind::Officers,
-                    });
-
-                    worker
-                        .entity_relation_producer
-                        .enqueue_job(&mut worker.database, Some(self.check_id), job_kind)
+                self.queue_followed_jobs(entity, worker).await?;
+
+                for enrichment_job in RelationJob::company_enrichment_jobs(
+                    entity.id,
+                    self.check_id,
+                    &entity.company_house_number,
+                    &self.traversal_policy,
+                ) {
+                    enrichment_job
+                        .enqueue(&mut worker.entity_relation_producer, &mut worker.database)
                         .await?;
                 }
 
@@ -159,21 +750,7 @@ impl RelationJob {
                     .await?;
             }
             Entitykind::Individual => {
-                if self.remaining_depth > 0 {
-                    let appointment_job = JobKind::RelationJob(RelationJob {
-                        child_id: entity.id,
-                        check_id: self.check_id,
-                        company_house_number: entity.company_house_number.clone(),
-                        officer_id: entity.officer_id.clone(),
-                        remaining_depth: self.remaining_depth - 1,
-                        relation_job_kind: RelationJobKind::Appointments,
-                    });
-
-                    worker
-                        .entity_relation_producer
-                        .enqueue_job(&mut worker.database, Some(self.check_id), appointment_job)
-                        .await?;
-                }
+                self.queue_followed_jobs(entity, worker).await?;
 
                 let flag_job = JobKind::RiskJob(RiskJob {
                     scope: RiskJobScope::Local(LocalRiskJob {
@@ -197,9 +774,172 @@ impl RelationJob {
                     .risk_producer
                     .enqueue_job(&mut worker.database, Some(self.check_id), outlier_age_job)
                     .await?;
+
+                let disqualification_job = JobKind::RiskJob(RiskJob {
+                    scope: RiskJobScope::Local(LocalRiskJob {
+                        entity_id: entity.id,
+                        kind: Disqualification,
+                    }),
+                });
+                worker
+                    .risk_producer
+                    .enqueue_job(
+                        &mut worker.database,
+                        Some(self.check_id),
+                        disqualification_job,
+                    )
+                    .await?;
             }
         }
 
         Ok(())
     }
+
+    async fn queue_followed_jobs<C: RegistryClient>(
+        &self,
+        entity: &Entity,
+        worker: &mut EntityRelationWorker<C>,
+    ) -> Result<(), failure::Error> {
+        if self.remaining_depth == 0 {
+            return Ok(());
+        }
+
+        let relation_job_kinds = match entity.kind {
+            Entitykind::Company => &self.traversal_policy.company_relations,
+            Entitykind::Individual => &self.traversal_policy.individual_relations,
+        };
+
+        for relation_job_kind in relation_job_kinds {
+            if !self.traversal_policy.follows(
+                &entity.kind,
+                *relation_job_kind,
+                self.remaining_depth - 1,
+            ) {
+                continue;
+            }
+
+            RelationJob {
+                child_id: entity.id,
+                check_id: self.check_id,
+                company_house_number: entity.company_house_number.clone(),
+                officer_id: entity.officer_id.clone(),
+                remaining_depth: self.remaining_depth - 1,
+                relation_job_kind: *relation_job_kind,
+                traversal_policy: self.traversal_policy.clone(),
+            }
+            .enqueue(&mut worker.entity_relation_producer, &mut worker.database)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::fixture_client::FixtureRegistryClient;
+
+    #[tokio::test]
+    async fn charges_and_insolvency_are_only_fetched_for_companies_with_any() {
+        let company_data =
+            FixtureRegistryClient::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/registry"))
+                .get_company_profile("00000001")
+                .await
+                .unwrap()
+                .unwrap()
+                .response;
+        let profile_job = RelationJob::company_enrichment_jobs(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "00000001",
+            &TraversalPolicy::default(),
+        )
+        .pop()
+        .unwrap();
+
+        // the profile has charges but no insolvency history
+        let relation_job_kinds: Vec<RelationJobKind> = profile_job
+            .profile_jobs(&company_data)
+            .into_iter()
+            .map(|job| job.relation_job_kind)
+            .collect();
+        assert_eq!(relation_job_kinds, vec![RelationJobKind::Charges]);
+    }
+
+    #[test]
+    fn only_new_entities_have_all_their_jobs_queued() {
+        assert_eq!(entity_jobs(true, true), EntityJobs::All);
+        assert_eq!(entity_jobs(true, false), EntityJobs::Relations);
+        assert_eq!(entity_jobs(false, true), EntityJobs::None);
+        assert_eq!(entity_jobs(false, false), EntityJobs::None);
+    }
+
+    #[test]
+    fn relationships_run_from_the_related_entity_to_the_company() {
+        let (company_id, officer_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let job = |child_id, relation_job_kind| RelationJob {
+            child_id,
+            check_id: Uuid::new_v4(),
+            company_house_number: "00000001".to_string(),
+            officer_id: None,
+            remaining_depth: 1,
+            relation_job_kind,
+            traversal_policy: TraversalPolicy::default(),
+        };
+        let entity_relation = EntityRelation {
+            entity: Entity::default(),
+            started_on: None,
+            ended_on: None,
+            ownership_band: None,
+        };
+
+        // an officer found on the company, and the company found on the officer's
+        // appointments, are the same relationship
+        let from_officers = job(company_id, RelationJobKind::Officers).relationship(
+            officer_id,
+            Relationshipkind::Officer,
+            &entity_relation,
+        );
+        let from_appointments = job(officer_id, RelationJobKind::Appointments).relationship(
+            company_id,
+            Relationshipkind::Officer,
+            &entity_relation,
+        );
+        for relationship in [from_officers, from_appointments] {
+            assert_eq!(
+                (relationship.parent_id, relationship.child_id),
+                (officer_id, company_id)
+            );
+        }
+    }
+
+    #[test]
+    fn checks_start_with_the_relations_their_policy_follows() {
+        let root_job_kinds = |traversal_policy: &TraversalPolicy| -> Vec<RelationJobKind> {
+            RelationJob::root_jobs(Uuid::new_v4(), Uuid::new_v4(), "00000001", traversal_policy)
+                .into_iter()
+                .map(|job| job.relation_job_kind)
+                .collect()
+        };
+
+        // a check which doesn't expand only fills in the root company
+        assert_eq!(
+            root_job_kinds(&TraversalPolicy::default()),
+            vec![RelationJobKind::CompanyProfile]
+        );
+
+        let traversal_policy = TraversalPolicy {
+            max_depth: 2,
+            max_depth_per_kind: HashMap::from([(RelationJobKind::Officers, 0)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            root_job_kinds(&traversal_policy),
+            vec![
+                RelationJobKind::CompanyProfile,
+                RelationJobKind::Shareholders
+            ]
+        );
+    }
 }
@@ -1,13 +1,51 @@
-use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use log::warn;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::{
+    exposure::{compute_exposures, PropagationPolicy},
+    jobs::JobKind,
+    relation_jobs::RelationJobKind,
+};
 use crate::{
-    models::{Entity, Entitykind, FlagStringList},
+    company_house::company_house_types::DisqualifiedOfficerSearch,
+    models::{
+        normalize_name, BeneficialOwner, BeneficialOwnerPath, Disqualification, Entity, Entitykind,
+        FlagStringList, Flagkind, OwnershipBand, PartialDate, Relationship, Relationshipkind,
+        SanctionsMatch,
+    },
+    registry::{fetched::Fetched, registry_client::RegistryClient},
     workers::risk_worker::RiskWorker,
 };
 
 const DORMANCY_YEARS: i64 = 5;
+const MAX_OWNERSHIP_PATHS: usize = 10_000;
+const RECENT_INSOLVENCY_YEARS: i64 = 3;
+// Charge holders whose name has any of these as whole words are taken to be mainstream
+// lenders: banks, building societies, asset and invoice financiers and the trustees who hold
+// security for lending syndicates. Clearing banks registering charges under their brand
+// alone are listed by name
+const USUAL_LENDER_TERMS: [&str; 16] = [
+    "BANK",
+    "BANKING",
+    "BUILDING SOCIETY",
+    "FINANCE",
+    "FINANCIAL",
+    "CREDIT",
+    "LENDING",
+    "LEASING",
+    "FUNDING",
+    "SECURITY TRUSTEE",
+    "SECURITY AGENT",
+    "HSBC",
+    "NATWEST",
+    "BARCLAYS",
+    "LLOYDS",
+    "SANTANDER",
+];
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RiskJob {
@@ -30,6 +68,12 @@ pub enum GlobalRiskJob {
     CircularRelations,
     // When companies are registered/created in bulk within the registration date window
     MassRegistration,
+    // Computes the effective ownership of the check's root entity by every owner up
+    // its ownership chains, identifying the ultimate beneficial owners
+    BeneficialOwners { check_id: Uuid },
+    // Pushes flags along ownership and control relationships onto the companies
+    // exposed to them, using the check's propagation policy
+    Exposure { check_id: Uuid },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -50,60 +94,235 @@ pub enum LocalRiskJobKind {
     OutlierAge,
     // Determines if a company has been dormant for more than 5 years
     Dormancy,
+    // Determines if a company has had an insolvency case event in the last 3 years
+    RecentInsolvency,
+    // Determines if any of a company's charges are held by lenders which aren't
+    // recognisable financial institutions
+    UnusualLenders,
+    // Finds director disqualifications (orders and undertakings) of individual officers
+    Disqualification,
+}
+
+// Whether someone is implausibly young or old on the date. Without the day of birth the
+// youngest possible age is judged against the lower bound and the oldest against the upper
+fn is_outlier_age(dob: &PartialDate, date: NaiveDate) -> bool {
+    dob.age_at(date).is_some_and(|age| age < 15)
+        || dob.oldest_age_at(date).is_some_and(|age| age > 85)
 }
 
 impl RiskJob {
-    pub async fn do_job(&self, worker: &mut RiskWorker) -> Result<(), failure::Error> {
+    pub async fn do_job<C: RegistryClient>(
+        &self,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
         match &self.scope {
             RiskJobScope::Global(global_risk_job) => self.do_global_job(global_risk_job, worker),
             RiskJobScope::Local(local_risk_job) => self.do_local_job(local_risk_job, worker).await,
         }
     }
 
-    fn do_global_job(
+    fn do_global_job<C: RegistryClient>(
         &self,
         job: &GlobalRiskJob,
-        worker: &mut RiskWorker,
+        worker: &mut RiskWorker<C>,
     ) -> Result<(), failure::Error> {
         match job {
             GlobalRiskJob::CircularRelations => unimplemented!(),
             GlobalRiskJob::MassRegistration => unimplemented!(),
+            GlobalRiskJob::BeneficialOwners { check_id } => {
+                self.do_beneficial_owners_job(*check_id, worker)
+            }
+            GlobalRiskJob::Exposure { check_id } => self.do_exposure_job(*check_id, worker),
+        }
+    }
+
+    fn do_exposure_job<C: RegistryClient>(
+        &self,
+        check_id: Uuid,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        worker.database.take_exposure_request(&check_id)?;
+        let policy: PropagationPolicy = worker
+            .database
+            .get_propagation_policy(&check_id)?
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        let flags = worker.database.get_flags_for_check(&check_id)?;
+        let relationships: Vec<Relationship> = worker
+            .database
+            .get_check_relationships(&check_id)?
+            .into_iter()
+            .filter(|relationship| relationship.ended_on.is_none())
+            .collect();
+
+        let exposures = compute_exposures(check_id, &flags, &relationships, &policy);
+
+        worker.database.insert_exposures(check_id, exposures)
+    }
+
+    // Local jobs only know their entity, so responses are archived against the check it
+    // was found by. Snapshot entities aren't part of a check, and aren't archived
+    fn archive_fetch<T, C: RegistryClient>(
+        &self,
+        entity_id: &Uuid,
+        fetched: &Fetched<T>,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<Option<Uuid>, failure::Error> {
+        match worker.database.get_entity_check_ids(entity_id)?.first() {
+            Some(check_id) => Ok(Some(worker.database.archive_fetch(*check_id, fetched)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Flags found after a check's relations are all known wouldn't otherwise be
+    // propagated, so a recompute of the exposures of each of the entity's finished checks is
+    // queued. Flags found while one is queued are left to it
+    async fn update_exposures<C: RegistryClient>(
+        &self,
+        entity_id: &Uuid,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        for check_id in worker.database.get_entity_check_ids(entity_id)? {
+            if worker.database.has_pending_relation_jobs(&check_id)?
+                || !worker.database.request_exposure(&check_id)?
+            {
+                continue;
+            }
+
+            let risk_job = JobKind::RiskJob(RiskJob {
+                scope: RiskJobScope::Global(GlobalRiskJob::Exposure { check_id }),
+            });
+            // released so the redelivered job, or the next flag found, can try again
+            match worker
+                .risk_producer
+                .enqueue_job(&mut worker.database, Some(check_id), risk_job)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => worker.database.take_exposure_request(&check_id)?,
+                Err(e) => {
+                    worker.database.take_exposure_request(&check_id)?;
+                    return Err(e);
+                }
+            }
         }
 
         Ok(())
     }
 
-    async fn do_local_job(
+    fn do_beneficial_owners_job<C: RegistryClient>(
+        &self,
+        check_id: Uuid,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let root_entity = worker.database.get_root_entity(&check_id)?;
+        let ownership_relations = worker.database.get_ownership_relations(&check_id)?;
+        let individuals: HashSet<Uuid> = worker
+            .database
+            .get_entities(check_id)?
+            .into_iter()
+            .filter(|entity| entity.kind == Entitykind::Individual)
+            .map(|entity| entity.id)
+            .collect();
+        let shareholders_fetched = worker
+            .database
+            .get_fetched_entities(&check_id, RelationJobKind::Shareholders.as_str())?;
+
+        let (owners, paths) = compute_beneficial_owners(
+            check_id,
+            root_entity.id,
+            &ownership_relations,
+            &individuals,
+            &shareholders_fetched,
+        );
+
+        worker
+            .database
+            .insert_beneficial_owners(check_id, owners, paths)
+    }
+
+    async fn do_local_job<C: RegistryClient>(
         &self,
         job: &LocalRiskJob,
-        worker: &mut RiskWorker,
+        worker: &mut RiskWorker<C>,
     ) -> Result<(), failure::Error> {
         let entity = worker.database.get_entity(job.entity_id)?;
         match job.kind {
-            LocalRiskJobKind::Flags => self.do_flags_job(entity, worker).await,
+            LocalRiskJobKind::Flags => {
+                self.do_flags_job(entity, worker).await?;
+                self.update_exposures(&job.entity_id, worker).await
+            }
             LocalRiskJobKind::OutlierAge => self.do_outlier_age_job(entity, worker),
             LocalRiskJobKind::Dormancy => self.do_dormancy_job(entity, worker).await,
+            LocalRiskJobKind::RecentInsolvency => self.do_recent_insolvency_job(entity, worker),
+            LocalRiskJobKind::UnusualLenders => self.do_unusual_lenders_job(entity, worker),
+            LocalRiskJobKind::Disqualification => {
+                self.do_disqualification_job(entity, worker).await?;
+                self.update_exposures(&job.entity_id, worker).await
+            }
         }
     }
 
-    async fn do_flags_job(
+    async fn do_flags_job<C: RegistryClient>(
         &self,
         entity: Entity,
-        worker: &mut RiskWorker,
+        worker: &mut RiskWorker<C>,
     ) -> Result<(), failure::Error> {
         if entity.kind != Entitykind::Individual {
             return Ok(());
         }
 
+        let date_of_birth = entity.partial_date_of_origin();
+        // analysts' false positives on earlier screenings of the same person aren't raised again
+        let (dismissed_flags, dismissed_matches) = worker.database.get_false_positives(&entity)?;
+
         if let Some(name) = entity.name {
             let search_result = worker.open_sanctions_client.get_flags(name).await?;
+            let source_fetch_id = self.archive_fetch(&entity.id, &search_result, worker)?;
+            let search_result = search_result.response;
+
+            // a name match is only accepted if the date of birth doesn't contradict it
+            let os_entity = search_result
+                .results
+                .iter()
+                .filter(|os_entity| !dismissed_matches.contains(&os_entity.id))
+                .find(
+                    |os_entity| match (&date_of_birth, os_entity.properties.get("birthDate")) {
+                        (Some(date_of_birth), Some(birth_dates)) if !birth_dates.is_empty() => {
+                            birth_dates
+                                .iter()
+                                .any(|birth_date| date_of_birth.matches_str(birth_date))
+                        }
+                        _ => true,
+                    },
+                );
+
+            if let Some(os_entity) = os_entity {
+                let sanctions_match_id = Uuid::new_v4();
+                worker.database.insert_sanctions_match(&SanctionsMatch {
+                    id: sanctions_match_id,
+                    entity_id: entity.id,
+                    open_sanctions_id: os_entity.id.clone(),
+                    caption: os_entity.caption.clone(),
+                    source_fetch_id,
+                })?;
 
-            if let Some(os_entity) = search_result.results.first() {
                 for (key, value) in os_entity.properties.to_owned().into_iter() {
                     if key == "topics" {
-                        worker
-                            .database
-                            .insert_flags(entity.id, FlagStringList(value).into())?
+                        let flag_kinds: Vec<Flagkind> = FlagStringList(value).into();
+                        worker.database.insert_flags(
+                            entity.id,
+                            flag_kinds
+                                .into_iter()
+                                .filter(|flag_kind| {
+                                    !dismissed_flags
+                                        .contains(&(Some(os_entity.id.clone()), *flag_kind))
+                                })
+                                .collect(),
+                            source_fetch_id,
+                            Some(sanctions_match_id),
+                        )?
                     } else if key == "position" {
                         worker.database.insert_positions(entity.id, value)?
                     }
@@ -116,29 +335,18 @@ impl RiskJob {
         Ok(())
     }
 
-    fn do_outlier_age_job(
+    fn do_outlier_age_job<C: RegistryClient>(
         &self,
         entity: Entity,
-        worker: &mut RiskWorker,
+        worker: &mut RiskWorker<C>,
     ) -> Result<(), failure::Error> {
         if entity.kind != Entitykind::Individual {
             return Ok(());
         }
 
-        let mut outlier_age = false;
-
-        if let Some(dob) = entity.date_of_origin {
-            let parsed_dob: DateTime<Utc> = dob.parse()?;
-
-            match chrono::Utc::now().years_since(parsed_dob) {
-                Some(age) => {
-                    if age < 15 || age > 85 {
-                        outlier_age = true;
-                    }
-                }
-                None => {}
-            }
-        }
+        let outlier_age = entity
+            .partial_date_of_origin()
+            .is_some_and(|dob| is_outlier_age(&dob, Utc::now().date_naive()));
 
         worker
             .database
@@ -147,19 +355,26 @@ impl RiskJob {
         Ok(())
     }
 
-    async fn do_dormancy_job(
+    async fn do_dormancy_job<C: RegistryClient>(
         &self,
         entity: Entity,
-        worker: &mut RiskWorker,
+        worker: &mut RiskWorker<C>,
     ) -> Result<(), failure::Error> {
         // TODO: this should be rate limited as it's using the company house api
         // but as companies are processed less frequently, it can go without for now
 
         let mut is_dormant = false;
-        let filing_history = worker
-            .company_house_client
+        // nothing to judge a company without a filing history by
+        let filing_history = match worker
+            .registry_client
             .get_filing_history(&entity.company_house_number)
-            .await?;
+            .await?
+        {
+            Some(filing_history) => filing_history,
+            None => return Ok(()),
+        };
+        self.archive_fetch(&entity.id, &filing_history, worker)?;
+        let filing_history = filing_history.response;
 
         // if last filing over 5 years ago (relative to now), mark company as dormant
         let last_filing = filing_history.items.first();
@@ -178,4 +393,523 @@ impl RiskJob {
 
         Ok(())
     }
+
+    fn do_recent_insolvency_job<C: RegistryClient>(
+        &self,
+        entity: Entity,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let insolvency_dates = worker.database.get_insolvency_dates(&entity.id)?;
+
+        let cutoff = Utc::now().date_naive() - Duration::days(RECENT_INSOLVENCY_YEARS * 365);
+        let recent_insolvency = insolvency_dates.into_iter().any(|date| date >= cutoff);
+
+        worker
+            .database
+            .insert_recent_insolvency(&entity.id, recent_insolvency)?;
+
+        Ok(())
+    }
+
+    fn do_unusual_lenders_job<C: RegistryClient>(
+        &self,
+        entity: Entity,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let mut unusual_lender = false;
+        let charge_holders = worker
+            .database
+            .get_relations(entity.id, Relationshipkind::ChargeHolder)?;
+
+        for (charge_holder_id, _, _) in charge_holders {
+            let charge_holder = worker.database.get_entity(charge_holder_id)?;
+            if !is_usual_lender(&charge_holder) {
+                unusual_lender = true;
+                break;
+            }
+        }
+
+        worker
+            .database
+            .insert_unusual_lender(&entity.id, unusual_lender)?;
+
+        Ok(())
+    }
+
+    async fn do_disqualification_job<C: RegistryClient>(
+        &self,
+        entity: Entity,
+        worker: &mut RiskWorker<C>,
+    ) -> Result<(), failure::Error> {
+        let name = match (&entity.kind, &entity.name) {
+            (Entitykind::Individual, Some(name)) => name.clone(),
+            _ => return Ok(()),
+        };
+
+        // TODO: this should be rate limited as it's using the company house api
+        let search = match worker
+            .registry_client
+            .search_disqualified_officers(&name)
+            .await?
+        {
+            Some(search) => search,
+            None => return Ok(()),
+        };
+        self.archive_fetch(&entity.id, &search, worker)?;
+
+        let today = Utc::now().date_naive();
+        let mut disqualifications = vec![];
+        let mut source_fetch_id = None;
+        for link in matching_disqualified_officers(&entity, &search.response) {
+            // a disqualified officer that isn't found any more isn't disqualified
+            let disqualified_officer =
+                match worker.registry_client.get_disqualifications(&link).await? {
+                    Some(disqualified_officer) => disqualified_officer,
+                    None => continue,
+                };
+            let fetch_id = self.archive_fetch(&entity.id, &disqualified_officer, worker)?;
+            let officer_disqualifications = Disqualification::from_disqualified_officer(
+                entity.id,
+                &disqualified_officer.response,
+            );
+            if is_disqualified(&officer_disqualifications, today) {
+                source_fetch_id = fetch_id;
+            }
+            disqualifications.extend(officer_disqualifications);
+        }
+        let is_disqualified = is_disqualified(&disqualifications, today);
+        let (dismissed_flags, _) = worker.database.get_false_positives(&entity)?;
+
+        // past disqualifications are kept, only current ones raise a flag
+        worker
+            .database
+            .insert_disqualifications(entity.id, disqualifications)?;
+
+        // flag may have already been raised by open sanctions
+        if is_disqualified
+            && !dismissed_flags.contains(&(None, Flagkind::Disqualified))
+            && !worker
+                .database
+                .get_flag_kinds_for_entity(&entity.id)?
+                .contains(&Flagkind::Disqualified)
+        {
+            worker.database.insert_flags(
+                entity.id,
+                vec![Flagkind::Disqualified],
+                source_fetch_id,
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Links of the disqualified officers in the search with the entity's name and month of
+// birth. Without a date of birth an officer can't be told apart from others of the same
+// name, so isn't matched
+fn matching_disqualified_officers(
+    entity: &Entity,
+    search: &DisqualifiedOfficerSearch,
+) -> Vec<String> {
+    let (name, date_of_birth) = match (&entity.name, entity.partial_date_of_origin()) {
+        (Some(name), Some(date_of_birth)) => (normalize_name(name), date_of_birth),
+        _ => return vec![],
+    };
+
+    search
+        .items
+        .iter()
+        .flatten()
+        .filter(|item| item.title.as_deref().map(normalize_name).as_ref() == Some(&name))
+        .filter(|item| {
+            item.date_of_birth.is_some_and(|dob| {
+                dob.year() == date_of_birth.year && dob.month() == date_of_birth.month
+            })
+        })
+        .filter_map(|item| item.links.as_ref()?.self_.clone())
+        .collect()
+}
+
+fn is_disqualified(disqualifications: &[Disqualification], today: NaiveDate) -> bool {
+    disqualifications
+        .iter()
+        .any(|disqualification| disqualification.is_current_at(today))
+}
+
+// Walks every ownership chain up from the root entity. Each step narrows the ownership
+// range by the shareholder's band, and owners reached by several chains have the
+// ranges of each summed. Densely cross held groups have too many chains to walk, only the
+// first MAX_OWNERSHIP_PATHS are
+//
+// Owners are ultimate if they're individuals, or companies whose shareholders were fetched
+// and turned out to have none. Companies the check stopped expanding at are unresolved
+pub fn compute_beneficial_owners(
+    check_id: Uuid,
+    root_id: Uuid,
+    ownership_relations: &[(Uuid, Uuid, OwnershipBand)],
+    individuals: &HashSet<Uuid>,
+    shareholders_fetched: &HashSet<Uuid>,
+) -> (Vec<BeneficialOwner>, Vec<BeneficialOwnerPath>) {
+    let mut shareholders: HashMap<Uuid, Vec<(Uuid, OwnershipBand)>> = HashMap::new();
+    for (parent_id, child_id, ownership_band) in ownership_relations {
+        shareholders
+            .entry(*child_id)
+            .or_default()
+            .push((*parent_id, *ownership_band));
+    }
+
+    let mut paths = Vec::new();
+    // (entity, path to it excluding the root, min ownership, max ownership)
+    let mut stack = vec![(root_id, vec![], 1.0, 1.0)];
+    'walk: while let Some((entity_id, path, min_ownership, max_ownership)) = stack.pop() {
+        for (shareholder_id, ownership_band) in shareholders.get(&entity_id).into_iter().flatten() {
+            if paths.len() >= MAX_OWNERSHIP_PATHS {
+                warn!(
+                    "Check with ID: {:?} has more than {} ownership paths, the rest aren't walked",
+                    check_id, MAX_OWNERSHIP_PATHS
+                );
+                break 'walk;
+            }
+
+            // circular ownership is only followed once around
+            if *shareholder_id == root_id || path.contains(shareholder_id) {
+                continue;
+            }
+
+            let (band_min, band_max) = ownership_band.range();
+            let mut shareholder_path = path.clone();
+            shareholder_path.push(*shareholder_id);
+
+            paths.push(BeneficialOwnerPath {
+                id: Uuid::new_v4(),
+                check_id,
+                entity_id: *shareholder_id,
+                path: shareholder_path.clone(),
+                min_ownership: min_ownership * band_min,
+                max_ownership: max_ownership * band_max,
+            });
+            stack.push((
+                *shareholder_id,
+                shareholder_path,
+                min_ownership * band_min,
+                max_ownership * band_max,
+            ));
+        }
+    }
+
+    let mut owners: HashMap<Uuid, BeneficialOwner> = HashMap::new();
+    for path in &paths {
+        let owner = owners
+            .entry(path.entity_id)
+            .or_insert_with(|| BeneficialOwner {
+                check_id,
+                entity_id: path.entity_id,
+                min_ownership: 0.0,
+                max_ownership: 0.0,
+                is_ultimate: individuals.contains(&path.entity_id)
+                    || (!shareholders.contains_key(&path.entity_id)
+                        && shareholders_fetched.contains(&path.entity_id)),
+            });
+        owner.min_ownership = (owner.min_ownership + path.min_ownership).min(1.0);
+        owner.max_ownership = (owner.max_ownership + path.max_ownership).min(1.0);
+    }
+
+    (owners.into_values().collect(), paths)
+}
+
+fn is_usual_lender(charge_holder: &Entity) -> bool {
+    let words: Vec<String> = charge_holder
+        .name
+        .as_deref()
+        .unwrap_or_default()
+        .to_uppercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+    // padded so terms only match whole words
+    let name = format!(" {} ", words.join(" "));
+
+    USUAL_LENDER_TERMS
+        .iter()
+        .any(|term| name.contains(&format!(" {} ", term)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::fixture_client::FixtureRegistryClient;
+
+    fn fixture_client() -> FixtureRegistryClient {
+        FixtureRegistryClient::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/registry"))
+    }
+
+    #[test]
+    fn outlier_age_bounds_use_the_youngest_and_oldest_possible_ages() {
+        // 85 or 86 in June 2056, depending on the day of birth
+        let dob = PartialDate::new(Some(1970), Some(6), None).unwrap();
+        assert!(is_outlier_age(
+            &dob,
+            NaiveDate::from_ymd_opt(2056, 6, 15).unwrap()
+        ));
+        assert!(!is_outlier_age(
+            &dob,
+            NaiveDate::from_ymd_opt(2056, 5, 31).unwrap()
+        ));
+
+        // 14 or 15 in June 1985
+        assert!(is_outlier_age(
+            &dob,
+            NaiveDate::from_ymd_opt(1985, 6, 15).unwrap()
+        ));
+        assert!(!is_outlier_age(
+            &dob,
+            NaiveDate::from_ymd_opt(1985, 7, 1).unwrap()
+        ));
+    }
+
+    fn officer() -> Entity {
+        let mut entity = Entity {
+            kind: Entitykind::Individual,
+            name: Some("SMITH, Jane".to_string()),
+            ..Default::default()
+        };
+        entity.set_date_of_origin(PartialDate::new(Some(1970), Some(6), None));
+        entity
+    }
+
+    #[test]
+    fn usual_lenders_are_matched_on_whole_words() {
+        let lender = |name: &str| Entity {
+            kind: Entitykind::Company,
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        assert!(is_usual_lender(&lender("HSBC UK Bank PLC")));
+        assert!(is_usual_lender(&lender("Nationwide Building Society")));
+        assert!(is_usual_lender(&lender(
+            "Lombard North Central (Leasing) Ltd"
+        )));
+        assert!(is_usual_lender(&lender(
+            "GLAS Trust Corporation (Security Agent)"
+        )));
+        assert!(!is_usual_lender(&lender("Bankside Properties Limited")));
+        assert!(!is_usual_lender(&lender("John Smith")));
+        assert!(!is_usual_lender(&Entity::default()));
+    }
+
+    #[tokio::test]
+    async fn disqualified_officers_are_matched_by_name_and_birth_month() {
+        let search = fixture_client()
+            .search_disqualified_officers("SMITH, Jane")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            matching_disqualified_officers(&officer(), &search.response),
+            vec![
+                "/disqualified-officers/natural/Dq456Natural".to_string(),
+                "/disqualified-officers/natural/Dq000Removed".to_string(),
+            ]
+        );
+
+        let mut without_date_of_birth = officer();
+        without_date_of_birth.set_date_of_origin(None);
+        assert!(
+            matching_disqualified_officers(&without_date_of_birth, &search.response).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_disqualifications_dont_disqualify() {
+        let disqualified_officer = fixture_client()
+            .get_disqualifications("/disqualified-officers/natural/Dq456Natural")
+            .await
+            .unwrap()
+            .unwrap();
+        let disqualifications = Disqualification::from_disqualified_officer(
+            Uuid::new_v4(),
+            &disqualified_officer.response,
+        );
+
+        // disqualified until 2025-06-01
+        assert!(is_disqualified(
+            &disqualifications,
+            NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+        ));
+        assert!(!is_disqualified(
+            &disqualifications,
+            NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn disqualified_officers_not_found_arent_disqualified() {
+        let disqualified_officer = fixture_client()
+            .get_disqualifications("/disqualified-officers/natural/Dq000Removed")
+            .await
+            .unwrap();
+
+        assert!(disqualified_officer.is_none());
+        assert!(!is_disqualified(&[], Utc::now().date_naive()));
+    }
+
+    #[test]
+    fn beneficial_ownership_multiplies_along_chains() {
+        let (check_id, root_id, holding_id, individual_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        let ownership_relations = vec![
+            (holding_id, root_id, OwnershipBand::SeventyFiveToHundred),
+            (individual_id, holding_id, OwnershipBand::FiftyToSeventyFive),
+            (individual_id, root_id, OwnershipBand::TwentyFiveToFifty),
+        ];
+
+        let (owners, paths) = compute_beneficial_owners(
+            check_id,
+            root_id,
+            &ownership_relations,
+            &HashSet::from([individual_id]),
+            &HashSet::from([root_id, holding_id]),
+        );
+
+        assert_eq!(paths.len(), 3);
+        let individual = owners
+            .iter()
+            .find(|owner| owner.entity_id == individual_id)
+            .unwrap();
+        assert!(individual.is_ultimate);
+        assert_eq!(individual.min_ownership, 0.75 * 0.5 + 0.25);
+        assert_eq!(individual.max_ownership, 1.0);
+        let holding = owners
+            .iter()
+            .find(|owner| owner.entity_id == holding_id)
+            .unwrap();
+        assert!(!holding.is_ultimate);
+    }
+
+    #[test]
+    fn companies_not_expanded_are_not_ultimate_owners() {
+        let (check_id, root_id, holding_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let ownership_relations = vec![(holding_id, root_id, OwnershipBand::SeventyFiveToHundred)];
+        let is_ultimate = |shareholders_fetched: HashSet<Uuid>| {
+            compute_beneficial_owners(
+                check_id,
+                root_id,
+                &ownership_relations,
+                &HashSet::new(),
+                &shareholders_fetched,
+            )
+            .0[0]
+                .is_ultimate
+        };
+
+        // the check stopped at the holding company, its owners are unknown
+        assert!(!is_ultimate(HashSet::from([root_id])));
+        // its shareholders were fetched and it has none
+        assert!(is_ultimate(HashSet::from([root_id, holding_id])));
+    }
+
+    #[test]
+    fn checks_stopped_at_their_job_limit_still_have_owners_and_exposures() {
+        let [check_id, root_id, holding_id, unexpanded_id, individual_id, flag_id] =
+            [(); 6].map(|_| Uuid::new_v4());
+        let shareholding = |parent_id, child_id, ownership_band| Relationship {
+            parent_id,
+            child_id,
+            kind: Relationshipkind::Shareholder,
+            started_on: None,
+            ended_on: None,
+            ownership_band: Some(ownership_band),
+        };
+        let relationships = vec![
+            shareholding(holding_id, root_id, OwnershipBand::FiftyToSeventyFive),
+            shareholding(
+                individual_id,
+                holding_id,
+                OwnershipBand::SeventyFiveToHundred,
+            ),
+            shareholding(unexpanded_id, root_id, OwnershipBand::TwentyFiveToFifty),
+        ];
+        let ownership_relations: Vec<(Uuid, Uuid, OwnershipBand)> = relationships
+            .iter()
+            .map(|relationship| {
+                (
+                    relationship.parent_id,
+                    relationship.child_id,
+                    relationship.ownership_band.unwrap(),
+                )
+            })
+            .collect();
+
+        // the job fetching the shareholders of one company was dropped at the job limit, and
+        // taken off the frontier, so the check's global risk jobs still run
+        let (owners, _) = compute_beneficial_owners(
+            check_id,
+            root_id,
+            &ownership_relations,
+            &HashSet::from([individual_id]),
+            &HashSet::from([root_id, holding_id]),
+        );
+        let owner = |entity_id| {
+            owners
+                .iter()
+                .find(|owner| owner.entity_id == entity_id)
+                .unwrap()
+        };
+        assert!(owner(individual_id).is_ultimate);
+        assert_eq!(owner(individual_id).min_ownership, 0.5 * 0.75);
+        assert!(!owner(unexpanded_id).is_ultimate);
+
+        let exposures = compute_exposures(
+            check_id,
+            &[(individual_id, flag_id, Flagkind::SanctionedEntity)],
+            &relationships,
+            &PropagationPolicy::default(),
+        );
+        let root_exposure = exposures
+            .iter()
+            .find(|exposure| exposure.entity_id == root_id)
+            .unwrap();
+        assert_eq!(root_exposure.path, vec![individual_id, holding_id, root_id]);
+        assert!(root_exposure.score > 0.0);
+    }
+
+    #[test]
+    fn ownership_paths_walked_are_capped() {
+        // each company is owned by both companies of the layer above, doubling the chains
+        // with every layer
+        let root_id = Uuid::new_v4();
+        let mut layer = vec![root_id];
+        let mut ownership_relations = vec![];
+        for _ in 0..16 {
+            let owners = [Uuid::new_v4(), Uuid::new_v4()];
+            for company_id in &layer {
+                for owner_id in owners {
+                    ownership_relations.push((
+                        owner_id,
+                        *company_id,
+                        OwnershipBand::FiftyToSeventyFive,
+                    ));
+                }
+            }
+            layer = owners.to_vec();
+        }
+
+        let (_, paths) = compute_beneficial_owners(
+            Uuid::new_v4(),
+            root_id,
+            &ownership_relations,
+            &HashSet::new(),
+            &HashSet::new(),
+        );
+
+        assert_eq!(paths.len(), MAX_OWNERSHIP_PATHS);
+    }
 }
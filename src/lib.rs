@@ -1,8 +1,16 @@
+pub mod auth;
+pub mod batch;
+pub mod check_query;
+pub mod check_response;
 pub mod company_house;
+pub mod graph;
 pub mod jobs;
 pub mod models;
 mod open_sanctions;
 pub mod postgres;
 pub mod pulsar;
+pub mod registry;
+pub mod report;
+pub mod risk;
 pub mod schema;
 pub mod workers;
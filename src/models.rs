@@ -1,8 +1,8 @@
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
 use diesel::deserialize::{self, FromSql, FromSqlRow};
 use diesel::expression::AsExpression;
 use diesel::pg::{Pg, PgValue};
-use diesel::prelude::{Insertable, Queryable};
+use diesel::prelude::{AsChangeset, Insertable, Queryable};
 use diesel::serialize::{self, IsNull, Output, ToSql};
 use diesel::Selectable;
 use log::warn;
@@ -12,19 +12,49 @@ use uuid::Uuid;
 
 use crate::company_house::company_house_streaming_types::CompanyData;
 use crate::company_house::company_house_types::{
-    AppointmentListItem, AppointmentsResponse, CompanyItem, Identification, OfficerListItem,
-    OfficerListResponse, ShareholderList, ShareholderListItem,
+    AppointmentListItem, AppointmentsResponse, ChargeList, CompanyInsolvency, CompanyItem,
+    DisqualifiedOfficer, Identification, OfficerListItem, OfficerListResponse, ShareholderList,
+    ShareholderListItem,
 };
 use crate::jobs::streaming_update_jobs::UpdateKind;
 use crate::workers::streaming_worker::StreamingKind;
 
 type CompanyHouseNumber = String;
 
-#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, AsExpression, FromSqlRow, Serialize, Deserialize,
+)]
 #[diesel(sql_type = crate::schema::sql_types::Relationshipkind)]
 pub enum Relationshipkind {
     Shareholder,
     Officer,
+    ChargeHolder,
+}
+
+impl Relationshipkind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relationshipkind::Shareholder => "shareholder",
+            Relationshipkind::Officer => "officer",
+            Relationshipkind::ChargeHolder => "charge_holder",
+        }
+    }
+}
+
+impl std::str::FromStr for Relationshipkind {
+    type Err = failure::Error;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "shareholder" => Ok(Relationshipkind::Shareholder),
+            "officer" => Ok(Relationshipkind::Officer),
+            "charge_holder" => Ok(Relationshipkind::ChargeHolder),
+            _ => Err(failure::format_err!(
+                "Unrecognized relationship kind {}",
+                kind
+            )),
+        }
+    }
 }
 
 impl ToSql<crate::schema::sql_types::Relationshipkind, Pg> for Relationshipkind {
@@ -32,6 +62,7 @@ impl ToSql<crate::schema::sql_types::Relationshipkind, Pg> for Relationshipkind
         match *self {
             Relationshipkind::Shareholder => out.write_all(b"shareholder")?,
             Relationshipkind::Officer => out.write_all(b"officer")?,
+            Relationshipkind::ChargeHolder => out.write_all(b"charge_holder")?,
         }
         Ok(IsNull::No)
     }
@@ -42,12 +73,15 @@ impl FromSql<crate::schema::sql_types::Relationshipkind, Pg> for Relationshipkin
         match bytes.as_bytes() {
             b"shareholder" => Ok(Relationshipkind::Shareholder),
             b"officer" => Ok(Relationshipkind::Officer),
+            b"charge_holder" => Ok(Relationshipkind::ChargeHolder),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
 }
 
-#[derive(Debug, AsExpression, FromSqlRow, Default, Serialize, Deserialize, PartialEq)]
+#[derive(
+    Debug, Clone, Copy, AsExpression, FromSqlRow, Default, Serialize, Deserialize, PartialEq,
+)]
 #[diesel(sql_type = crate::schema::sql_types::Entitykind)]
 pub enum Entitykind {
     #[default]
@@ -55,6 +89,15 @@ pub enum Entitykind {
     Individual,
 }
 
+impl Entitykind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entitykind::Company => "company",
+            Entitykind::Individual => "individual",
+        }
+    }
+}
+
 impl ToSql<crate::schema::sql_types::Entitykind, Pg> for Entitykind {
     fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
         match *self {
@@ -129,9 +172,18 @@ pub struct Entity {
     pub kind: Entitykind,
     pub country: Option<String>,
     pub postal_code: Option<String>,
-    pub date_of_origin: Option<String>,
+    // date of birth for individuals, date of creation for companies
+    pub date_of_origin: Option<NaiveDate>,
     pub is_root: bool,
     pub officer_id: Option<String>,
+    // false when only the month and year of date_of_origin are known
+    pub date_of_origin_day_known: bool,
+    // the same person or company across every check, set when the entity is inserted
+    #[diesel(skip_insertion)]
+    pub canonical_entity_id: Option<Uuid>,
+    // the registry response the entity was found in, set when the entity is inserted
+    #[diesel(skip_insertion)]
+    pub source_fetch_id: Option<Uuid>,
 }
 
 impl Entity {
@@ -143,6 +195,219 @@ impl Entity {
             ..Default::default()
         }
     }
+
+    pub fn partial_date_of_origin(&self) -> Option<PartialDate> {
+        self.date_of_origin
+            .map(|date| PartialDate::from_columns(date, self.date_of_origin_day_known))
+    }
+
+    // Identifiers the entity can be resolved across checks by, strongest first
+    pub fn canonical_keys(&self) -> Vec<(&'static str, String)> {
+        let mut keys = Vec::new();
+
+        if !self.company_house_number.is_empty() {
+            match self.kind {
+                Entitykind::Individual => {
+                    keys.push((PERSON_NUMBER_KEY, self.company_house_number.clone()))
+                }
+                Entitykind::Company => {
+                    keys.push((REGISTRATION_NUMBER_KEY, self.company_house_number.clone()))
+                }
+            }
+        }
+
+        if let Some(officer_id) = &self.officer_id {
+            keys.push((OFFICER_ID_KEY, officer_id.clone()));
+        }
+
+        // companies without a registry number, i.e. lenders holding charges
+        if self.kind == Entitykind::Company && self.company_house_number.is_empty() {
+            if let Some(name) = &self.name {
+                let name = normalize_name(name);
+                if !name.is_empty() {
+                    keys.push((LENDER_NAME_KEY, name));
+                }
+            }
+        }
+
+        // individual shareholders have neither a person number nor an officer id, so
+        // individuals are also matched on their name and month of birth, which officer and psc
+        // lists give for most. Those without a date of birth aren't matched on name alone
+        if self.kind == Entitykind::Individual {
+            if let (Some(name), Some(date_of_birth)) = (&self.name, self.partial_date_of_origin()) {
+                let name = normalize_name(name);
+                if !name.is_empty() {
+                    keys.push((
+                        NAME_AND_BIRTH_MONTH_KEY,
+                        format!(
+                            "{}|{:04}-{:02}",
+                            name, date_of_birth.year, date_of_birth.month
+                        ),
+                    ));
+                }
+            }
+        }
+
+        keys
+    }
+
+    pub fn set_date_of_origin(&mut self, date_of_origin: Option<PartialDate>) {
+        (self.date_of_origin, self.date_of_origin_day_known) =
+            PartialDate::to_columns(date_of_origin);
+    }
+}
+
+pub const PERSON_NUMBER_KEY: &str = "person_number";
+pub const REGISTRATION_NUMBER_KEY: &str = "registration_number";
+pub const OFFICER_ID_KEY: &str = "officer_id";
+pub const NAME_AND_BIRTH_MONTH_KEY: &str = "name_and_birth_month";
+pub const LENDER_NAME_KEY: &str = "lender_name";
+
+// Upper case name words in alphabetical order, so "SMITH, Jane" and "Jane Smith" match
+pub fn normalize_name(name: &str) -> String {
+    let mut words: Vec<String> = name
+        .to_uppercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+    words.sort();
+
+    words.join(" ")
+}
+
+// A single real world person or company, which entities in different checks resolve to
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::canonical_entity)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CanonicalEntity {
+    pub id: Uuid,
+    pub kind: Entitykind,
+    pub name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::canonical_entity_key)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CanonicalEntityKey {
+    pub key_kind: String,
+    pub key_value: String,
+    pub canonical_entity_id: Uuid,
+}
+
+// A date which may be missing its day, company house only publishes the month and
+// year of birth for individuals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: Option<u32>,
+}
+
+impl PartialDate {
+    // None unless there is at least a valid month and year
+    pub fn new(year: Option<i32>, month: Option<i32>, day: Option<i32>) -> Option<Self> {
+        let year = year?;
+        let month = u32::try_from(month?).ok()?;
+        // company house uses 0 for an unknown day
+        let day = day
+            .filter(|day| *day != 0)
+            .map(u32::try_from)
+            .transpose()
+            .ok()?;
+
+        NaiveDate::from_ymd_opt(year, month, day.unwrap_or(1))?;
+
+        Some(Self { year, month, day })
+    }
+
+    pub fn from_date(date: NaiveDate) -> Self {
+        Self {
+            year: date.year(),
+            month: date.month(),
+            day: Some(date.day()),
+        }
+    }
+
+    // Parses "YYYY-MM-DD" or "YYYY-MM"
+    pub fn parse(date: &str) -> Option<Self> {
+        let mut parts = date.trim().split('-').map(|part| part.parse::<i32>().ok());
+        let year = parts.next()??;
+        let month = parts.next()??;
+        let day = match parts.next() {
+            Some(day) => Some(day?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Self::new(Some(year), Some(month), day)
+    }
+
+    // Stored as the first day of the month when the day is unknown
+    pub fn to_columns(date: Option<Self>) -> (Option<NaiveDate>, bool) {
+        match date {
+            Some(date) => (
+                NaiveDate::from_ymd_opt(date.year, date.month, date.day.unwrap_or(1)),
+                date.day.is_some(),
+            ),
+            None => (None, true),
+        }
+    }
+
+    pub fn from_columns(date: NaiveDate, day_known: bool) -> Self {
+        Self {
+            day: day_known.then(|| date.day()),
+            ..Self::from_date(date)
+        }
+    }
+
+    // Age in whole years at the given date, when the day is unknown the youngest
+    // possible age is used
+    pub fn age_at(&self, date: NaiveDate) -> Option<u32> {
+        let latest_birth_date = match self.day {
+            Some(day) => NaiveDate::from_ymd_opt(self.year, self.month, day)?,
+            None => {
+                let (year, month) = match self.month {
+                    12 => (self.year + 1, 1),
+                    month => (self.year, month + 1),
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)?.pred_opt()?
+            }
+        };
+
+        date.years_since(latest_birth_date)
+    }
+
+    // As age_at, but when the day is unknown the oldest possible age is used
+    pub fn oldest_age_at(&self, date: NaiveDate) -> Option<u32> {
+        let earliest_birth_date =
+            NaiveDate::from_ymd_opt(self.year, self.month, self.day.unwrap_or(1))?;
+
+        date.years_since(earliest_birth_date)
+    }
+
+    // Whether two dates could be the same, only the components known on both sides
+    // are compared
+    pub fn matches(&self, other: &PartialDate) -> bool {
+        self.year == other.year
+            && self.month == other.month
+            && match (self.day, other.day) {
+                (Some(day), Some(other_day)) => day == other_day,
+                _ => true,
+            }
+    }
+
+    // As matches, for dates from other sources which may be "YYYY", "YYYY-MM" or
+    // "YYYY-MM-DD"
+    pub fn matches_str(&self, date: &str) -> bool {
+        match date.trim().parse::<i32>() {
+            Ok(year) => self.year == year,
+            Err(_) => PartialDate::parse(date).is_some_and(|other| self.matches(&other)),
+        }
+    }
 }
 
 impl From<CompanyData> for Entity {
@@ -159,9 +424,62 @@ impl From<CompanyData> for Entity {
             kind: Entitykind::Company,
             country,
             postal_code,
-            date_of_origin: company_data.date_of_creation,
+            date_of_origin: parse_date(&company_data.date_of_creation),
             is_root: false,
             officer_id: None,
+            date_of_origin_day_known: true,
+            canonical_entity_id: None,
+            source_fetch_id: None,
+        }
+    }
+}
+
+// Details of an entity which can be filled in after it was first recorded,
+// fields which are None are left unchanged
+#[derive(AsChangeset, Default)]
+#[diesel(table_name = crate::schema::entity)]
+pub struct EntityDetails {
+    pub name: Option<String>,
+    pub country: Option<String>,
+    pub postal_code: Option<String>,
+    pub date_of_origin: Option<NaiveDate>,
+    pub date_of_origin_day_known: Option<bool>,
+}
+
+impl EntityDetails {
+    pub fn from_date_of_origin(date_of_origin: Option<PartialDate>) -> Self {
+        match PartialDate::to_columns(date_of_origin) {
+            (Some(date_of_origin), day_known) => Self {
+                date_of_origin: Some(date_of_origin),
+                date_of_origin_day_known: Some(day_known),
+                ..Default::default()
+            },
+            (None, _) => Self::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.country.is_none()
+            && self.postal_code.is_none()
+            && self.date_of_origin.is_none()
+            && self.date_of_origin_day_known.is_none()
+    }
+}
+
+impl From<&CompanyData> for EntityDetails {
+    fn from(company_data: &CompanyData) -> Self {
+        let (country, postal_code) = match &company_data.registered_office_address {
+            Some(address) => (address.country.clone(), address.postal_code.clone()),
+            None => (None, None),
+        };
+
+        Self {
+            name: company_data.company_name.clone(),
+            country,
+            postal_code,
+            date_of_origin: parse_date(&company_data.date_of_creation),
+            date_of_origin_day_known: Some(true),
         }
     }
 }
@@ -170,6 +488,87 @@ pub struct EntityRelation {
     pub entity: Entity,
     pub started_on: Option<NaiveDate>,
     pub ended_on: Option<NaiveDate>,
+    // only known for shareholders
+    pub ownership_band: Option<OwnershipBand>,
+}
+
+// Share ownership bands persons with significant control are reported in
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(sql_type = crate::schema::sql_types::Ownershipband)]
+pub enum OwnershipBand {
+    // more than 25% up to 50%
+    TwentyFiveToFifty,
+    // more than 50% but less than 75%
+    FiftyToSeventyFive,
+    // 75% or more
+    SeventyFiveToHundred,
+}
+
+impl ToSql<crate::schema::sql_types::Ownershipband, Pg> for OwnershipBand {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            OwnershipBand::TwentyFiveToFifty => out.write_all(b"twenty_five_to_fifty")?,
+            OwnershipBand::FiftyToSeventyFive => out.write_all(b"fifty_to_seventy_five")?,
+            OwnershipBand::SeventyFiveToHundred => out.write_all(b"seventy_five_to_hundred")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::Ownershipband, Pg> for OwnershipBand {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"twenty_five_to_fifty" => Ok(OwnershipBand::TwentyFiveToFifty),
+            b"fifty_to_seventy_five" => Ok(OwnershipBand::FiftyToSeventyFive),
+            b"seventy_five_to_hundred" => Ok(OwnershipBand::SeventyFiveToHundred),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+impl OwnershipBand {
+    // Lower and upper bound of the share of ownership, as a fraction
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            OwnershipBand::TwentyFiveToFifty => (0.25, 0.5),
+            OwnershipBand::FiftyToSeventyFive => (0.5, 0.75),
+            OwnershipBand::SeventyFiveToHundred => (0.75, 1.0),
+        }
+    }
+
+    // The highest share ownership band in a PSC's nature of control, e.g.
+    // "ownership-of-shares-25-to-50-percent-as-trust". None for persons with only
+    // voting rights, appointment rights or significant influence
+    pub fn from_nature_of_control(nature_of_control: &[String]) -> Option<Self> {
+        nature_of_control
+            .iter()
+            .filter_map(|nature| {
+                let band = nature.strip_prefix("ownership-of-shares-")?;
+
+                if band.starts_with("25-to-50-percent") {
+                    Some(OwnershipBand::TwentyFiveToFifty)
+                } else if band.starts_with("50-to-75-percent") {
+                    Some(OwnershipBand::FiftyToSeventyFive)
+                } else if band.starts_with("75-to-100-percent") {
+                    Some(OwnershipBand::SeventyFiveToHundred)
+                } else {
+                    None
+                }
+            })
+            .max()
+    }
 }
 
 impl From<OfficerListResponse> for Vec<EntityRelation> {
@@ -218,60 +617,94 @@ impl From<AppointmentsResponse> for Vec<EntityRelation> {
     }
 }
 
+// Charge holders are only identified by name, which is used as their entity number
+// so that a lender holding several charges is recorded as a single entity
+impl From<ChargeList> for Vec<EntityRelation> {
+    fn from(charges: ChargeList) -> Self {
+        let mut entity_relations: Vec<EntityRelation> = Vec::new();
+        for charge in charges.items.unwrap_or_default() {
+            for person_entitled in charge.persons_entitled.unwrap_or_default() {
+                let name = match person_entitled.name {
+                    Some(name) => name,
+                    None => {
+                        warn!("Failed to convert charge holder into an entity.");
+                        continue;
+                    }
+                };
+
+                // lenders have no registry number, so are resolved by their name
+                entity_relations.push(EntityRelation {
+                    entity: Entity {
+                        id: Uuid::new_v4(),
+                        company_house_number: String::new(),
+                        name: Some(name),
+                        kind: Entitykind::Company,
+                        ..Default::default()
+                    },
+                    started_on: charge.created_on,
+                    ended_on: charge.satisfied_on,
+                    ownership_band: None,
+                })
+            }
+        }
+        entity_relations
+    }
+}
+
 impl TryFrom<(ShareholderListItem, bool)> for EntityRelation {
     type Error = ();
 
     fn try_from(value: (ShareholderListItem, bool)) -> Result<Self, Self::Error> {
         let shareholder = value.0;
         let is_root = value.1;
-        let shareholder_identification = match shareholder.identification {
-            Some(identification) => identification,
-            None => return Err(()),
-        };
-        let company_house_number = match shareholder_identification.registration_number {
-            Some(registration_numer) => registration_numer,
-            None => return Err(()),
-        };
+        let kind: Entitykind = shareholder.kind.into();
         let (country, postal_code) = match shareholder.address {
             Some(address) => (address.country, address.postal_code),
             None => (None, None),
         };
 
-        let date_of_origin = match shareholder.date_of_birth {
-            Some(dob) => {
-                let day = dob.day.unwrap_or_else(|| 0);
-                let month = dob.month.unwrap_or_else(|| 0);
-
-                if let Some(year) = dob.year {
-                    Some(format!(
-                        "{}/{}/{}",
-                        day.to_string(),
-                        month.to_string(),
-                        year.to_string()
-                    ))
-                } else {
-                    None
+        let date_of_origin = shareholder
+            .date_of_birth
+            .and_then(|dob| PartialDate::new(dob.year, dob.month, dob.day));
+
+        // only corporate PSCs have a registration number. Individuals are left without a
+        // number and resolved on their name and month of birth, so need both
+        let company_house_number = match kind {
+            Entitykind::Company => shareholder
+                .identification
+                .and_then(|identification| identification.registration_number)
+                .ok_or(())?,
+            Entitykind::Individual => {
+                if shareholder.name.is_none() || date_of_origin.is_none() {
+                    return Err(());
                 }
+                String::new()
             }
-            None => None,
         };
 
-        let entity = Entity {
+        let mut entity = Entity {
             id: Uuid::new_v4(),
             company_house_number,
             officer_id: None,
             name: shareholder.name,
-            kind: shareholder.kind.into(),
+            kind,
             country: country,
             postal_code: postal_code,
-            date_of_origin,
             is_root,
+            ..Default::default()
         };
+        entity.set_date_of_origin(date_of_origin);
+
+        let ownership_band = shareholder
+            .nature_of_control
+            .as_deref()
+            .and_then(OwnershipBand::from_nature_of_control);
 
         Ok(Self {
             entity,
             started_on: shareholder.notified_on,
             ended_on: shareholder.ceased_on,
+            ownership_band,
         })
     }
 }
@@ -311,14 +744,17 @@ impl TryFrom<(OfficerListItem, bool)> for EntityRelation {
             None => (None, None),
         };
 
-        let doi = Some("00/00/0000".to_string()); // TODO THIS PROPERLY
+        let date_of_origin = officer
+            .date_of_birth
+            .as_ref()
+            .and_then(|dob| PartialDate::new(dob.year, dob.month, dob.day));
 
         let name = officer.name.clone();
         let started_on = officer.appointed_on.clone();
         let ended_on = officer.resigned_on.clone();
         let officer_id = extract_officer_id(officer);
 
-        let entity = Entity {
+        let mut entity = Entity {
             id: Uuid::new_v4(),
             company_house_number,
             officer_id: officer_id.ok(),
@@ -326,14 +762,16 @@ impl TryFrom<(OfficerListItem, bool)> for EntityRelation {
             kind: entity_kind,
             country: country,
             postal_code: postal_code,
-            date_of_origin: doi,
             is_root,
+            ..Default::default()
         };
+        entity.set_date_of_origin(date_of_origin);
 
         Ok(Self {
             entity,
             started_on,
             ended_on,
+            ownership_band: None,
         })
     }
 }
@@ -360,14 +798,15 @@ impl TryFrom<AppointmentListItem> for EntityRelation {
             kind: Entitykind::Company,
             country: None,
             postal_code: None,
-            date_of_origin: None,
             is_root: false,
+            ..Default::default()
         };
 
         Ok(Self {
             entity,
             started_on: appointment.appointed_on,
             ended_on: appointment.resigned_on,
+            ownership_band: None,
         })
     }
 }
@@ -387,9 +826,75 @@ impl TryFrom<CompanyItem> for Entity {
 pub struct CheckEntityMap {
     pub check_id: Uuid,
     pub entity_id: Uuid,
+    // unique within the check
+    pub canonical_entity_id: Option<Uuid>,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::check_traversal_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckTraversalPolicy {
+    pub check_id: Uuid,
+    pub policy: serde_json::Value,
+}
+
+// A check as listed on the checks page, refreshed whenever one of its jobs completes
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::check_summary)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckSummary {
+    pub check_id: Uuid,
+    pub root_entity_id: Option<Uuid>,
+    pub company_house_number: Option<String>,
+    pub name: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+    pub has_error: bool,
+    pub entity_count: i64,
+    pub distinct_flags: Vec<Flagkind>,
+    pub updated_at: NaiveDateTime,
+    pub kind: Checkkind,
+    pub risk_score: f64,
+    // Low, Medium or High
+    pub risk_level: String,
+    pub tenant_id: Uuid,
+    pub status: CheckStatus,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::check_report)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckReport {
+    pub id: Uuid,
+    pub check_id: Uuid,
+    // html or pdf
+    pub format: String,
+    pub content: Vec<u8>,
+    pub content_hash: String,
+    pub generated_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::check_propagation_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckPropagationPolicy {
+    pub check_id: Uuid,
+    pub policy: serde_json::Value,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::check_frontier)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckFrontier {
+    pub check_id: Uuid,
+    pub entity_id: Uuid,
+    pub relation_job_kind: String,
+    pub remaining_depth: i32,
+    pub enqueued_at: NaiveDateTime,
+    pub fetched_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::relationship)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Relationship {
@@ -398,6 +903,137 @@ pub struct Relationship {
     pub kind: Relationshipkind,
     pub started_on: Option<NaiveDate>,
     pub ended_on: Option<NaiveDate>,
+    pub ownership_band: Option<OwnershipBand>,
+}
+
+impl Relationship {
+    // Relationships with no start or end date are assumed to extend indefinitely
+    pub fn is_active_at(&self, date: NaiveDate) -> bool {
+        self.started_on
+            .map_or(true, |started_on| started_on <= date)
+            && self.ended_on.map_or(true, |ended_on| ended_on >= date)
+    }
+}
+
+// An owner of a check's root entity, through any number of intermediate companies
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::beneficial_owner)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BeneficialOwner {
+    pub check_id: Uuid,
+    pub entity_id: Uuid,
+    pub min_ownership: f64,
+    pub max_ownership: f64,
+    // the owner is an individual, or a company with no known shareholders of its own
+    pub is_ultimate: bool,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::beneficial_owner_path)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BeneficialOwnerPath {
+    pub id: Uuid,
+    pub check_id: Uuid,
+    pub entity_id: Uuid,
+    // shareholders from the one closest to the root entity up to the owner
+    pub path: Vec<Uuid>,
+    pub min_ownership: f64,
+    pub max_ownership: f64,
+}
+
+// Risk pushed onto an entity from a flag on an entity it's related to, the score
+// decays with every relationship along the path
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::exposure)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Exposure {
+    pub id: Uuid,
+    pub check_id: Uuid,
+    pub entity_id: Uuid,
+    pub origin_entity_id: Uuid,
+    pub origin_flag_id: Uuid,
+    pub flag_kind: Flagkind,
+    // from the flagged entity to the exposed entity
+    pub path: Vec<Uuid>,
+    pub score: f64,
+}
+
+// An organisation using the service, every check belongs to one
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::tenant)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Tenant {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::tenant_user)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TenantUser {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub email: String,
+    pub name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::api_key)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    // keys made for a user act as that user, others act for the tenant as a whole
+    pub user_id: Option<Uuid>,
+    pub name: String,
+    // hex encoded sha256 of the key
+    pub key_hash: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+// A mutating api call, kept for as long as the tenant exists
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::audit_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub api_key_id: Option<Uuid>,
+    pub method: String,
+    pub path: String,
+    // the check, report or entity acted on, if any
+    pub target_id: Option<Uuid>,
+    pub status: i32,
+    pub occurred_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::raw_payload)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RawPayload {
+    // hex encoded sha256 of the response body as received
+    pub response_hash: String,
+    // the response body as received
+    pub body: Vec<u8>,
+}
+
+// One response from an external source, which entities, relationships and flags
+// record as their provenance
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::source_fetch)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SourceFetch {
+    pub id: Uuid,
+    pub check_id: Uuid,
+    pub source: String,
+    pub endpoint: String,
+    pub fetched_at: NaiveDateTime,
+    pub response_hash: String,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -407,6 +1043,276 @@ pub struct Check {
     pub id: Uuid,
     pub started_at: NaiveDateTime,
     pub kind: Checkkind,
+    pub tenant_id: Uuid,
+    pub priority: Priority,
+    pub status: CheckStatus,
+    pub status_updated_at: Option<NaiveDateTime>,
+    pub global_risk_queued_at: Option<NaiveDateTime>,
+    pub exposure_requested_at: Option<NaiveDateTime>,
+}
+
+// Whether a check's jobs are being worked on. Jobs of a paused check are parked until it's
+// resumed, and jobs of a cancelled check are skipped
+#[derive(Debug, Clone, Copy, PartialEq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = crate::schema::sql_types::Checkstatus)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Running => "running",
+            CheckStatus::Paused => "paused",
+            CheckStatus::Cancelled => "cancelled",
+        }
+    }
+
+    // Running and paused checks can be paused, resumed or cancelled, a cancelled check stays so
+    pub fn can_become(&self, status: CheckStatus) -> bool {
+        matches!(
+            (self, status),
+            (CheckStatus::Running, CheckStatus::Paused)
+                | (CheckStatus::Paused, CheckStatus::Running)
+                | (
+                    CheckStatus::Running | CheckStatus::Paused,
+                    CheckStatus::Cancelled
+                )
+        )
+    }
+}
+
+impl ToSql<crate::schema::sql_types::Checkstatus, Pg> for CheckStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::Checkstatus, Pg> for CheckStatus {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"running" => Ok(CheckStatus::Running),
+            b"paused" => Ok(CheckStatus::Paused),
+            b"cancelled" => Ok(CheckStatus::Cancelled),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+// A job of a paused check, as it was sent, and the topic to send it to again on resuming
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::parked_job)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ParkedJob {
+    pub job_id: Uuid,
+    pub check_id: Uuid,
+    pub topic: String,
+    pub job: serde_json::Value,
+    pub parked_at: NaiveDateTime,
+}
+
+// The class of work a job is, each queued on its own topics. Workers take jobs from each in
+// turn, as often as their weight, and producers split their rate limit by weight too
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(sql_type = crate::schema::sql_types::Priority)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    // checks started by an analyst
+    #[default]
+    Interactive,
+    Batch,
+    // monitoring re-screens
+    Background,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Interactive, Priority::Batch, Priority::Background];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Batch => "batch",
+            Priority::Background => "background",
+        }
+    }
+
+    pub fn weight(&self) -> u32 {
+        match self {
+            Priority::Interactive => 6,
+            Priority::Batch => 3,
+            Priority::Background => 1,
+        }
+    }
+
+    // The topic jobs of this priority are queued on, for a base topic
+    pub fn topic(&self, topic: &str) -> String {
+        format!("{}-{}", topic, self.as_str())
+    }
+}
+
+impl ToSql<crate::schema::sql_types::Priority, Pg> for Priority {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::Priority, Pg> for Priority {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"interactive" => Ok(Priority::Interactive),
+            b"batch" => Ok(Priority::Batch),
+            b"background" => Ok(Priority::Background),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow, Serialize, Deserialize, PartialEq)]
+#[diesel(sql_type = crate::schema::sql_types::Casestatus)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    Open,
+    InReview,
+    Escalated,
+    Closed,
+}
+
+impl ToSql<crate::schema::sql_types::Casestatus, Pg> for CaseStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            CaseStatus::Open => out.write_all(b"open")?,
+            CaseStatus::InReview => out.write_all(b"in_review")?,
+            CaseStatus::Escalated => out.write_all(b"escalated")?,
+            CaseStatus::Closed => out.write_all(b"closed")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::Casestatus, Pg> for CaseStatus {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"open" => Ok(CaseStatus::Open),
+            b"in_review" => Ok(CaseStatus::InReview),
+            b"escalated" => Ok(CaseStatus::Escalated),
+            b"closed" => Ok(CaseStatus::Closed),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+// Opened along with its check
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::check_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CheckCase {
+    pub check_id: Uuid,
+    pub status: CaseStatus,
+    pub assignee_id: Option<Uuid>,
+    pub opened_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow, Serialize, Deserialize, PartialEq)]
+#[diesel(sql_type = crate::schema::sql_types::Verdict)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    FalsePositive,
+    TrueMatch,
+}
+
+impl ToSql<crate::schema::sql_types::Verdict, Pg> for Verdict {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        match *self {
+            Verdict::FalsePositive => out.write_all(b"false_positive")?,
+            Verdict::TrueMatch => out.write_all(b"true_match")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<crate::schema::sql_types::Verdict, Pg> for Verdict {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"false_positive" => Ok(Verdict::FalsePositive),
+            b"true_match" => Ok(Verdict::TrueMatch),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::batch)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Batch {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub submitted_at: NaiveDateTime,
+}
+
+// Either company_number, or name and postcode, are set
+#[derive(Queryable, Selectable, Insertable, Clone, Debug, PartialEq)]
+#[diesel(table_name = crate::schema::batch_row)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BatchRow {
+    pub batch_id: Uuid,
+    // 1 for the first row after the header
+    pub row_number: i32,
+    pub company_number: Option<String>,
+    pub name: Option<String>,
+    pub postcode: Option<String>,
+    pub traversal_policy: serde_json::Value,
+    pub check_id: Option<Uuid>,
+    // why the row couldn't be started as a check
+    pub error: Option<String>,
+    pub started_at: Option<NaiveDateTime>,
+}
+
+// The open sanctions entity an individual was matched to, which its flags were raised from
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::sanctions_match)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SanctionsMatch {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub open_sanctions_id: String,
+    pub caption: String,
+    pub source_fetch_id: Option<Uuid>,
+}
+
+// A verdict on either a flag or a sanctions match. Earlier dispositions are kept, the
+// latest is the one that applies
+#[derive(Queryable, Selectable, Insertable, Serialize)]
+#[diesel(table_name = crate::schema::disposition)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Disposition {
+    pub id: Uuid,
+    pub check_id: Uuid,
+    pub flag_id: Option<Uuid>,
+    pub sanctions_match_id: Option<Uuid>,
+    pub verdict: Verdict,
+    pub comment: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -427,7 +1333,19 @@ pub struct CheckJobMap {
     pub job_id: Uuid,
 }
 
-#[derive(Debug, AsExpression, FromSqlRow, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(
+    Debug,
+    AsExpression,
+    FromSqlRow,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Copy,
+)]
 #[diesel(sql_type = crate::schema::sql_types::Flagkind)]
 pub enum Flagkind {
     #[default]
@@ -494,6 +1412,25 @@ pub enum Flagkind {
     PersonOfInterest,
 }
 
+// Flags which mark an entity as a risk in itself, rather than describing who it is
+pub const ADVERSE_FLAGS: [Flagkind; 15] = [
+    Flagkind::Crime,
+    Flagkind::Fraud,
+    Flagkind::Cybercrime,
+    Flagkind::FinancialCrime,
+    Flagkind::Terrorism,
+    Flagkind::Trafficking,
+    Flagkind::DrugTrafficking,
+    Flagkind::HumanTrafficking,
+    Flagkind::WarCrimes,
+    Flagkind::Wanted,
+    Flagkind::Disqualified,
+    Flagkind::FrozenAsset,
+    Flagkind::SanctionedEntity,
+    Flagkind::SanctionLinkedEntity,
+    Flagkind::DEbarredEntity,
+];
+
 impl ToSql<crate::schema::sql_types::Flagkind, Pg> for Flagkind {
     fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
         match *self {
@@ -729,6 +1666,9 @@ impl From<FlagStringList> for Vec<Flagkind> {
 pub struct Flag {
     pub id: Uuid,
     pub kind: Flagkind,
+    pub source_fetch_id: Option<Uuid>,
+    // the open sanctions match the flag was raised from, if any
+    pub sanctions_match_id: Option<Uuid>,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -787,7 +1727,310 @@ pub struct DormantCompany {
     pub dormant: bool,
 }
 
-#[derive(Debug, AsExpression, FromSqlRow, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::company_profile)]
+#[diesel(primary_key(entity_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CompanyProfile {
+    pub entity_id: Uuid,
+    pub company_status: Option<String>,
+    pub company_type: Option<String>,
+    pub sic_codes: Vec<String>,
+    pub date_of_creation: Option<NaiveDate>,
+    pub date_of_cessation: Option<NaiveDate>,
+    pub jurisdiction: Option<String>,
+    pub premises: Option<String>,
+    pub address_line_1: Option<String>,
+    pub address_line_2: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    pub has_charges: Option<bool>,
+    pub has_insolvency_history: Option<bool>,
+    pub has_been_liquidated: Option<bool>,
+    pub fetched_at: NaiveDateTime,
+}
+
+impl From<(Uuid, &CompanyData)> for CompanyProfile {
+    fn from(value: (Uuid, &CompanyData)) -> Self {
+        let (entity_id, company_data) = value;
+        let address = company_data.registered_office_address.clone();
+
+        Self {
+            entity_id,
+            company_status: company_data.company_status.clone(),
+            company_type: company_data.r#type.clone(),
+            sic_codes: company_data.sic_codes.clone().unwrap_or_default(),
+            date_of_creation: parse_date(&company_data.date_of_creation),
+            date_of_cessation: parse_date(&company_data.date_of_cessation),
+            jurisdiction: company_data.jurisdiction.clone(),
+            premises: address.as_ref().and_then(|a| a.premises.clone()),
+            address_line_1: address.as_ref().and_then(|a| a.address_line_1.clone()),
+            address_line_2: address.as_ref().and_then(|a| a.address_line_2.clone()),
+            locality: address.as_ref().and_then(|a| a.locality.clone()),
+            region: address.as_ref().and_then(|a| a.region.clone()),
+            postal_code: address.as_ref().and_then(|a| a.postal_code.clone()),
+            country: address.as_ref().and_then(|a| a.country.clone()),
+            has_charges: company_data.has_charges,
+            has_insolvency_history: company_data.has_insolvency_history,
+            has_been_liquidated: company_data.has_been_liquidated,
+            fetched_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::previous_name)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PreviousName {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub name: String,
+    pub effective_from: Option<NaiveDate>,
+    pub ceased_on: Option<NaiveDate>,
+}
+
+impl PreviousName {
+    pub fn from_company_data(entity_id: Uuid, company_data: &CompanyData) -> Vec<Self> {
+        company_data
+            .previous_company_names
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|previous_name| {
+                Some(PreviousName {
+                    id: Uuid::new_v4(),
+                    entity_id,
+                    name: previous_name.name?,
+                    effective_from: parse_date(&previous_name.effective_from),
+                    ceased_on: parse_date(&previous_name.ceased_on),
+                })
+            })
+            .collect()
+    }
+}
+
+// Registry dates are ISO 8601 strings, i.e. 2024-12-21
+fn parse_date(date: &Option<String>) -> Option<NaiveDate> {
+    date.as_ref().and_then(|date| date.parse().ok())
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::charge)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Charge {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub charge_code: Option<String>,
+    pub classification: Option<String>,
+    pub status: Option<String>,
+    pub created_on: Option<NaiveDate>,
+    pub delivered_on: Option<NaiveDate>,
+    pub satisfied_on: Option<NaiveDate>,
+}
+
+impl Charge {
+    pub fn from_charge_list(entity_id: Uuid, charges: &ChargeList) -> Vec<Self> {
+        charges
+            .items
+            .iter()
+            .flatten()
+            .map(|charge| Charge {
+                id: Uuid::new_v4(),
+                entity_id,
+                charge_code: charge.charge_code.clone(),
+                classification: charge
+                    .classification
+                    .as_ref()
+                    .and_then(|classification| classification.description.clone()),
+                status: charge.status.clone(),
+                created_on: charge.created_on,
+                delivered_on: charge.delivered_on,
+                satisfied_on: charge.satisfied_on,
+            })
+            .collect()
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::insolvency)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Insolvency {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub case_number: Option<String>,
+    pub case_type: Option<String>,
+    pub notes: Vec<String>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::insolvency_date)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsolvencyDate {
+    pub insolvency_id: Uuid,
+    // i.e. wound-up-on, administration-started-on
+    pub kind: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::insolvency_practitioner)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsolvencyPractitioner {
+    pub id: Uuid,
+    pub insolvency_id: Uuid,
+    pub name: String,
+    pub role: Option<String>,
+    pub appointed_on: Option<NaiveDate>,
+    pub ceased_to_act_on: Option<NaiveDate>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+pub struct InsolvencyCaseRecord {
+    pub insolvency: Insolvency,
+    pub dates: Vec<InsolvencyDate>,
+    pub practitioners: Vec<InsolvencyPractitioner>,
+}
+
+impl InsolvencyCaseRecord {
+    pub fn from_company_insolvency(entity_id: Uuid, insolvency: &CompanyInsolvency) -> Vec<Self> {
+        insolvency
+            .cases
+            .iter()
+            .flatten()
+            .map(|case| {
+                let insolvency_id = Uuid::new_v4();
+
+                Self {
+                    insolvency: Insolvency {
+                        id: insolvency_id,
+                        entity_id,
+                        case_number: case.number.clone(),
+                        case_type: case.r#type.clone(),
+                        notes: case.notes.clone().unwrap_or_default(),
+                    },
+                    dates: case
+                        .dates
+                        .iter()
+                        .flatten()
+                        .filter_map(|case_date| {
+                            Some(InsolvencyDate {
+                                insolvency_id,
+                                kind: case_date.r#type.clone()?,
+                                date: case_date.date?,
+                            })
+                        })
+                        .collect(),
+                    practitioners: case
+                        .practitioners
+                        .iter()
+                        .flatten()
+                        .filter_map(|practitioner| {
+                            let (postal_code, country) = match &practitioner.address {
+                                Some(address) => {
+                                    (address.postal_code.clone(), address.country.clone())
+                                }
+                                None => (None, None),
+                            };
+
+                            Some(InsolvencyPractitioner {
+                                id: Uuid::new_v4(),
+                                insolvency_id,
+                                name: practitioner.name.clone()?,
+                                role: practitioner.role.clone(),
+                                appointed_on: practitioner.appointed_on,
+                                ceased_to_act_on: practitioner.ceased_to_act_on,
+                                postal_code,
+                                country,
+                            })
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::disqualification)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Disqualification {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub case_identifier: Option<String>,
+    // either a court order or an undertaking
+    pub disqualification_type: Option<String>,
+    pub disqualified_from: Option<NaiveDate>,
+    pub disqualified_until: Option<NaiveDate>,
+    pub heard_on: Option<NaiveDate>,
+    pub undertaken_on: Option<NaiveDate>,
+    pub reason: Option<String>,
+    pub act: Option<String>,
+    pub section: Option<String>,
+    pub court_name: Option<String>,
+    pub company_names: Vec<String>,
+}
+
+impl Disqualification {
+    // Disqualifications without an end date are taken to be indefinite
+    pub fn is_current_at(&self, date: NaiveDate) -> bool {
+        self.disqualified_until.is_none_or(|until| until >= date)
+    }
+
+    pub fn from_disqualified_officer(
+        entity_id: Uuid,
+        disqualified_officer: &DisqualifiedOfficer,
+    ) -> Vec<Self> {
+        disqualified_officer
+            .disqualifications
+            .iter()
+            .flatten()
+            .map(|disqualification| {
+                let reason = disqualification.reason.clone();
+
+                Disqualification {
+                    id: Uuid::new_v4(),
+                    entity_id,
+                    case_identifier: disqualification.case_identifier.clone(),
+                    disqualification_type: disqualification.disqualification_type.clone(),
+                    disqualified_from: disqualification.disqualified_from,
+                    disqualified_until: disqualification.disqualified_until,
+                    heard_on: disqualification.heard_on,
+                    undertaken_on: disqualification.undertaken_on,
+                    reason: reason
+                        .as_ref()
+                        .and_then(|reason| reason.description_identifier.clone()),
+                    act: reason.as_ref().and_then(|reason| reason.act.clone()),
+                    section: reason.as_ref().and_then(|reason| reason.section.clone()),
+                    court_name: disqualification.court_name.clone(),
+                    company_names: disqualification.company_names.clone().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::recent_insolvency)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RecentInsolvency {
+    pub entity_id: Uuid,
+    pub recent: bool,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::unusual_lender)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UnusualLender {
+    pub entity_id: Uuid,
+    pub unusual: bool,
+}
+
+#[derive(
+    Debug, Clone, Copy, AsExpression, FromSqlRow, Default, Serialize, Deserialize, PartialEq,
+)]
 #[diesel(sql_type = crate::schema::sql_types::Checkkind)]
 pub enum Checkkind {
     #[default]
@@ -830,6 +2073,7 @@ pub struct MonitoredEntity {
     pub id: Uuid,
     pub company_house_id: String,
     pub monitoring_span_id: Uuid,
+    pub tenant_id: Uuid,
 }
 
 #[derive(Queryable, Selectable, Insertable)]
@@ -917,3 +2161,132 @@ impl From<&StreamingKind> for Updatekind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_date_ages_use_youngest_and_oldest_possible_ages() {
+        let date_of_birth = PartialDate::new(Some(1970), Some(6), None).unwrap();
+
+        assert_eq!(
+            date_of_birth.age_at(NaiveDate::from_ymd_opt(2020, 6, 15).unwrap()),
+            Some(49)
+        );
+        assert_eq!(
+            date_of_birth.age_at(NaiveDate::from_ymd_opt(2020, 7, 1).unwrap()),
+            Some(50)
+        );
+        assert_eq!(
+            date_of_birth.oldest_age_at(NaiveDate::from_ymd_opt(2020, 6, 15).unwrap()),
+            Some(50)
+        );
+        assert_eq!(
+            date_of_birth.oldest_age_at(NaiveDate::from_ymd_opt(2020, 5, 31).unwrap()),
+            Some(49)
+        );
+    }
+
+    #[test]
+    fn ownership_band_is_highest_share_band() {
+        let nature_of_control = vec![
+            "voting-rights-75-to-100-percent".to_string(),
+            "ownership-of-shares-25-to-50-percent-as-trust".to_string(),
+            "ownership-of-shares-50-to-75-percent".to_string(),
+        ];
+
+        assert_eq!(
+            OwnershipBand::from_nature_of_control(&nature_of_control),
+            Some(OwnershipBand::FiftyToSeventyFive)
+        );
+        assert_eq!(
+            OwnershipBand::from_nature_of_control(&nature_of_control[..1]),
+            None
+        );
+    }
+
+    #[test]
+    fn individuals_resolve_on_name_and_birth_month() {
+        let mut entity = Entity {
+            kind: Entitykind::Individual,
+            name: Some("SMITH, Jane".to_string()),
+            ..Default::default()
+        };
+        entity.set_date_of_origin(PartialDate::new(Some(1970), Some(6), Some(12)));
+
+        assert_eq!(
+            entity.canonical_keys(),
+            vec![(NAME_AND_BIRTH_MONTH_KEY, "JANE SMITH|1970-06".to_string())]
+        );
+    }
+
+    fn shareholder(kind: &str, registration_number: Option<&str>) -> ShareholderListItem {
+        serde_json::from_value(serde_json::json!({
+            "kind": kind,
+            "name": "Jane Smith",
+            "date_of_birth": { "month": 6, "year": 1970 },
+            "identification": { "registration_number": registration_number },
+            "nature_of_control": ["ownership-of-shares-75-to-100-percent"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn individual_shareholders_need_no_registration_number() {
+        let relation = EntityRelation::try_from((
+            shareholder("individual-person-with-significant-control", None),
+            false,
+        ))
+        .unwrap();
+
+        assert_eq!(relation.entity.kind, Entitykind::Individual);
+        assert_eq!(relation.entity.company_house_number, "");
+        assert_eq!(
+            relation.entity.canonical_keys(),
+            vec![(NAME_AND_BIRTH_MONTH_KEY, "JANE SMITH|1970-06".to_string())]
+        );
+        assert_eq!(
+            relation.ownership_band,
+            Some(OwnershipBand::SeventyFiveToHundred)
+        );
+
+        let corporate = "corporate-entity-person-with-significant-control";
+        assert!(EntityRelation::try_from((shareholder(corporate, None), false)).is_err());
+        assert_eq!(
+            EntityRelation::try_from((shareholder(corporate, Some("01234567")), false))
+                .unwrap()
+                .entity
+                .company_house_number,
+            "01234567"
+        );
+    }
+
+    #[test]
+    fn partial_date_matches_known_components() {
+        let date_of_birth = PartialDate::new(Some(1970), Some(6), Some(0)).unwrap();
+
+        assert_eq!(date_of_birth.day, None);
+        assert!(date_of_birth.matches_str("1970"));
+        assert!(date_of_birth.matches_str("1970-06-12"));
+        assert!(!date_of_birth.matches_str("1970-07"));
+        assert_eq!(
+            PartialDate::to_columns(Some(date_of_birth)),
+            (NaiveDate::from_ymd_opt(1970, 6, 1), false)
+        );
+    }
+
+    #[test]
+    fn cancelled_checks_stay_cancelled() {
+        use CheckStatus::*;
+
+        assert!(Running.can_become(Paused));
+        assert!(Paused.can_become(Running));
+        assert!(Running.can_become(Cancelled));
+        assert!(Paused.can_become(Cancelled));
+        assert!(!Running.can_become(Running));
+        assert!(!Paused.can_become(Paused));
+        assert!(!Cancelled.can_become(Running));
+        assert!(!Cancelled.can_become(Paused));
+    }
+}
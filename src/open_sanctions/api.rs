@@ -2,6 +2,8 @@ use std::{collections::HashMap, env};
 
 use reqwest::Client;
 
+use crate::registry::fetched::{DataSource, Fetched};
+
 use super::types::FlagSearchResponse;
 
 const FLAG_SEARCH_URL: &str = "https://api.opensanctions.org/search/default";
@@ -20,13 +22,13 @@ impl OpenSanctionsClient {
     pub async fn get_flags(
         &self,
         individual_name: String,
-    ) -> Result<FlagSearchResponse, failure::Error> {
+    ) -> Result<Fetched<FlagSearchResponse>, failure::Error> {
         let mut params = HashMap::new();
         params.insert(
             "api_key",
             env::var("OPEN_SANCTIONS_API_KEY").expect("API KEY should be set"),
         );
-        params.insert("q", individual_name);
+        params.insert("q", individual_name.clone());
 
         let response = self
             .client
@@ -34,9 +36,12 @@ impl OpenSanctionsClient {
             .query(&params)
             .send()
             .await?;
-        let search_result: FlagSearchResponse = response.json().await?;
 
-        Ok(search_result)
+        Fetched::parse(
+            DataSource::OpenSanctions,
+            format!("/search/default?q={}", individual_name),
+            &response.bytes().await?,
+        )
     }
 }
 #[cfg(test)]
@@ -50,7 +55,7 @@ mod tests {
         let client = OpenSanctionsClient::new();
         let result = client.get_flags("boris johnson".to_string()).await.unwrap();
 
-        if let Some(entity) = result.results.first() {
+        if let Some(entity) = result.response.results.first() {
             for (key, value) in entity.properties.to_owned().into_iter() {
                 println!("{:?} : {:?}", key, value)
             }
@@ -1,41 +1,216 @@
-use chrono::{NaiveDate, NaiveDateTime, Utc};
-use diesel::{insert_into, Connection, PgConnection};
-use diesel::{prelude::*, update};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use diesel::query_dsl::methods::ExecuteDsl;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel::{insert_into, upsert::excluded, Connection, PgConnection};
+use diesel::{pg::Pg, prelude::*, query_builder::QueryFragment, update};
+use failure::{format_err, Fail};
 use uuid::Uuid;
 
+use crate::check_query::{
+    CheckSort, ChecksCursor, ChecksQuery, SortDirection, STARTED_AT_CURSOR_FORMAT,
+};
+
 use crate::models::{
-    Check, CheckEntityMap, CheckJobMap, CheckMonitoredEntity, CheckSnapshot, Checkkind, Dataset,
-    Datasets, DormantCompany, Entity, Flag, Flagkind, Flags, Job, MonitoredEntity, MonitoringSpan,
-    OutlierAge, Position, Positions, ProcessedUpdate, Relationship, Relationshipkind, Snapshot,
-    Updatekind,
+    ApiKey, AuditLog, Batch, BatchRow, BeneficialOwner, BeneficialOwnerPath, CanonicalEntity,
+    CanonicalEntityKey, CaseStatus, Charge, Check, CheckCase, CheckEntityMap, CheckFrontier,
+    CheckJobMap, CheckMonitoredEntity, CheckPropagationPolicy, CheckReport, CheckSnapshot,
+    CheckStatus, CheckSummary, CheckTraversalPolicy, Checkkind, CompanyProfile, Dataset, Datasets,
+    Disposition, Disqualification, DormantCompany, Entity, EntityDetails, Exposure, Flag, Flagkind,
+    Flags, Insolvency, InsolvencyCaseRecord, Job, MonitoredEntity, MonitoringSpan, OutlierAge,
+    OwnershipBand, ParkedJob, Position, Positions, PreviousName, Priority, ProcessedUpdate,
+    RawPayload, RecentInsolvency, Relationship, Relationshipkind, SanctionsMatch, Snapshot,
+    SourceFetch, Tenant, TenantUser, UnusualLender, Updatekind, Verdict, ADVERSE_FLAGS,
 };
+use crate::registry::fetched::Fetched;
+use crate::risk::RiskBreakdown;
 use crate::schema::{
-    check, check_entity_map, check_job_map, check_monitored_entity, check_snapshot, dataset,
-    datasets, dormant_company, entity, flag, flags, job, monitored_entity, monitoring_span,
-    outlier_age, position, positions, processed_update, relationship, snapshot,
+    api_key, audit_log, batch, batch_row, beneficial_owner, beneficial_owner_path,
+    canonical_entity, canonical_entity_key, charge, check, check_case, check_entity_map,
+    check_frontier, check_job_map, check_monitored_entity, check_propagation_policy, check_report,
+    check_snapshot, check_summary, check_traversal_policy, company_profile, dataset, datasets,
+    disposition, disqualification, dormant_company, entity, exposure, flag, flags, insolvency,
+    insolvency_date, insolvency_practitioner, job, monitored_entity, monitoring_span, outlier_age,
+    parked_job, position, positions, previous_name, processed_update, raw_payload,
+    recent_insolvency, relationship, sanctions_match, snapshot, source_fetch, tenant, tenant_user,
+    unusual_lender,
 };
 
+const DEFAULT_POOL_SIZE: u32 = 10;
+
+// How often a running check's summary is refreshed as its jobs complete
+const CHECK_SUMMARY_REFRESH_SECS: i64 = 30;
+// How often an API key's last use is recorded, rather than on every request
+const API_KEY_LAST_USED_SECS: i64 = 60;
+
+// Owns everything created before tenants were introduced
+pub const DEFAULT_TENANT_ID: Uuid = Uuid::from_u128(1);
+
+define_sql_function! {
+    fn coalesce(
+        x: diesel::sql_types::Nullable<diesel::sql_types::Text>,
+        y: diesel::sql_types::Text,
+    ) -> diesel::sql_types::Text;
+}
+
+pub type DatabasePool = Pool<ConnectionManager<PgConnection>>;
+
+// Returned when a record looked up by id doesn't exist, so callers can tell it apart from
+// the database failing
+#[derive(Debug)]
+pub struct NotFound {
+    pub kind: &'static str,
+    pub id: Uuid,
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not found with id: {}", self.kind, self.id)
+    }
+}
+
+impl Fail for NotFound {}
+
+// Returned when a change can't be made to a record in the state it's in
+#[derive(Debug)]
+pub struct Conflict {
+    pub message: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Fail for Conflict {}
+
+// Workers hold a single connection for their lifetime, the web server borrows one from
+// its pool for each request
+enum DatabaseConnection {
+    Owned(PgConnection),
+    Pooled(PooledConnection<ConnectionManager<PgConnection>>),
+}
+
+impl Deref for DatabaseConnection {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DatabaseConnection::Owned(conn) => conn,
+            DatabaseConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for DatabaseConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            DatabaseConnection::Owned(conn) => conn,
+            DatabaseConnection::Pooled(conn) => conn,
+        }
+    }
+}
+
+// Scoped to a tenant, queries only see that tenant's checks and what they found. Workers
+// aren't scoped, they process jobs for every tenant
 pub struct Database {
-    conn: PgConnection,
+    conn: DatabaseConnection,
+    tenant_id: Option<Uuid>,
 }
 
 impl Database {
     pub fn connect() -> Result<Self, failure::Error> {
         let database_url = std::env::var("DATABASE_URL")?;
         let conn = PgConnection::establish(&database_url)?;
-        Ok(Self { conn })
+        Ok(Self {
+            conn: DatabaseConnection::Owned(conn),
+            tenant_id: None,
+        })
+    }
+
+    // Sized by DATABASE_POOL_SIZE, defaulting to 10 connections
+    pub fn create_pool() -> Result<DatabasePool, failure::Error> {
+        let database_url = std::env::var("DATABASE_URL")?;
+        let pool_size = match std::env::var("DATABASE_POOL_SIZE") {
+            Ok(pool_size) => pool_size.parse()?,
+            Err(_) => DEFAULT_POOL_SIZE,
+        };
+
+        Ok(Pool::builder()
+            .max_size(pool_size)
+            .build(ConnectionManager::<PgConnection>::new(database_url))?)
+    }
+
+    // Blocks until a connection is free, so should be called off the async executor
+    pub fn from_pool(pool: &DatabasePool) -> Result<Self, failure::Error> {
+        Ok(Self {
+            conn: DatabaseConnection::Pooled(pool.get()?),
+            tenant_id: None,
+        })
+    }
+
+    pub fn for_tenant(self, tenant_id: Uuid) -> Self {
+        Self {
+            tenant_id: Some(tenant_id),
+            ..self
+        }
+    }
+
+    pub fn tenant_id(&self) -> Option<Uuid> {
+        self.tenant_id
+    }
+
+    // Runs f scoped to the tenant, for workers which act for every tenant over one connection
+    pub fn with_tenant<T>(&mut self, tenant_id: Uuid, f: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = self.tenant_id.replace(tenant_id);
+        let result = f(self);
+        self.tenant_id = previous;
+        result
     }
 
-    pub fn insert_check(&mut self, kind: Checkkind) -> Result<Uuid, failure::Error> {
+    pub fn insert_check(
+        &mut self,
+        kind: Checkkind,
+        priority: Priority,
+    ) -> Result<Uuid, failure::Error> {
+        let tenant_id = self
+            .tenant_id
+            .ok_or_else(|| format_err!("Checks can only be started for a tenant"))?;
         let id = Uuid::new_v4();
+        let started_at = Utc::now().naive_utc();
 
-        insert_into(check::table)
-            .values(&Check {
-                id,
-                started_at: Utc::now().naive_utc(),
-                kind,
-            })
-            .execute(&mut self.conn)?;
+        // every check is opened as a case for analysts to work through
+        self.conn.transaction(|conn| {
+            insert_into(check::table)
+                .values(&Check {
+                    id,
+                    started_at,
+                    kind,
+                    tenant_id,
+                    priority,
+                    status: CheckStatus::Running,
+                    status_updated_at: None,
+                    global_risk_queued_at: None,
+                    exposure_requested_at: None,
+                })
+                .execute(conn)?;
+
+            insert_into(check_case::table)
+                .values(&CheckCase {
+                    check_id: id,
+                    status: CaseStatus::Open,
+                    assignee_id: None,
+                    opened_at: started_at,
+                    updated_at: started_at,
+                })
+                .execute(conn)?;
+
+            diesel::result::QueryResult::Ok(())
+        })?;
 
         Ok(id)
     }
@@ -45,34 +220,476 @@ impl Database {
         entity: &Entity,
         check_id: Uuid,
     ) -> Result<Uuid, failure::Error> {
-        match self.get_existing_entity_id(&check_id, &entity.company_house_number)? {
-            Some(id) => return Ok(id),
-            None => {}
+        Ok(self.insert_or_get_entity(entity, check_id, None)?.0)
+    }
+
+    // Returns the id of the entity within the check, and whether it was newly inserted
+    // rather than already part of the check. Entities keep the provenance of the response
+    // they were first found in
+    pub fn insert_or_get_entity(
+        &mut self,
+        entity: &Entity,
+        check_id: Uuid,
+        source_fetch_id: Option<Uuid>,
+    ) -> Result<(Uuid, bool), failure::Error> {
+        // entities without a registry number, such as individual shareholders, are only
+        // found again in the check through their canonical entity
+        if !entity.company_house_number.is_empty() {
+            match self.get_existing_entity_id(&check_id, &entity.company_house_number)? {
+                Some(id) => return Ok((id, false)),
+                None => {}
+            }
         }
 
-        self.conn.transaction(|conn| {
-            insert_into(entity::table).values(entity).execute(conn)?;
+        let entity_id = self.conn.transaction(|conn| {
+            let canonical_entity_id = resolve_canonical_entity(conn, entity)?;
+
+            insert_into(entity::table)
+                .values((
+                    entity,
+                    entity::canonical_entity_id.eq(canonical_entity_id),
+                    entity::source_fetch_id.eq(source_fetch_id),
+                ))
+                .execute(conn)?;
 
-            insert_into(check_entity_map::table)
+            // the same entity can be reached by routes which identify it differently, e.g. as
+            // an officer with a person number and as a shareholder by name, or by two workers
+            // at once. Whichever maps it into the check first is kept
+            let is_inserted = insert_into(check_entity_map::table)
                 .values(&CheckEntityMap {
                     check_id,
                     entity_id: entity.id,
+                    canonical_entity_id: Some(canonical_entity_id),
+                })
+                .on_conflict((
+                    check_entity_map::check_id,
+                    check_entity_map::canonical_entity_id,
+                ))
+                .do_nothing()
+                .execute(conn)?
+                > 0;
+            if is_inserted {
+                return diesel::result::QueryResult::Ok((entity.id, true));
+            }
+
+            diesel::delete(entity::table.filter(entity::id.eq(entity.id))).execute(conn)?;
+            let existing_entity_id = check_entity_map::table
+                .filter(check_entity_map::check_id.eq(check_id))
+                .filter(check_entity_map::canonical_entity_id.eq(canonical_entity_id))
+                .select(check_entity_map::entity_id)
+                .first::<Uuid>(conn)?;
+
+            diesel::result::QueryResult::Ok((existing_entity_id, false))
+        })?;
+
+        Ok(entity_id)
+    }
+
+    pub fn insert_traversal_policy(
+        &mut self,
+        check_id: Uuid,
+        policy: serde_json::Value,
+    ) -> Result<(), failure::Error> {
+        insert_into(check_traversal_policy::table)
+            .values(&CheckTraversalPolicy { check_id, policy })
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_traversal_policy(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Option<serde_json::Value>, failure::Error> {
+        Ok(check_traversal_policy::table
+            .filter(check_traversal_policy::check_id.eq(check_id))
+            .select(check_traversal_policy::policy)
+            .first::<serde_json::Value>(&mut *self.conn)
+            .optional()?)
+    }
+
+    // Replaces any policy the check's exposure was previously propagated with
+    pub fn upsert_propagation_policy(
+        &mut self,
+        check_id: Uuid,
+        policy: serde_json::Value,
+    ) -> Result<(), failure::Error> {
+        insert_into(check_propagation_policy::table)
+            .values(&CheckPropagationPolicy { check_id, policy })
+            .on_conflict(check_propagation_policy::check_id)
+            .do_update()
+            .set(check_propagation_policy::policy.eq(excluded(check_propagation_policy::policy)))
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_propagation_policy(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Option<serde_json::Value>, failure::Error> {
+        Ok(check_propagation_policy::table
+            .filter(check_propagation_policy::check_id.eq(check_id))
+            .select(check_propagation_policy::policy)
+            .first::<serde_json::Value>(&mut *self.conn)
+            .optional()?)
+    }
+
+    pub fn insert_check_report(
+        &mut self,
+        check_report: &CheckReport,
+    ) -> Result<(), failure::Error> {
+        insert_into(check_report::table)
+            .values(check_report)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_check_report(&mut self, report_id: &Uuid) -> Result<CheckReport, failure::Error> {
+        let mut query = check_report::table
+            .filter(check_report::id.eq(report_id))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check_report::check_id.eq_any(tenant_check_ids(tenant_id)));
+        }
+
+        Ok(query.first::<CheckReport>(&mut *self.conn)?)
+    }
+
+    // Every report generated for the check without its content, as (id, format, content
+    // hash, generated at), newest first
+    pub fn get_check_reports(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<(Uuid, String, String, NaiveDateTime)>, failure::Error> {
+        let mut query = check_report::table
+            .filter(check_report::check_id.eq(check_id))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check_report::check_id.eq_any(tenant_check_ids(tenant_id)));
+        }
+
+        Ok(query
+            .order(check_report::generated_at.desc())
+            .select((
+                check_report::id,
+                check_report::format,
+                check_report::content_hash,
+                check_report::generated_at,
+            ))
+            .load::<(Uuid, String, String, NaiveDateTime)>(&mut *self.conn)?)
+    }
+
+    // Every period the company has been monitored for, as (started at, ended at, last
+    // update received at)
+    pub fn get_monitoring_history(
+        &mut self,
+        company_house_id: &String,
+    ) -> Result<Vec<(NaiveDateTime, Option<NaiveDateTime>, Option<NaiveDateTime>)>, failure::Error>
+    {
+        let mut query = monitored_entity::table
+            .inner_join(
+                monitoring_span::table
+                    .on(monitoring_span::id.eq(monitored_entity::monitoring_span_id)),
+            )
+            .filter(monitored_entity::company_house_id.eq(company_house_id))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(monitored_entity::tenant_id.eq(tenant_id));
+        }
+        let monitored_entities = query
+            .order(monitoring_span::started_at.asc())
+            .select((
+                monitored_entity::id,
+                monitoring_span::started_at,
+                monitoring_span::ended_at,
+            ))
+            .load::<(Uuid, NaiveDateTime, Option<NaiveDateTime>)>(&mut *self.conn)?;
+
+        let mut history = Vec::new();
+        for (monitored_entity_id, started_at, ended_at) in monitored_entities {
+            history.push((
+                started_at,
+                ended_at,
+                self.get_last_update(monitored_entity_id)?,
+            ));
+        }
+
+        Ok(history)
+    }
+
+    // Adds a relation job to the check's frontier, returns false if it shouldn't be
+    // enqueued, see FrontierAction
+    pub fn claim_relation_job(
+        &mut self,
+        check_id: Uuid,
+        entity_id: Uuid,
+        relation_job_kind: &str,
+        remaining_depth: i32,
+    ) -> Result<bool, failure::Error> {
+        let is_claimed = self.conn.transaction(|conn| {
+            let existing = check_frontier::table
+                .filter(check_frontier::check_id.eq(check_id))
+                .filter(check_frontier::entity_id.eq(entity_id))
+                .filter(check_frontier::relation_job_kind.eq(relation_job_kind))
+                .for_update()
+                .first::<CheckFrontier>(conn)
+                .optional()?;
+            let frontier_row = check_frontier::table
+                .filter(check_frontier::check_id.eq(check_id))
+                .filter(check_frontier::entity_id.eq(entity_id))
+                .filter(check_frontier::relation_job_kind.eq(relation_job_kind));
+
+            match (
+                frontier_action(existing.as_ref(), remaining_depth),
+                existing,
+            ) {
+                (FrontierAction::Skip, _) => diesel::result::QueryResult::Ok(false),
+                (FrontierAction::RaiseDepth, _) => {
+                    update(frontier_row)
+                        .set(check_frontier::remaining_depth.eq(remaining_depth))
+                        .execute(conn)?;
+
+                    diesel::result::QueryResult::Ok(false)
+                }
+                // back on the frontier, to be fetched again with more depth left
+                (FrontierAction::Queue, Some(_)) => {
+                    update(frontier_row)
+                        .set((
+                            check_frontier::remaining_depth.eq(remaining_depth),
+                            check_frontier::fetched_at.eq(None::<NaiveDateTime>),
+                        ))
+                        .execute(conn)?;
+
+                    diesel::result::QueryResult::Ok(true)
+                }
+                (FrontierAction::Queue, None) => {
+                    insert_into(check_frontier::table)
+                        .values(&CheckFrontier {
+                            check_id,
+                            entity_id,
+                            relation_job_kind: relation_job_kind.to_string(),
+                            remaining_depth,
+                            enqueued_at: Utc::now().naive_utc(),
+                            fetched_at: None,
+                        })
+                        .execute(conn)?;
+
+                    diesel::result::QueryResult::Ok(true)
+                }
+            }
+        })?;
+
+        Ok(is_claimed)
+    }
+
+    // The remaining depth a relation job should run with, or None if its (entity, kind) has
+    // already been fetched with at least as much depth left. The job stays on the frontier
+    // until mark_relation_job_fetched, so a failed fetch is retried when it's redelivered
+    pub fn visit_relation_job(
+        &mut self,
+        check_id: Uuid,
+        entity_id: Uuid,
+        relation_job_kind: &str,
+        remaining_depth: i32,
+    ) -> Result<Option<i32>, failure::Error> {
+        let visited_depth = self.conn.transaction(|conn| {
+            let existing = check_frontier::table
+                .filter(check_frontier::check_id.eq(check_id))
+                .filter(check_frontier::entity_id.eq(entity_id))
+                .filter(check_frontier::relation_job_kind.eq(relation_job_kind))
+                .for_update()
+                .first::<CheckFrontier>(conn)
+                .optional()?;
+            let visited_depth = match visit_depth(existing.as_ref(), remaining_depth) {
+                Some(visited_depth) => visited_depth,
+                None => return diesel::result::QueryResult::Ok(None),
+            };
+
+            // jobs enqueued without claiming are added to the frontier as they're visited
+            insert_into(check_frontier::table)
+                .values(&CheckFrontier {
+                    check_id,
+                    entity_id,
+                    relation_job_kind: relation_job_kind.to_string(),
+                    remaining_depth: visited_depth,
+                    enqueued_at: Utc::now().naive_utc(),
+                    fetched_at: None,
                 })
+                .on_conflict((
+                    check_frontier::check_id,
+                    check_frontier::entity_id,
+                    check_frontier::relation_job_kind,
+                ))
+                .do_update()
+                .set((
+                    check_frontier::remaining_depth.eq(visited_depth),
+                    check_frontier::fetched_at.eq(None::<NaiveDateTime>),
+                ))
                 .execute(conn)?;
 
-            diesel::result::QueryResult::Ok(())
+            diesel::result::QueryResult::Ok(Some(visited_depth))
+        })?;
+
+        Ok(visited_depth)
+    }
+
+    // Takes the relation job off the frontier once its relations have been recorded. If it
+    // was found again by a shorter path meanwhile it's left on, and the raised remaining
+    // depth is returned for the job to be run again with
+    pub fn mark_relation_job_fetched(
+        &mut self,
+        check_id: Uuid,
+        entity_id: Uuid,
+        relation_job_kind: &str,
+        remaining_depth: i32,
+    ) -> Result<Option<i32>, failure::Error> {
+        let raised_depth = self.conn.transaction(|conn| {
+            let frontier_row = check_frontier::table
+                .filter(check_frontier::check_id.eq(check_id))
+                .filter(check_frontier::entity_id.eq(entity_id))
+                .filter(check_frontier::relation_job_kind.eq(relation_job_kind));
+            let existing_depth = frontier_row
+                .select(check_frontier::remaining_depth)
+                .for_update()
+                .first::<i32>(conn)?;
+            if existing_depth > remaining_depth {
+                return diesel::result::QueryResult::Ok(Some(existing_depth));
+            }
+
+            update(frontier_row)
+                .set(check_frontier::fetched_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+
+            diesel::result::QueryResult::Ok(None)
         })?;
 
-        Ok(entity.id)
+        Ok(raised_depth)
+    }
+
+    // Takes a claimed job which was never enqueued off the frontier. It isn't left as fetched,
+    // so the entity's relations aren't taken to be known
+    pub fn drop_relation_job(
+        &mut self,
+        check_id: Uuid,
+        entity_id: Uuid,
+        relation_job_kind: &str,
+    ) -> Result<(), failure::Error> {
+        diesel::delete(check_frontier::table)
+            .filter(check_frontier::check_id.eq(check_id))
+            .filter(check_frontier::entity_id.eq(entity_id))
+            .filter(check_frontier::relation_job_kind.eq(relation_job_kind))
+            .filter(check_frontier::fetched_at.is_null())
+            .execute(&mut *self.conn)?;
+        Ok(())
+    }
+
+    // Entities of the check whose relations of the kind have been fetched
+    pub fn get_fetched_entities(
+        &mut self,
+        check_id: &Uuid,
+        relation_job_kind: &str,
+    ) -> Result<HashSet<Uuid>, failure::Error> {
+        Ok(check_frontier::table
+            .filter(check_frontier::check_id.eq(check_id))
+            .filter(check_frontier::relation_job_kind.eq(relation_job_kind))
+            .filter(check_frontier::fetched_at.is_not_null())
+            .select(check_frontier::entity_id)
+            .load::<Uuid>(&mut *self.conn)?
+            .into_iter()
+            .collect())
+    }
+
+    // Whether any relation jobs of the check are yet to be started
+    pub fn has_pending_relation_jobs(&mut self, check_id: &Uuid) -> Result<bool, failure::Error> {
+        let pending_jobs = check_frontier::table
+            .filter(check_frontier::check_id.eq(check_id))
+            .filter(check_frontier::fetched_at.is_null())
+            .count()
+            .get_result::<i64>(&mut *self.conn)?;
+
+        Ok(pending_jobs > 0)
+    }
+
+    // Whether this call is the one to queue the check's beneficial owner and exposure jobs,
+    // they're queued once, when the frontier first empties
+    pub fn claim_global_risk_jobs(&mut self, check_id: &Uuid) -> Result<bool, failure::Error> {
+        let claimed = update(check::table)
+            .filter(check::id.eq(check_id))
+            .filter(check::global_risk_queued_at.is_null())
+            .set(check::global_risk_queued_at.eq(Utc::now().naive_utc()))
+            .execute(&mut *self.conn)?;
+
+        Ok(claimed > 0)
+    }
+
+    pub fn release_global_risk_jobs(&mut self, check_id: &Uuid) -> Result<(), failure::Error> {
+        update(check::table)
+            .filter(check::id.eq(check_id))
+            .set(check::global_risk_queued_at.eq(None::<NaiveDateTime>))
+            .execute(&mut *self.conn)?;
+        Ok(())
+    }
+
+    // Whether this call is the one to queue a recompute of the check's exposures, requests
+    // made while one is queued are left to it
+    pub fn request_exposure(&mut self, check_id: &Uuid) -> Result<bool, failure::Error> {
+        let requested = update(check::table)
+            .filter(check::id.eq(check_id))
+            .filter(check::exposure_requested_at.is_null())
+            .set(check::exposure_requested_at.eq(Utc::now().naive_utc()))
+            .execute(&mut *self.conn)?;
+
+        Ok(requested > 0)
+    }
+
+    // Taken by the recompute before it reads the check, so anything found after that is
+    // requested again
+    pub fn take_exposure_request(&mut self, check_id: &Uuid) -> Result<(), failure::Error> {
+        update(check::table)
+            .filter(check::id.eq(check_id))
+            .set(check::exposure_requested_at.eq(None::<NaiveDateTime>))
+            .execute(&mut *self.conn)?;
+        Ok(())
+    }
+
+    pub fn get_canonical_entity(
+        &mut self,
+        canonical_entity_id: &Uuid,
+    ) -> Result<CanonicalEntity, failure::Error> {
+        Ok(canonical_entity::table
+            .filter(canonical_entity::id.eq(canonical_entity_id))
+            .first::<CanonicalEntity>(&mut *self.conn)?)
+    }
+
+    // Every check the canonical entity has appeared in, along with the entity it
+    // appeared as
+    pub fn get_canonical_entity_appearances(
+        &mut self,
+        canonical_entity_id: &Uuid,
+    ) -> Result<Vec<(Check, Uuid)>, failure::Error> {
+        let mut query = check::table
+            .inner_join(check_entity_map::table.on(check_entity_map::check_id.eq(check::id)))
+            .inner_join(entity::table.on(entity::id.eq(check_entity_map::entity_id)))
+            .filter(entity::canonical_entity_id.eq(canonical_entity_id))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check::tenant_id.eq(tenant_id));
+        }
+
+        Ok(query
+            .order_by(check::started_at.desc())
+            .select((check::all_columns, entity::id))
+            .load::<(Check, Uuid)>(&mut *self.conn)?)
     }
 
     pub fn insert_relationship(
         &mut self,
         relationship: Relationship,
+        source_fetch_id: Option<Uuid>,
     ) -> Result<(), failure::Error> {
-        insert_into(relationship::table)
-            .values(&relationship)
-            .execute(&mut self.conn)?;
+        upsert_relationship(&relationship, source_fetch_id).execute(&mut *self.conn)?;
 
         Ok(())
     }
@@ -91,11 +708,195 @@ impl Database {
                 relationship::started_on,
                 relationship::ended_on,
             ))
-            .load::<(Uuid, Option<NaiveDate>, Option<NaiveDate>)>(&mut self.conn)?;
+            .load::<(Uuid, Option<NaiveDate>, Option<NaiveDate>)>(&mut *self.conn)?;
 
         Ok(relations)
     }
 
+    // Every relationship between the check's entities
+    pub fn get_check_relationships(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<Relationship>, failure::Error> {
+        Ok(relationship::table
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(relationship::child_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select(Relationship::as_select())
+            .load::<Relationship>(&mut *self.conn)?)
+    }
+
+    // Relationships either end of which is one of the entities
+    pub fn get_relationships_of(
+        &mut self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<Relationship>, failure::Error> {
+        Ok(relationship::table
+            .filter(
+                relationship::parent_id
+                    .eq_any(entity_ids)
+                    .or(relationship::child_id.eq_any(entity_ids)),
+            )
+            .select(Relationship::as_select())
+            .load::<Relationship>(&mut *self.conn)?)
+    }
+
+    // Entities in any check resolved to the same canonical entity as one of the given
+    // entities, as (entity, same entity)
+    pub fn get_same_entities(
+        &mut self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<(Uuid, Uuid)>, failure::Error> {
+        let canonical_entity_ids = entity::table
+            .filter(entity::id.eq_any(entity_ids))
+            .filter(entity::canonical_entity_id.is_not_null())
+            .select((entity::id, entity::canonical_entity_id))
+            .load::<(Uuid, Option<Uuid>)>(&mut *self.conn)?;
+
+        let mut query = entity::table
+            .filter(
+                entity::canonical_entity_id.eq_any(
+                    canonical_entity_ids
+                        .iter()
+                        .filter_map(|(_, canonical_entity_id)| *canonical_entity_id)
+                        .collect::<Vec<Uuid>>(),
+                ),
+            )
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(entity::id.eq_any(tenant_entity_ids(tenant_id)));
+        }
+        let same_entities = query
+            .select((entity::id, entity::canonical_entity_id))
+            .load::<(Uuid, Option<Uuid>)>(&mut *self.conn)?;
+
+        Ok(canonical_entity_ids
+            .iter()
+            .flat_map(|(entity_id, canonical_entity_id)| {
+                same_entities
+                    .iter()
+                    .filter(move |(same_entity_id, same_canonical_entity_id)| {
+                        same_canonical_entity_id == canonical_entity_id
+                            && same_entity_id != entity_id
+                    })
+                    .map(move |(same_entity_id, _)| (*entity_id, *same_entity_id))
+            })
+            .collect())
+    }
+
+    // Current shareholdings between the check's entities, as (shareholder, company, band)
+    pub fn get_ownership_relations(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<(Uuid, Uuid, OwnershipBand)>, failure::Error> {
+        let relations = relationship::table
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(relationship::child_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .filter(relationship::kind.eq(Relationshipkind::Shareholder))
+            .filter(relationship::ended_on.is_null())
+            .select((
+                relationship::parent_id,
+                relationship::child_id,
+                relationship::ownership_band,
+            ))
+            .load::<(Uuid, Uuid, Option<OwnershipBand>)>(&mut *self.conn)?;
+
+        Ok(relations
+            .into_iter()
+            .filter_map(|(parent_id, child_id, ownership_band)| {
+                ownership_band.map(|ownership_band| (parent_id, child_id, ownership_band))
+            })
+            .collect())
+    }
+
+    pub fn insert_beneficial_owners(
+        &mut self,
+        check_id: Uuid,
+        owners: Vec<BeneficialOwner>,
+        paths: Vec<BeneficialOwnerPath>,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            diesel::delete(beneficial_owner::table)
+                .filter(beneficial_owner::check_id.eq(check_id))
+                .execute(conn)?;
+            diesel::delete(beneficial_owner_path::table)
+                .filter(beneficial_owner_path::check_id.eq(check_id))
+                .execute(conn)?;
+
+            if !owners.is_empty() {
+                insert_into(beneficial_owner::table)
+                    .values(&owners)
+                    .execute(conn)?;
+            }
+            if !paths.is_empty() {
+                insert_into(beneficial_owner_path::table)
+                    .values(&paths)
+                    .execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_beneficial_owners(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<BeneficialOwner>, failure::Error> {
+        Ok(beneficial_owner::table
+            .filter(beneficial_owner::check_id.eq(check_id))
+            .order_by(beneficial_owner::max_ownership.desc())
+            .load::<BeneficialOwner>(&mut *self.conn)?)
+    }
+
+    pub fn insert_exposures(
+        &mut self,
+        check_id: Uuid,
+        exposures: Vec<Exposure>,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            // concurrent recomputes of a check take turns, otherwise neither sees the rows the
+            // other is inserting and both sets are kept
+            diesel::sql_query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+                .bind::<diesel::sql_types::Text, _>(check_id.to_string())
+                .execute(conn)?;
+            diesel::delete(exposure::table)
+                .filter(exposure::check_id.eq(check_id))
+                .execute(conn)?;
+
+            if !exposures.is_empty() {
+                insert_into(exposure::table)
+                    .values(&exposures)
+                    .execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    // Highest scoring first
+    pub fn get_exposures(&mut self, check_id: &Uuid) -> Result<Vec<Exposure>, failure::Error> {
+        Ok(exposure::table
+            .filter(exposure::check_id.eq(check_id))
+            .order(exposure::score.desc())
+            .load::<Exposure>(&mut *self.conn)?)
+    }
+
+    pub fn get_beneficial_owner_paths(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<BeneficialOwnerPath>, failure::Error> {
+        Ok(beneficial_owner_path::table
+            .filter(beneficial_owner_path::check_id.eq(check_id))
+            .load::<BeneficialOwnerPath>(&mut *self.conn)?)
+    }
+
     fn get_existing_entity_id(
         &mut self,
         check_id: &Uuid,
@@ -106,19 +907,25 @@ impl Database {
             .filter(check_entity_map::check_id.eq(check_id))
             .filter(entity::company_house_number.eq(company_house_number))
             .select(entity::id)
-            .first::<Uuid>(&mut self.conn)
+            .first::<Uuid>(&mut *self.conn)
             .optional()?;
 
         Ok(entity_id)
     }
 
     pub fn get_entity(&mut self, entity_id: Uuid) -> Result<Entity, failure::Error> {
-        let entity = entity::table
-            .filter(entity::id.eq(entity_id))
+        let mut query = entity::table.filter(entity::id.eq(entity_id)).into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(entity::id.eq_any(tenant_entity_ids(tenant_id)));
+        }
+        let entity = query
             .select(entity::all_columns)
-            .first::<Entity>(&mut self.conn)
+            .first::<Entity>(&mut *self.conn)
             .optional()?
-            .ok_or_else(|| failure::format_err!("Entity not found with id: {}", entity_id))?;
+            .ok_or(NotFound {
+                kind: "Entity",
+                id: entity_id,
+            })?;
 
         Ok(entity)
     }
@@ -128,17 +935,23 @@ impl Database {
             .inner_join(check_entity_map::table.on(check_entity_map::entity_id.eq(entity::id)))
             .filter(check_entity_map::check_id.eq(check_id))
             .select(entity::all_columns)
-            .load::<Entity>(&mut self.conn)?;
+            .load::<Entity>(&mut *self.conn)?;
 
         Ok(entities)
     }
 
     pub fn get_check(&mut self, check_id: Uuid) -> Result<Check, failure::Error> {
-        let check = check::table
-            .filter(check::id.eq(check_id))
-            .first::<Check>(&mut self.conn)
+        let mut query = check::table.filter(check::id.eq(check_id)).into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check::tenant_id.eq(tenant_id));
+        }
+        let check = query
+            .first::<Check>(&mut *self.conn)
             .optional()?
-            .ok_or_else(|| failure::format_err!("Check not found with id: {}", check_id))?;
+            .ok_or(NotFound {
+                kind: "Check",
+                id: check_id,
+            })?;
 
         Ok(check)
     }
@@ -178,7 +991,7 @@ impl Database {
                 completed_at: None,
                 has_error: false,
             })
-            .execute(&mut self.conn)?;
+            .execute(&mut *self.conn)?;
         Ok(id)
     }
 
@@ -186,23 +999,36 @@ impl Database {
         update(job::table)
             .filter(job::id.eq(job_id))
             .set(job::completed_at.eq(Utc::now().naive_utc()))
-            .execute(&mut self.conn)?;
+            .execute(&mut *self.conn)?;
 
-        Ok(())
+        self.refresh_job_check_summaries(&job_id, false)
     }
 
     pub fn check_completed_at(
         &mut self,
         check_id: Uuid,
     ) -> Result<Option<NaiveDateTime>, failure::Error> {
-        let incomplete_jobs = job::table
+        // a cancelled check completed when it was cancelled, whatever jobs were left, and a
+        // paused check isn't complete until it's resumed
+        let (status, status_updated_at) = check::table
+            .filter(check::id.eq(check_id))
+            .select((check::status, check::status_updated_at))
+            .first::<(CheckStatus, Option<NaiveDateTime>)>(&mut *self.conn)?;
+        match status {
+            CheckStatus::Cancelled => return Ok(status_updated_at),
+            CheckStatus::Paused => return Ok(None),
+            CheckStatus::Running => {}
+        }
+
+        let incomplete_job = job::table
             .inner_join(check_job_map::table.on(check_job_map::job_id.eq(job::id)))
             .filter(check_job_map::check_id.eq(check_id))
             .filter(job::completed_at.is_null())
             .select(job::id)
-            .load::<Uuid>(&mut self.conn)?;
+            .first::<Uuid>(&mut *self.conn)
+            .optional()?;
 
-        if incomplete_jobs.len() > 0 {
+        if incomplete_job.is_some() {
             return Ok(None);
         }
 
@@ -210,33 +1036,405 @@ impl Database {
             .inner_join(check_job_map::table.on(check_job_map::job_id.eq(job::id)))
             .filter(check_job_map::check_id.eq(check_id))
             .select(diesel::dsl::max(job::completed_at))
-            .first::<Option<NaiveDateTime>>(&mut self.conn)?;
+            .first::<Option<NaiveDateTime>>(&mut *self.conn)?;
 
         Ok(latest_completion)
     }
 
-    pub fn get_checks(&mut self) -> Result<Vec<Check>, failure::Error> {
-        Ok(check::table
-            .select(check::all_columns)
-            .load::<Check>(&mut self.conn)?)
+    // Pauses, resumes or cancels the check. Cancelling completes any jobs parked while the
+    // check was paused
+    pub fn set_check_status(
+        &mut self,
+        check_id: Uuid,
+        status: CheckStatus,
+    ) -> Result<Check, failure::Error> {
+        let check = self.get_check(check_id)?;
+        let cant_become = || Conflict {
+            message: format!(
+                "Check {} is {}, it can't be made {}",
+                check_id,
+                check.status.as_str(),
+                status.as_str()
+            ),
+        };
+        if !check.status.can_become(status) {
+            return Err(cant_become().into());
+        }
+        if check.status == CheckStatus::Running && self.check_completed_at(check_id)?.is_some() {
+            return Err(Conflict {
+                message: format!("Check {} has already completed", check_id),
+            }
+            .into());
+        }
+
+        let now = Utc::now().naive_utc();
+        self.conn.transaction::<_, failure::Error, _>(|conn| {
+            // only made if the check is still as it was read, another request may have
+            // changed it since
+            let updated = update(check::table)
+                .filter(check::id.eq(check_id))
+                .filter(check::status.eq(check.status))
+                .set((check::status.eq(status), check::status_updated_at.eq(now)))
+                .execute(conn)?;
+            if updated == 0 {
+                return Err(Conflict {
+                    message: format!("Check {} was changed by another request", check_id),
+                }
+                .into());
+            }
+
+            if status == CheckStatus::Cancelled {
+                let parked_job_ids =
+                    diesel::delete(parked_job::table.filter(parked_job::check_id.eq(check_id)))
+                        .returning(parked_job::job_id)
+                        .get_results::<Uuid>(conn)?;
+                update(job::table)
+                    .filter(job::id.eq_any(parked_job_ids))
+                    .set(job::completed_at.eq(now))
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })?;
+        self.refresh_check_summary(check_id)?;
+
+        self.get_check(check_id)
+    }
+
+    // Unscoped, for workers to look up the check of a job they've been given
+    pub fn get_job_check_status(
+        &mut self,
+        job_id: &Uuid,
+    ) -> Result<Option<(Uuid, CheckStatus)>, failure::Error> {
+        Ok(check_job_map::table
+            .inner_join(check::table.on(check::id.eq(check_job_map::check_id)))
+            .filter(check_job_map::job_id.eq(job_id))
+            .select((check::id, check::status))
+            .first::<(Uuid, CheckStatus)>(&mut *self.conn)
+            .optional()?)
+    }
+
+    pub fn get_check_status(&mut self, check_id: &Uuid) -> Result<CheckStatus, failure::Error> {
+        Ok(check::table
+            .filter(check::id.eq(check_id))
+            .select(check::status)
+            .first::<CheckStatus>(&mut *self.conn)?)
+    }
+
+    pub fn park_job(&mut self, parked_job: &ParkedJob) -> Result<(), failure::Error> {
+        insert_into(parked_job::table)
+            .values(parked_job)
+            .on_conflict_do_nothing()
+            .execute(&mut *self.conn)?;
+        Ok(())
+    }
+
+    // Whether the job was still parked
+    pub fn unpark_job(&mut self, job_id: &Uuid) -> Result<bool, failure::Error> {
+        let deleted = diesel::delete(parked_job::table.filter(parked_job::job_id.eq(job_id)))
+            .execute(&mut *self.conn)?;
+        Ok(deleted > 0)
+    }
+
+    // The jobs parked while the check was paused, oldest first
+    pub fn get_parked_jobs(&mut self, check_id: Uuid) -> Result<Vec<ParkedJob>, failure::Error> {
+        Ok(parked_job::table
+            .filter(parked_job::check_id.eq(check_id))
+            .order(parked_job::parked_at.asc())
+            .load::<ParkedJob>(&mut *self.conn)?)
+    }
+
+    pub fn get_checks(&mut self) -> Result<Vec<Check>, failure::Error> {
+        let mut query = check::table.into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check::tenant_id.eq(tenant_id));
+        }
+
+        Ok(query
+            .select(check::all_columns)
+            .load::<Check>(&mut *self.conn)?)
+    }
+
+    pub fn get_root_entity(&mut self, check_id: &Uuid) -> Result<Entity, failure::Error> {
+        Ok(entity::table
+            .inner_join(check_entity_map::table.on(check_entity_map::entity_id.eq(entity::id)))
+            .filter(check_entity_map::check_id.eq(check_id))
+            .filter(entity::is_root.eq(true))
+            .select(entity::all_columns)
+            .first::<Entity>(&mut *self.conn)?)
+    }
+
+    pub fn update_job_with_error(&mut self, job_id: &Uuid) -> Result<(), failure::Error> {
+        update(job::table)
+            .filter(job::id.eq(job_id))
+            .set(job::has_error.eq(true))
+            .execute(&mut *self.conn)?;
+
+        self.refresh_job_check_summaries(job_id, true)
+    }
+
+    // Jobs complete often, so their checks' summaries are only refreshed once stale, when
+    // the check has completed, or when the job errored and the summary doesn't show an error
+    fn refresh_job_check_summaries(
+        &mut self,
+        job_id: &Uuid,
+        has_error: bool,
+    ) -> Result<(), failure::Error> {
+        let check_ids = check_job_map::table
+            .filter(check_job_map::job_id.eq(job_id))
+            .select(check_job_map::check_id)
+            .load::<Uuid>(&mut *self.conn)?;
+        let now = Utc::now().naive_utc();
+        for check_id in check_ids {
+            let summary = check_summary::table
+                .filter(check_summary::check_id.eq(check_id))
+                .select((check_summary::updated_at, check_summary::has_error))
+                .first::<(NaiveDateTime, bool)>(&mut *self.conn)
+                .optional()?;
+            let is_due = match summary {
+                None => true,
+                Some((updated_at, _)) if is_summary_stale(updated_at, now) => true,
+                Some((_, summary_has_error)) if has_error => !summary_has_error,
+                Some(_) => self.check_completed_at(check_id)?.is_some(),
+            };
+            if is_due {
+                self.refresh_check_summary(check_id)?;
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn get_root_entity(&mut self, check_id: &Uuid) -> Result<Entity, failure::Error> {
-        Ok(entity::table
+    // Recomputes the check's row in the summary table from its jobs, entities and flags
+    pub fn refresh_check_summary(&mut self, check_id: Uuid) -> Result<(), failure::Error> {
+        let check = self.get_check(check_id)?;
+        let root_entity = entity::table
             .inner_join(check_entity_map::table.on(check_entity_map::entity_id.eq(entity::id)))
             .filter(check_entity_map::check_id.eq(check_id))
             .filter(entity::is_root.eq(true))
             .select(entity::all_columns)
-            .first::<Entity>(&mut self.conn)?)
+            .first::<Entity>(&mut *self.conn)
+            .optional()?;
+        let entity_count = check_entity_map::table
+            .filter(check_entity_map::check_id.eq(check_id))
+            .count()
+            .get_result::<i64>(&mut *self.conn)?;
+        let flag_kinds = self.get_flag_kinds_for_check(&check_id)?;
+        let adverse_flags = flag_kinds
+            .iter()
+            .filter(|flag_kind| ADVERSE_FLAGS.contains(flag_kind))
+            .count();
+        let root_exposures: Vec<f64> = match &root_entity {
+            Some(root_entity) => self
+                .get_exposures(&check_id)?
+                .into_iter()
+                .filter(|exposure| exposure.entity_id == root_entity.id)
+                .map(|exposure| exposure.score)
+                .collect(),
+            None => vec![],
+        };
+        let risk = RiskBreakdown::from_counts(
+            adverse_flags,
+            flag_kinds.len() - adverse_flags,
+            self.get_check_disqualifications(&check_id)?.len(),
+            self.get_check_insolvencies(&check_id)?.len(),
+            &root_exposures,
+        );
+        let distinct_flags: HashSet<Flagkind> = flag_kinds.into_iter().collect();
+
+        let summary = CheckSummary {
+            check_id,
+            root_entity_id: root_entity.as_ref().map(|entity| entity.id),
+            company_house_number: root_entity
+                .as_ref()
+                .map(|entity| entity.company_house_number.clone()),
+            name: root_entity.and_then(|entity| entity.name),
+            started_at: check.started_at,
+            completed_at: self.check_completed_at(check_id)?,
+            has_error: self.does_check_have_errored_job(&check_id)?,
+            entity_count,
+            distinct_flags: distinct_flags.into_iter().collect(),
+            updated_at: Utc::now().naive_utc(),
+            kind: check.kind,
+            risk_score: risk.score,
+            risk_level: risk.level.to_string(),
+            tenant_id: check.tenant_id,
+            status: check.status,
+        };
+
+        insert_into(check_summary::table)
+            .values(&summary)
+            .on_conflict(check_summary::check_id)
+            .do_update()
+            .set(&summary)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
     }
 
-    pub fn update_job_with_error(&mut self, job_id: &Uuid) -> Result<(), failure::Error> {
-        update(job::table)
-            .filter(job::id.eq(job_id))
-            .set(job::has_error.eq(true))
-            .execute(&mut self.conn)?;
+    // Summarises the checks which don't have a summary, those started before summaries were
+    // kept or whose summaries were dropped by a migration. Returns how many were summarised
+    pub fn summarise_unsummarised_checks(&mut self) -> Result<usize, failure::Error> {
+        let mut unsummarised = check::table
+            .left_join(check_summary::table.on(check_summary::check_id.eq(check::id)))
+            .filter(check_summary::check_id.is_null())
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            unsummarised = unsummarised.filter(check::tenant_id.eq(tenant_id));
+        }
+        let unsummarised_check_ids = unsummarised
+            .select(check::id)
+            .load::<Uuid>(&mut *self.conn)?;
+        for check_id in &unsummarised_check_ids {
+            self.refresh_check_summary(*check_id)?;
+        }
 
-        Ok(())
+        Ok(unsummarised_check_ids.len())
+    }
+
+    // A page of check summaries matching the query, and the cursor of the next page if
+    // there are more. Checks are summarised when they're started, see
+    // summarise_unsummarised_checks for older ones
+    pub fn query_check_summaries(
+        &mut self,
+        checks_query: &ChecksQuery,
+    ) -> Result<(Vec<CheckSummary>, Option<String>), failure::Error> {
+        let mut query = check_summary::table.into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check_summary::tenant_id.eq(tenant_id));
+        }
+        if let Some(kind) = checks_query.kind {
+            query = query.filter(check_summary::kind.eq(kind));
+        }
+        if let Some(started_from) = checks_query.started_from {
+            query =
+                query.filter(check_summary::started_at.ge(started_from.and_time(NaiveTime::MIN)));
+        }
+        if let Some(started_to) = checks_query.started_to.and_then(|date| date.succ_opt()) {
+            query = query.filter(check_summary::started_at.lt(started_to.and_time(NaiveTime::MIN)));
+        }
+        match checks_query.completed {
+            Some(true) => query = query.filter(check_summary::completed_at.is_not_null()),
+            Some(false) => query = query.filter(check_summary::completed_at.is_null()),
+            None => {}
+        }
+        if let Some(has_error) = checks_query.has_error {
+            query = query.filter(check_summary::has_error.eq(has_error));
+        }
+        if let Some(risk_level) = &checks_query.risk_level {
+            query = query.filter(check_summary::risk_level.eq(risk_level.clone()));
+        }
+        if let Some(flag) = checks_query.flag {
+            query = query.filter(check_summary::distinct_flags.contains(vec![flag]));
+        }
+        if let Some(company) = &checks_query.company {
+            query = query.filter(
+                check_summary::company_house_number
+                    .eq(format!("{:0>8}", company))
+                    .or(check_summary::name.ilike(format!("%{}%", escape_like(company)))),
+            );
+        }
+
+        // keyset pagination, each page continues after the (sort value, check id) of the cursor
+        macro_rules! sorted {
+            ($query:expr, $column:expr, $after:expr) => {{
+                let mut query = $query;
+                match (checks_query.direction, $after) {
+                    (SortDirection::Asc, Some((value, check_id))) => {
+                        query = query.filter(
+                            $column
+                                .gt(value.clone())
+                                .or($column.eq(value).and(check_summary::check_id.gt(check_id))),
+                        )
+                    }
+                    (SortDirection::Desc, Some((value, check_id))) => {
+                        query = query.filter(
+                            $column
+                                .lt(value.clone())
+                                .or($column.eq(value).and(check_summary::check_id.lt(check_id))),
+                        )
+                    }
+                    (_, None) => {}
+                }
+                match checks_query.direction {
+                    SortDirection::Asc => {
+                        query.order_by(($column.asc(), check_summary::check_id.asc()))
+                    }
+                    SortDirection::Desc => {
+                        query.order_by(($column.desc(), check_summary::check_id.desc()))
+                    }
+                }
+            }};
+        }
+
+        let cursor = checks_query.decode_cursor()?;
+        let invalid_cursor = || failure::format_err!("Cursor doesn't match the sort");
+        let query = match checks_query.sort {
+            CheckSort::StartedAt => {
+                let after = cursor
+                    .map(|cursor| {
+                        NaiveDateTime::parse_from_str(&cursor.value, STARTED_AT_CURSOR_FORMAT)
+                            .map(|value| (value, cursor.check_id))
+                            .map_err(|_| invalid_cursor())
+                    })
+                    .transpose()?;
+                sorted!(query, check_summary::started_at, after)
+            }
+            CheckSort::Name => {
+                let after = cursor.map(|cursor| (cursor.value, cursor.check_id));
+                sorted!(query, coalesce(check_summary::name, ""), after)
+            }
+            CheckSort::RiskScore => {
+                let after = cursor
+                    .map(|cursor| {
+                        cursor
+                            .value
+                            .parse::<f64>()
+                            .map(|value| (value, cursor.check_id))
+                            .map_err(|_| invalid_cursor())
+                    })
+                    .transpose()?;
+                sorted!(query, check_summary::risk_score, after)
+            }
+            CheckSort::EntityCount => {
+                let after = cursor
+                    .map(|cursor| {
+                        cursor
+                            .value
+                            .parse::<i64>()
+                            .map(|value| (value, cursor.check_id))
+                            .map_err(|_| invalid_cursor())
+                    })
+                    .transpose()?;
+                sorted!(query, check_summary::entity_count, after)
+            }
+        };
+
+        let page_size = checks_query.page_size();
+        let mut summaries = query
+            .limit(page_size + 1)
+            .load::<CheckSummary>(&mut *self.conn)?;
+        let next_cursor = if summaries.len() as i64 > page_size {
+            summaries.truncate(page_size as usize);
+            summaries
+                .last()
+                .map(|summary| ChecksCursor::after(summary, checks_query.sort).encode())
+        } else {
+            None
+        };
+
+        Ok((summaries, next_cursor))
+    }
+
+    pub fn any_check_has_error(&mut self) -> Result<bool, failure::Error> {
+        let mut query = check_summary::table
+            .filter(check_summary::has_error.eq(true))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(check_summary::tenant_id.eq(tenant_id));
+        }
+
+        Ok(diesel::select(diesel::dsl::exists(query)).get_result::<bool>(&mut *self.conn)?)
     }
 
     pub fn does_check_have_errored_job(&mut self, check_id: &Uuid) -> Result<bool, failure::Error> {
@@ -244,7 +1442,7 @@ impl Database {
             .inner_join(check_job_map::table.on(check_job_map::job_id.eq(job::id)))
             .filter(check_job_map::check_id.eq(check_id))
             .select(job::has_error)
-            .load::<bool>(&mut self.conn)?;
+            .load::<bool>(&mut *self.conn)?;
 
         Ok(has_error
             .into_iter()
@@ -259,11 +1457,19 @@ impl Database {
             .filter(check_job_map::check_id.eq(check_id))
             .select(job::id)
             .count()
-            .first::<i64>(&mut self.conn)?;
+            .first::<i64>(&mut *self.conn)?;
 
         Ok(num_jobs as usize)
     }
 
+    // Unscoped, jobs are queued at their check's priority whichever tenant it's for
+    pub fn get_check_priority(&mut self, check_id: &Uuid) -> Result<Priority, failure::Error> {
+        Ok(check::table
+            .filter(check::id.eq(check_id))
+            .select(check::priority)
+            .first::<Priority>(&mut *self.conn)?)
+    }
+
     pub fn insert_positions(
         &mut self,
         entity_id: Uuid,
@@ -295,6 +1501,8 @@ impl Database {
         &mut self,
         entity_id: Uuid,
         flag_kinds: Vec<Flagkind>,
+        source_fetch_id: Option<Uuid>,
+        sanctions_match_id: Option<Uuid>,
     ) -> Result<(), failure::Error> {
         self.conn.transaction(|conn| {
             for flag_kind in flag_kinds {
@@ -303,6 +1511,8 @@ impl Database {
                     .values(Flag {
                         id,
                         kind: flag_kind,
+                        source_fetch_id,
+                        sanctions_match_id,
                     })
                     .execute(conn)?;
 
@@ -354,7 +1564,29 @@ impl Database {
             .inner_join(flags::table.on(flags::flag_id.eq(flag::id)))
             .filter(flags::entity_id.eq(entity_id))
             .select(flag::kind)
-            .load::<Flagkind>(&mut self.conn)?)
+            .load::<Flagkind>(&mut *self.conn)?)
+    }
+
+    // Every flag on one of the check's entities, as (entity, flag, kind)
+    pub fn get_flags_for_check(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<(Uuid, Uuid, Flagkind)>, failure::Error> {
+        Ok(flag::table
+            .inner_join(flags::table.on(flags::flag_id.eq(flag::id)))
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(flags::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select((flags::entity_id, flag::id, flag::kind))
+            .load::<(Uuid, Uuid, Flagkind)>(&mut *self.conn)?)
+    }
+
+    pub fn get_entity_check_ids(&mut self, entity_id: &Uuid) -> Result<Vec<Uuid>, failure::Error> {
+        Ok(check_entity_map::table
+            .filter(check_entity_map::entity_id.eq(entity_id))
+            .select(check_entity_map::check_id)
+            .load::<Uuid>(&mut *self.conn)?)
     }
 
     pub fn get_flag_kinds_for_check(
@@ -368,7 +1600,7 @@ impl Database {
             )
             .filter(check_entity_map::check_id.eq(check_id))
             .select(flag::kind)
-            .load::<Flagkind>(&mut self.conn)?)
+            .load::<Flagkind>(&mut *self.conn)?)
     }
 
     pub fn get_positions(&mut self, entity_id: &Uuid) -> Result<Vec<String>, failure::Error> {
@@ -376,65 +1608,411 @@ impl Database {
             .inner_join(positions::table.on(positions::position_id.eq(position::id)))
             .filter(positions::entity_id.eq(entity_id))
             .select(position::title)
-            .load::<String>(&mut self.conn)?)
+            .load::<String>(&mut *self.conn)?)
+    }
+
+    pub fn get_datasets(&mut self, entity_id: &Uuid) -> Result<Vec<String>, failure::Error> {
+        Ok(dataset::table
+            .inner_join(datasets::table.on(datasets::dataset_id.eq(dataset::id)))
+            .filter(datasets::entity_id.eq(entity_id))
+            .select(dataset::name)
+            .load::<String>(&mut *self.conn)?)
+    }
+
+    // Positions of every one of the check's entities, as (entity, title)
+    pub fn get_check_positions(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<(Uuid, String)>, failure::Error> {
+        Ok(position::table
+            .inner_join(positions::table.on(positions::position_id.eq(position::id)))
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(positions::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select((positions::entity_id, position::title))
+            .load::<(Uuid, String)>(&mut *self.conn)?)
+    }
+
+    // Datasets of every one of the check's entities, as (entity, name)
+    pub fn get_check_datasets(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<(Uuid, String)>, failure::Error> {
+        Ok(dataset::table
+            .inner_join(datasets::table.on(datasets::dataset_id.eq(dataset::id)))
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(datasets::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select((datasets::entity_id, dataset::name))
+            .load::<(Uuid, String)>(&mut *self.conn)?)
+    }
+
+    pub fn insert_outlier_age(
+        &mut self,
+        entity_id: &Uuid,
+        outlier: bool,
+    ) -> Result<(), failure::Error> {
+        insert_into(outlier_age::table)
+            .values(OutlierAge {
+                entity_id: *entity_id,
+                outlier,
+            })
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn outlier_age(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
+        let is_outlier = outlier_age::table
+            .filter(outlier_age::entity_id.eq(*entity_id))
+            .select(outlier_age::outlier)
+            .first::<bool>(&mut *self.conn)
+            .optional()?;
+
+        Ok(is_outlier.unwrap_or_default())
+    }
+
+    pub fn insert_dormant_company(
+        &mut self,
+        entity_id: &Uuid,
+        dormant: bool,
+    ) -> Result<(), failure::Error> {
+        insert_into(dormant_company::table)
+            .values(DormantCompany {
+                entity_id: *entity_id,
+                dormant,
+            })
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn company_dormant(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
+        let is_dormant = dormant_company::table
+            .filter(dormant_company::entity_id.eq(*entity_id))
+            .select(dormant_company::dormant)
+            .first::<bool>(&mut *self.conn)
+            .optional()?;
+
+        Ok(is_dormant.unwrap_or_default())
+    }
+
+    pub fn insert_company_profile(
+        &mut self,
+        profile: CompanyProfile,
+        previous_names: Vec<PreviousName>,
+        entity_details: EntityDetails,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            insert_into(company_profile::table)
+                .values(&profile)
+                .on_conflict(company_profile::entity_id)
+                .do_update()
+                .set(&profile)
+                .execute(conn)?;
+
+            diesel::delete(previous_name::table)
+                .filter(previous_name::entity_id.eq(profile.entity_id))
+                .execute(conn)?;
+
+            if !previous_names.is_empty() {
+                insert_into(previous_name::table)
+                    .values(&previous_names)
+                    .execute(conn)?;
+            }
+
+            if !entity_details.is_empty() {
+                update(entity::table)
+                    .filter(entity::id.eq(profile.entity_id))
+                    .set(&entity_details)
+                    .execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn update_entity_details(
+        &mut self,
+        entity_id: Uuid,
+        entity_details: EntityDetails,
+    ) -> Result<(), failure::Error> {
+        if entity_details.is_empty() {
+            return Ok(());
+        }
+
+        update(entity::table)
+            .filter(entity::id.eq(entity_id))
+            .set(&entity_details)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_company_profile(
+        &mut self,
+        entity_id: &Uuid,
+    ) -> Result<Option<CompanyProfile>, failure::Error> {
+        Ok(company_profile::table
+            .filter(company_profile::entity_id.eq(entity_id))
+            .first::<CompanyProfile>(&mut *self.conn)
+            .optional()?)
+    }
+
+    pub fn get_check_company_profiles(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<CompanyProfile>, failure::Error> {
+        Ok(company_profile::table
+            .inner_join(
+                check_entity_map::table
+                    .on(check_entity_map::entity_id.eq(company_profile::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select(company_profile::all_columns)
+            .load::<CompanyProfile>(&mut *self.conn)?)
+    }
+
+    pub fn get_previous_names(
+        &mut self,
+        entity_id: &Uuid,
+    ) -> Result<Vec<PreviousName>, failure::Error> {
+        Ok(previous_name::table
+            .filter(previous_name::entity_id.eq(entity_id))
+            .order_by(previous_name::ceased_on.desc())
+            .load::<PreviousName>(&mut *self.conn)?)
+    }
+
+    pub fn get_check_previous_names(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<PreviousName>, failure::Error> {
+        Ok(previous_name::table
+            .inner_join(
+                check_entity_map::table
+                    .on(check_entity_map::entity_id.eq(previous_name::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .order_by(previous_name::ceased_on.desc())
+            .select(previous_name::all_columns)
+            .load::<PreviousName>(&mut *self.conn)?)
+    }
+
+    pub fn insert_charges(
+        &mut self,
+        entity_id: Uuid,
+        charges: Vec<Charge>,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            diesel::delete(charge::table)
+                .filter(charge::entity_id.eq(entity_id))
+                .execute(conn)?;
+
+            if !charges.is_empty() {
+                insert_into(charge::table).values(&charges).execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_charges(&mut self, entity_id: &Uuid) -> Result<Vec<Charge>, failure::Error> {
+        Ok(charge::table
+            .filter(charge::entity_id.eq(entity_id))
+            .order_by(charge::created_on.desc())
+            .load::<Charge>(&mut *self.conn)?)
+    }
+
+    pub fn get_check_charges(&mut self, check_id: &Uuid) -> Result<Vec<Charge>, failure::Error> {
+        Ok(charge::table
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(charge::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .order_by(charge::created_on.desc())
+            .select(charge::all_columns)
+            .load::<Charge>(&mut *self.conn)?)
+    }
+
+    pub fn insert_insolvencies(
+        &mut self,
+        entity_id: Uuid,
+        cases: Vec<InsolvencyCaseRecord>,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            let existing_ids = insolvency::table
+                .filter(insolvency::entity_id.eq(entity_id))
+                .select(insolvency::id)
+                .load::<Uuid>(conn)?;
+
+            diesel::delete(insolvency_date::table)
+                .filter(insolvency_date::insolvency_id.eq_any(&existing_ids))
+                .execute(conn)?;
+            diesel::delete(insolvency_practitioner::table)
+                .filter(insolvency_practitioner::insolvency_id.eq_any(&existing_ids))
+                .execute(conn)?;
+            diesel::delete(insolvency::table)
+                .filter(insolvency::id.eq_any(&existing_ids))
+                .execute(conn)?;
+
+            for case in cases {
+                insert_into(insolvency::table)
+                    .values(&case.insolvency)
+                    .execute(conn)?;
+                // the same date can be listed twice
+                if !case.dates.is_empty() {
+                    insert_into(insolvency_date::table)
+                        .values(&case.dates)
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                }
+                if !case.practitioners.is_empty() {
+                    insert_into(insolvency_practitioner::table)
+                        .values(&case.practitioners)
+                        .execute(conn)?;
+                }
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_insolvencies(
+        &mut self,
+        entity_id: &Uuid,
+    ) -> Result<Vec<Insolvency>, failure::Error> {
+        Ok(insolvency::table
+            .filter(insolvency::entity_id.eq(entity_id))
+            .load::<Insolvency>(&mut *self.conn)?)
+    }
+
+    pub fn get_check_insolvencies(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<Insolvency>, failure::Error> {
+        Ok(insolvency::table
+            .inner_join(
+                check_entity_map::table.on(check_entity_map::entity_id.eq(insolvency::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select(insolvency::all_columns)
+            .load::<Insolvency>(&mut *self.conn)?)
+    }
+
+    pub fn get_insolvency_dates(
+        &mut self,
+        entity_id: &Uuid,
+    ) -> Result<Vec<NaiveDate>, failure::Error> {
+        Ok(insolvency_date::table
+            .inner_join(insolvency::table.on(insolvency::id.eq(insolvency_date::insolvency_id)))
+            .filter(insolvency::entity_id.eq(entity_id))
+            .select(insolvency_date::date)
+            .load::<NaiveDate>(&mut *self.conn)?)
+    }
+
+    pub fn insert_disqualifications(
+        &mut self,
+        entity_id: Uuid,
+        disqualifications: Vec<Disqualification>,
+    ) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            diesel::delete(disqualification::table)
+                .filter(disqualification::entity_id.eq(entity_id))
+                .execute(conn)?;
+
+            if !disqualifications.is_empty() {
+                insert_into(disqualification::table)
+                    .values(&disqualifications)
+                    .execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_disqualifications(
+        &mut self,
+        entity_id: &Uuid,
+    ) -> Result<Vec<Disqualification>, failure::Error> {
+        Ok(disqualification::table
+            .filter(disqualification::entity_id.eq(entity_id))
+            .order_by(disqualification::disqualified_from.desc())
+            .load::<Disqualification>(&mut *self.conn)?)
     }
 
-    pub fn get_datasets(&mut self, entity_id: &Uuid) -> Result<Vec<String>, failure::Error> {
-        Ok(dataset::table
-            .inner_join(datasets::table.on(datasets::dataset_id.eq(dataset::id)))
-            .filter(datasets::entity_id.eq(entity_id))
-            .select(dataset::name)
-            .load::<String>(&mut self.conn)?)
+    pub fn get_check_disqualifications(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<Disqualification>, failure::Error> {
+        Ok(disqualification::table
+            .inner_join(
+                check_entity_map::table
+                    .on(check_entity_map::entity_id.eq(disqualification::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .order_by(disqualification::disqualified_from.desc())
+            .select(disqualification::all_columns)
+            .load::<Disqualification>(&mut *self.conn)?)
     }
 
-    pub fn insert_outlier_age(
+    pub fn insert_recent_insolvency(
         &mut self,
         entity_id: &Uuid,
-        outlier: bool,
+        recent: bool,
     ) -> Result<(), failure::Error> {
-        insert_into(outlier_age::table)
-            .values(OutlierAge {
+        insert_into(recent_insolvency::table)
+            .values(RecentInsolvency {
                 entity_id: *entity_id,
-                outlier,
+                recent,
             })
-            .execute(&mut self.conn)?;
+            .execute(&mut *self.conn)?;
 
         Ok(())
     }
 
-    pub fn outlier_age(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
-        let is_outlier = outlier_age::table
-            .filter(outlier_age::entity_id.eq(*entity_id))
-            .select(outlier_age::outlier)
-            .first::<bool>(&mut self.conn)
+    pub fn recent_insolvency(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
+        let is_recent = recent_insolvency::table
+            .filter(recent_insolvency::entity_id.eq(*entity_id))
+            .select(recent_insolvency::recent)
+            .first::<bool>(&mut *self.conn)
             .optional()?;
 
-        Ok(is_outlier.unwrap_or_default())
+        Ok(is_recent.unwrap_or_default())
     }
 
-    pub fn insert_dormant_company(
+    pub fn insert_unusual_lender(
         &mut self,
         entity_id: &Uuid,
-        dormant: bool,
+        unusual: bool,
     ) -> Result<(), failure::Error> {
-        insert_into(dormant_company::table)
-            .values(DormantCompany {
+        insert_into(unusual_lender::table)
+            .values(UnusualLender {
                 entity_id: *entity_id,
-                dormant,
+                unusual,
             })
-            .execute(&mut self.conn)?;
+            .execute(&mut *self.conn)?;
 
         Ok(())
     }
 
-    pub fn company_dormant(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
-        let is_dormant = dormant_company::table
-            .filter(dormant_company::entity_id.eq(*entity_id))
-            .select(dormant_company::dormant)
-            .first::<bool>(&mut self.conn)
+    pub fn unusual_lender(&mut self, entity_id: &Uuid) -> Result<bool, failure::Error> {
+        let is_unusual = unusual_lender::table
+            .filter(unusual_lender::entity_id.eq(*entity_id))
+            .select(unusual_lender::unusual)
+            .first::<bool>(&mut *self.conn)
             .optional()?;
 
-        Ok(is_dormant.unwrap_or_default())
+        Ok(is_unusual.unwrap_or_default())
     }
 
     pub fn start_monitoring(
@@ -443,6 +2021,11 @@ impl Database {
         company_house_id: String,
     ) -> Result<(), failure::Error> {
         self.conn.transaction(|conn| {
+            // the company is monitored for the tenant which started the check
+            let tenant_id = check::table
+                .filter(check::id.eq(check_id))
+                .select(check::tenant_id)
+                .first::<Uuid>(conn)?;
             let monitoring_span_id = Uuid::new_v4();
 
             insert_into(monitoring_span::table)
@@ -459,6 +2042,7 @@ impl Database {
                     id: monitored_entity_id,
                     company_house_id,
                     monitoring_span_id,
+                    tenant_id,
                 })
                 .execute(conn)?;
 
@@ -476,8 +2060,9 @@ impl Database {
     }
 
     pub fn cancel_monitoring(&mut self, check_id: Uuid) -> Result<(), failure::Error> {
+        let tenant_id = self.tenant_id;
         self.conn.transaction(|conn| {
-            let monitoring_span_id = monitoring_span::table
+            let mut query = monitoring_span::table
                 .inner_join(
                     monitored_entity::table
                         .on(monitored_entity::monitoring_span_id.eq(monitoring_span::id)),
@@ -487,8 +2072,11 @@ impl Database {
                         .on(check_monitored_entity::monitored_entity_id.eq(monitored_entity::id)),
                 )
                 .filter(check_monitored_entity::check_id.eq(check_id))
-                .select(monitoring_span::id)
-                .first::<Uuid>(conn)?;
+                .into_boxed();
+            if let Some(tenant_id) = tenant_id {
+                query = query.filter(monitored_entity::tenant_id.eq(tenant_id));
+            }
+            let monitoring_span_id = query.select(monitoring_span::id).first::<Uuid>(conn)?;
 
             update(monitoring_span::table)
                 .filter(monitoring_span::id.eq(monitoring_span_id))
@@ -509,7 +2097,10 @@ impl Database {
         let snapshot_id = Uuid::new_v4();
 
         self.conn.transaction(|conn| {
-            insert_into(entity::table).values(entity).execute(conn)?;
+            let canonical_entity_id = resolve_canonical_entity(conn, entity)?;
+            insert_into(entity::table)
+                .values((entity, entity::canonical_entity_id.eq(canonical_entity_id)))
+                .execute(conn)?;
             insert_into(snapshot::table)
                 .values(Snapshot {
                     id: snapshot_id,
@@ -545,7 +2136,7 @@ impl Database {
                 timepoint,
                 kind,
             })
-            .execute(&mut self.conn)?;
+            .execute(&mut *self.conn)?;
 
         Ok(())
     }
@@ -558,7 +2149,7 @@ impl Database {
             .filter(processed_update::kind.eq(kind))
             .order_by(processed_update::timepoint.desc())
             .select(processed_update::timepoint)
-            .first::<i32>(&mut self.conn)
+            .first::<i32>(&mut *self.conn)
             .optional()?)
     }
 
@@ -573,19 +2164,24 @@ impl Database {
             )
             .filter(monitored_entity::company_house_id.eq(company_house_id))
             .select(check_monitored_entity::check_id)
-            .load::<Uuid>(&mut self.conn)?)
+            .load::<Uuid>(&mut *self.conn)?)
     }
 
     pub fn get_monitored_entities(&mut self) -> Result<Vec<MonitoredEntity>, failure::Error> {
-        Ok(monitored_entity::table
+        let mut query = monitored_entity::table.into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(monitored_entity::tenant_id.eq(tenant_id));
+        }
+
+        Ok(query
             .select(monitored_entity::all_columns)
-            .load::<MonitoredEntity>(&mut self.conn)?)
+            .load::<MonitoredEntity>(&mut *self.conn)?)
     }
 
     pub fn get_monitoring_span(&mut self, id: Uuid) -> Result<MonitoringSpan, failure::Error> {
         Ok(monitoring_span::table
             .filter(monitoring_span::id.eq(id))
-            .first::<MonitoringSpan>(&mut self.conn)?)
+            .first::<MonitoringSpan>(&mut *self.conn)?)
     }
 
     pub fn get_last_update(
@@ -596,7 +2192,7 @@ impl Database {
             .filter(snapshot::entity_id.eq(monitored_entity_id))
             .order_by(snapshot::recieved_at.desc())
             .select(snapshot::recieved_at)
-            .first::<NaiveDateTime>(&mut self.conn)
+            .first::<NaiveDateTime>(&mut *self.conn)
             .optional()?)
     }
 
@@ -604,6 +2200,813 @@ impl Database {
         Ok(check_monitored_entity::table
             .filter(check_monitored_entity::monitored_entity_id.eq(monitored_entity_id))
             .select(check_monitored_entity::check_id)
-            .first::<Uuid>(&mut self.conn)?)
+            .first::<Uuid>(&mut *self.conn)?)
+    }
+
+    // Archives the response's payload, once per distinct payload, and records where and
+    // when it was fetched. Returns the fetch's id for what's recorded from it to refer to
+    pub fn archive_fetch<T>(
+        &mut self,
+        check_id: Uuid,
+        fetched: &Fetched<T>,
+    ) -> Result<Uuid, failure::Error> {
+        let id = Uuid::new_v4();
+
+        self.conn.transaction(|conn| {
+            insert_into(raw_payload::table)
+                .values(RawPayload {
+                    response_hash: fetched.response_hash.clone(),
+                    body: fetched.body.clone(),
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            insert_into(source_fetch::table)
+                .values(SourceFetch {
+                    id,
+                    check_id,
+                    source: fetched.source.as_str().to_string(),
+                    endpoint: fetched.endpoint.clone(),
+                    fetched_at: fetched.fetched_at,
+                    response_hash: fetched.response_hash.clone(),
+                })
+                .execute(conn)?;
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(id)
+    }
+
+    pub fn get_source_fetch(
+        &mut self,
+        source_fetch_id: Uuid,
+    ) -> Result<(SourceFetch, Vec<u8>), failure::Error> {
+        let mut query = source_fetch::table
+            .inner_join(
+                raw_payload::table.on(raw_payload::response_hash.eq(source_fetch::response_hash)),
+            )
+            .filter(source_fetch::id.eq(source_fetch_id))
+            .into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(source_fetch::check_id.eq_any(tenant_check_ids(tenant_id)));
+        }
+
+        Ok(query
+            .select((SourceFetch::as_select(), raw_payload::body))
+            .first::<(SourceFetch, Vec<u8>)>(&mut *self.conn)
+            .optional()?
+            .ok_or(NotFound {
+                kind: "Source fetch",
+                id: source_fetch_id,
+            })?)
+    }
+
+    pub fn insert_audit_log(&mut self, audit_log: &AuditLog) -> Result<(), failure::Error> {
+        insert_into(audit_log::table)
+            .values(audit_log)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    // The tenant's audit log, newest first, optionally only entries about one target
+    pub fn get_audit_log(
+        &mut self,
+        target_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AuditLog>, failure::Error> {
+        let tenant_id = self
+            .tenant_id
+            .ok_or_else(|| format_err!("The audit log can only be read for a tenant"))?;
+        let mut query = audit_log::table
+            .filter(audit_log::tenant_id.eq(tenant_id))
+            .into_boxed();
+        if let Some(target_id) = target_id {
+            query = query.filter(audit_log::target_id.eq(target_id));
+        }
+
+        Ok(query
+            .order(audit_log::occurred_at.desc())
+            .limit(limit)
+            .load::<AuditLog>(&mut *self.conn)?)
+    }
+
+    pub fn get_case(&mut self, check_id: Uuid) -> Result<CheckCase, failure::Error> {
+        self.get_check(check_id)?;
+
+        Ok(check_case::table
+            .filter(check_case::check_id.eq(check_id))
+            .first::<CheckCase>(&mut *self.conn)?)
+    }
+
+    // Only what's given is changed, an assignee of None unassigns the case. Cases can only
+    // be assigned to users of the check's tenant
+    pub fn update_case(
+        &mut self,
+        check_id: Uuid,
+        status: Option<CaseStatus>,
+        assignee_id: Option<Option<Uuid>>,
+    ) -> Result<CheckCase, failure::Error> {
+        let check = self.get_check(check_id)?;
+        if let Some(Some(assignee_id)) = assignee_id {
+            match self.get_tenant_user(assignee_id)? {
+                Some(user) if user.tenant_id == check.tenant_id => {}
+                _ => {
+                    return Err(NotFound {
+                        kind: "User",
+                        id: assignee_id,
+                    }
+                    .into())
+                }
+            }
+        }
+
+        let case = self.get_case(check_id)?;
+        Ok(
+            update(check_case::table.filter(check_case::check_id.eq(check_id)))
+                .set((
+                    check_case::status.eq(status.unwrap_or(case.status)),
+                    check_case::assignee_id.eq(assignee_id.unwrap_or(case.assignee_id)),
+                    check_case::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .get_result::<CheckCase>(&mut *self.conn)?,
+        )
+    }
+
+    pub fn insert_sanctions_match(
+        &mut self,
+        sanctions_match: &SanctionsMatch,
+    ) -> Result<(), failure::Error> {
+        insert_into(sanctions_match::table)
+            .values(sanctions_match)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn get_check_sanctions_matches(
+        &mut self,
+        check_id: &Uuid,
+    ) -> Result<Vec<SanctionsMatch>, failure::Error> {
+        Ok(sanctions_match::table
+            .inner_join(
+                check_entity_map::table
+                    .on(check_entity_map::entity_id.eq(sanctions_match::entity_id)),
+            )
+            .filter(check_entity_map::check_id.eq(check_id))
+            .select(SanctionsMatch::as_select())
+            .load::<SanctionsMatch>(&mut *self.conn)?)
+    }
+
+    // The flag or match must be on one of the check's entities
+    pub fn insert_disposition(&mut self, disposition: &Disposition) -> Result<(), failure::Error> {
+        let check_id = disposition.check_id;
+        self.get_check(check_id)?;
+
+        let on_check_entity = match (disposition.flag_id, disposition.sanctions_match_id) {
+            (Some(flag_id), None) => self
+                .get_flags_for_check(&check_id)?
+                .iter()
+                .any(|(_, id, _)| *id == flag_id)
+                .then_some(())
+                .ok_or(NotFound {
+                    kind: "Flag",
+                    id: flag_id,
+                }),
+            (None, Some(sanctions_match_id)) => self
+                .get_check_sanctions_matches(&check_id)?
+                .iter()
+                .any(|sanctions_match| sanctions_match.id == sanctions_match_id)
+                .then_some(())
+                .ok_or(NotFound {
+                    kind: "Sanctions match",
+                    id: sanctions_match_id,
+                }),
+            _ => return Err(format_err!("A disposition is on exactly one flag or match")),
+        };
+        on_check_entity?;
+
+        insert_into(disposition::table)
+            .values(disposition)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    // Oldest first
+    pub fn get_dispositions(&mut self, check_id: Uuid) -> Result<Vec<Disposition>, failure::Error> {
+        self.get_check(check_id)?;
+
+        Ok(disposition::table
+            .filter(disposition::check_id.eq(check_id))
+            .order(disposition::created_at.asc())
+            .load::<Disposition>(&mut *self.conn)?)
+    }
+
+    // Flags and open sanctions entities dismissed as false positives on the same entity, by
+    // any check of a tenant the entity was found for. Flags are dismissed by kind and the open
+    // sanctions entity they were raised from, None for flags raised from the registry. The
+    // latest disposition of each is the one that applies
+    //
+    // Dispositions are only carried over from the entity's appearances in other checks which
+    // share a strong key with it, its canonical entity may have been resolved by name and
+    // month of birth, which different people can share
+    pub fn get_false_positives(
+        &mut self,
+        entity: &Entity,
+    ) -> Result<(HashSet<(Option<String>, Flagkind)>, HashSet<String>), failure::Error> {
+        let mut same_entities = entity::table
+            .filter(entity::id.eq(entity.id))
+            .select(entity::id)
+            .into_boxed();
+        if !entity.company_house_number.is_empty() {
+            same_entities = same_entities.or_filter(
+                entity::company_house_number
+                    .eq(entity.company_house_number.clone())
+                    .and(entity::kind.eq(entity.kind)),
+            );
+        }
+        if let Some(officer_id) = &entity.officer_id {
+            same_entities = same_entities.or_filter(entity::officer_id.eq(officer_id.clone()));
+        }
+        let entity_ids = same_entities.load::<Uuid>(&mut *self.conn)?;
+
+        let tenant_ids = check_entity_map::table
+            .inner_join(check::table.on(check::id.eq(check_entity_map::check_id)))
+            .filter(check_entity_map::entity_id.eq(entity.id))
+            .select(check::tenant_id)
+            .load::<Uuid>(&mut *self.conn)?;
+        let tenant_check_ids = || {
+            check::table
+                .filter(check::tenant_id.eq_any(tenant_ids.clone()))
+                .select(check::id)
+        };
+
+        let flag_verdicts = disposition::table
+            .inner_join(flag::table.on(disposition::flag_id.eq(flag::id.nullable())))
+            .inner_join(flags::table.on(flags::flag_id.eq(flag::id)))
+            .left_join(
+                sanctions_match::table
+                    .on(flag::sanctions_match_id.eq(sanctions_match::id.nullable())),
+            )
+            .filter(flags::entity_id.eq_any(&entity_ids))
+            .filter(disposition::check_id.eq_any(tenant_check_ids()))
+            .order(disposition::created_at.asc())
+            .select((
+                (sanctions_match::open_sanctions_id.nullable(), flag::kind),
+                disposition::verdict,
+            ))
+            .load::<((Option<String>, Flagkind), Verdict)>(&mut *self.conn)?;
+
+        let match_verdicts = disposition::table
+            .inner_join(
+                sanctions_match::table
+                    .on(disposition::sanctions_match_id.eq(sanctions_match::id.nullable())),
+            )
+            .filter(sanctions_match::entity_id.eq_any(&entity_ids))
+            .filter(disposition::check_id.eq_any(tenant_check_ids()))
+            .order(disposition::created_at.asc())
+            .select((sanctions_match::open_sanctions_id, disposition::verdict))
+            .load::<(String, Verdict)>(&mut *self.conn)?;
+
+        Ok((
+            false_positives(flag_verdicts),
+            false_positives(match_verdicts),
+        ))
+    }
+
+    pub fn insert_batch(&mut self, batch: &Batch, rows: &[BatchRow]) -> Result<(), failure::Error> {
+        self.conn.transaction(|conn| {
+            insert_into(batch::table).values(batch).execute(conn)?;
+            // kept under postgres' limit on bind parameters
+            for rows in rows.chunks(1000) {
+                insert_into(batch_row::table).values(rows).execute(conn)?;
+            }
+
+            diesel::result::QueryResult::Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    pub fn get_batch(&mut self, batch_id: Uuid) -> Result<Batch, failure::Error> {
+        let mut query = batch::table.filter(batch::id.eq(batch_id)).into_boxed();
+        if let Some(tenant_id) = self.tenant_id {
+            query = query.filter(batch::tenant_id.eq(tenant_id));
+        }
+
+        Ok(query
+            .first::<Batch>(&mut *self.conn)
+            .optional()?
+            .ok_or(NotFound {
+                kind: "Batch",
+                id: batch_id,
+            })?)
+    }
+
+    pub fn get_batch_row(
+        &mut self,
+        batch_id: Uuid,
+        row_number: i32,
+    ) -> Result<BatchRow, failure::Error> {
+        self.get_batch(batch_id)?;
+
+        Ok(batch_row::table
+            .filter(batch_row::batch_id.eq(batch_id))
+            .filter(batch_row::row_number.eq(row_number))
+            .first::<BatchRow>(&mut *self.conn)?)
+    }
+
+    // Marks the row as started, unless it already has been. Whether it was claimed
+    pub fn claim_batch_row(
+        &mut self,
+        batch_id: Uuid,
+        row_number: i32,
+    ) -> Result<bool, failure::Error> {
+        let claimed = update(batch_row::table)
+            .filter(batch_row::batch_id.eq(batch_id))
+            .filter(batch_row::row_number.eq(row_number))
+            .filter(batch_row::started_at.is_null())
+            .set(batch_row::started_at.eq(Utc::now().naive_utc()))
+            .execute(&mut *self.conn)?;
+
+        Ok(claimed > 0)
+    }
+
+    // Records the check the claimed row was started as, or why it couldn't be
+    pub fn record_batch_row_start(
+        &mut self,
+        batch_id: Uuid,
+        row_number: i32,
+        started: Result<Uuid, String>,
+    ) -> Result<(), failure::Error> {
+        let (check_id, error) = match started {
+            Ok(check_id) => (Some(check_id), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        update(batch_row::table)
+            .filter(batch_row::batch_id.eq(batch_id))
+            .filter(batch_row::row_number.eq(row_number))
+            .set((batch_row::check_id.eq(check_id), batch_row::error.eq(error)))
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    // The batch's rows in order, along with the completion and error of their check's
+    // summary once they've been started
+    pub fn get_batch_rows(
+        &mut self,
+        batch_id: Uuid,
+    ) -> Result<Vec<(BatchRow, Option<NaiveDateTime>, Option<bool>)>, failure::Error> {
+        self.get_batch(batch_id)?;
+
+        Ok(batch_row::table
+            .left_join(
+                check_summary::table.on(batch_row::check_id.eq(check_summary::check_id.nullable())),
+            )
+            .filter(batch_row::batch_id.eq(batch_id))
+            .order(batch_row::row_number.asc())
+            .select((
+                BatchRow::as_select(),
+                check_summary::completed_at.nullable(),
+                check_summary::has_error.nullable(),
+            ))
+            .load::<(BatchRow, Option<NaiveDateTime>, Option<bool>)>(&mut *self.conn)?)
+    }
+
+    // The tenant's checks started from any batch which still have jobs to do, paused checks
+    // aren't counted until they're resumed. Counted from the jobs themselves rather than the
+    // check summaries, which can lag behind
+    pub fn count_running_batch_checks(&mut self, tenant_id: Uuid) -> Result<i64, failure::Error> {
+        let incomplete_jobs = job::table
+            .inner_join(check_job_map::table.on(check_job_map::job_id.eq(job::id)))
+            .filter(check_job_map::check_id.eq(check::id))
+            .filter(job::completed_at.is_null());
+
+        Ok(batch_row::table
+            .inner_join(check::table.on(batch_row::check_id.eq(check::id.nullable())))
+            .filter(check::tenant_id.eq(tenant_id))
+            .filter(check::status.eq(CheckStatus::Running))
+            .filter(diesel::dsl::exists(incomplete_jobs))
+            .count()
+            .get_result::<i64>(&mut *self.conn)?)
+    }
+
+    pub fn insert_tenant(&mut self, name: String) -> Result<Uuid, failure::Error> {
+        let id = Uuid::new_v4();
+        insert_into(tenant::table)
+            .values(Tenant {
+                id,
+                name,
+                created_at: Utc::now().naive_utc(),
+            })
+            .execute(&mut *self.conn)?;
+
+        Ok(id)
+    }
+
+    pub fn insert_tenant_user(
+        &mut self,
+        tenant_id: Uuid,
+        email: String,
+        name: Option<String>,
+    ) -> Result<Uuid, failure::Error> {
+        let id = Uuid::new_v4();
+        insert_into(tenant_user::table)
+            .values(TenantUser {
+                id,
+                tenant_id,
+                email,
+                name,
+                created_at: Utc::now().naive_utc(),
+            })
+            .execute(&mut *self.conn)?;
+
+        Ok(id)
+    }
+
+    pub fn get_tenant_user(&mut self, user_id: Uuid) -> Result<Option<TenantUser>, failure::Error> {
+        Ok(tenant_user::table
+            .filter(tenant_user::id.eq(user_id))
+            .first::<TenantUser>(&mut *self.conn)
+            .optional()?)
+    }
+
+    pub fn insert_api_key(&mut self, api_key: &ApiKey) -> Result<(), failure::Error> {
+        insert_into(api_key::table)
+            .values(api_key)
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+
+    // Finds the unrevoked key with the hash, recording that it's been used unless that was
+    // recorded within the last API_KEY_LAST_USED_SECS
+    pub fn use_api_key(&mut self, key_hash: &str) -> Result<Option<ApiKey>, failure::Error> {
+        let api_key = api_key::table
+            .filter(api_key::key_hash.eq(key_hash))
+            .filter(api_key::revoked_at.is_null())
+            .first::<ApiKey>(&mut *self.conn)
+            .optional()?;
+
+        let now = Utc::now().naive_utc();
+        if let Some(api_key) = &api_key {
+            if is_last_use_stale(api_key.last_used_at, now) {
+                update(api_key::table)
+                    .filter(api_key::id.eq(api_key.id))
+                    .set(api_key::last_used_at.eq(now))
+                    .execute(&mut *self.conn)?;
+            }
+        }
+
+        Ok(api_key)
+    }
+
+    pub fn revoke_api_key(&mut self, api_key_id: Uuid) -> Result<(), failure::Error> {
+        update(api_key::table)
+            .filter(api_key::id.eq(api_key_id))
+            .set(api_key::revoked_at.eq(Utc::now().naive_utc()))
+            .execute(&mut *self.conn)?;
+
+        Ok(())
+    }
+}
+
+// Checks started by the tenant
+fn tenant_check_ids(
+    tenant_id: Uuid,
+) -> check::BoxedQuery<'static, diesel::pg::Pg, diesel::sql_types::Uuid> {
+    check::table
+        .filter(check::tenant_id.eq(tenant_id))
+        .select(check::id)
+        .into_boxed()
+}
+
+// What the latest verdict was a false positive for, of verdicts ordered oldest first
+fn false_positives<T: Eq + std::hash::Hash>(verdicts: Vec<(T, Verdict)>) -> HashSet<T> {
+    verdicts
+        .into_iter()
+        .collect::<HashMap<T, Verdict>>()
+        .into_iter()
+        .filter(|(_, verdict)| *verdict == Verdict::FalsePositive)
+        .map(|(target, _)| target)
+        .collect()
+}
+
+// Entities found by any of the tenant's checks
+fn tenant_entity_ids(
+    tenant_id: Uuid,
+) -> check_entity_map::BoxedQuery<'static, diesel::pg::Pg, diesel::sql_types::Uuid> {
+    check_entity_map::table
+        .filter(check_entity_map::check_id.eq_any(tenant_check_ids(tenant_id)))
+        .select(check_entity_map::entity_id)
+        .into_boxed()
+}
+
+// Escapes the characters LIKE patterns treat specially, so text is matched as it is
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn is_last_use_stale(last_used_at: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+    last_used_at
+        .is_none_or(|last_used_at| now - last_used_at >= TimeDelta::seconds(API_KEY_LAST_USED_SECS))
+}
+
+fn is_summary_stale(updated_at: NaiveDateTime, now: NaiveDateTime) -> bool {
+    now - updated_at >= TimeDelta::seconds(CHECK_SUMMARY_REFRESH_SECS)
+}
+
+// What to do with a relation job found for an (entity, kind), given its row on the frontier
+#[derive(Debug, PartialEq)]
+enum FrontierAction {
+    // not on the frontier yet, or already fetched with less depth left, in which case it's
+    // fetched again to expand the entity's relations further
+    Queue,
+    // still pending with less depth left, the pending job picks up the raised depth
+    RaiseDepth,
+    Skip,
+}
+
+fn frontier_action(existing: Option<&CheckFrontier>, remaining_depth: i32) -> FrontierAction {
+    match existing {
+        None => FrontierAction::Queue,
+        Some(existing) if existing.remaining_depth >= remaining_depth => FrontierAction::Skip,
+        Some(existing) if existing.fetched_at.is_some() => FrontierAction::Queue,
+        Some(_) => FrontierAction::RaiseDepth,
+    }
+}
+
+fn visit_depth(existing: Option<&CheckFrontier>, remaining_depth: i32) -> Option<i32> {
+    match existing {
+        None => Some(remaining_depth),
+        Some(existing) if existing.fetched_at.is_none() => {
+            Some(existing.remaining_depth.max(remaining_depth))
+        }
+        Some(existing) if existing.remaining_depth < remaining_depth => Some(remaining_depth),
+        Some(_) => None,
+    }
+}
+
+// Finds the canonical entity matching any of the entity's keys, creating one if none
+// match, and records any keys it wasn't known by yet. Keys are checked strongest first
+// and never reassigned, so a weak key can't merge two already distinct entities. Must be
+// run in a transaction
+fn resolve_canonical_entity(
+    conn: &mut PgConnection,
+    entity: &Entity,
+) -> diesel::result::QueryResult<Uuid> {
+    let keys = entity.canonical_keys();
+
+    let mut canonical_entity_id = None;
+    for (key_kind, key_value) in &keys {
+        canonical_entity_id = canonical_entity_key::table
+            .filter(canonical_entity_key::key_kind.eq(key_kind))
+            .filter(canonical_entity_key::key_value.eq(key_value))
+            .select(canonical_entity_key::canonical_entity_id)
+            .first::<Uuid>(conn)
+            .optional()?;
+
+        if canonical_entity_id.is_some() {
+            break;
+        }
+    }
+
+    let canonical_entity_id = match canonical_entity_id {
+        Some(canonical_entity_id) => {
+            update(canonical_entity::table)
+                .filter(canonical_entity::id.eq(canonical_entity_id))
+                .filter(canonical_entity::name.is_null())
+                .set(canonical_entity::name.eq(&entity.name))
+                .execute(conn)?;
+
+            canonical_entity_id
+        }
+        None => {
+            let canonical_entity = CanonicalEntity {
+                id: Uuid::new_v4(),
+                kind: entity.kind,
+                name: entity.name.clone(),
+                created_at: Utc::now().naive_utc(),
+            };
+            insert_into(canonical_entity::table)
+                .values(&canonical_entity)
+                .execute(conn)?;
+
+            // another resolver may have created one for the same strongest key meanwhile,
+            // inserting the key waits for it to commit, and its canonical entity is taken
+            // instead of leaving two
+            match keys.first() {
+                Some((key_kind, key_value)) => {
+                    insert_into(canonical_entity_key::table)
+                        .values(&CanonicalEntityKey {
+                            key_kind: key_kind.to_string(),
+                            key_value: key_value.clone(),
+                            canonical_entity_id: canonical_entity.id,
+                        })
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                    let canonical_entity_id = canonical_entity_key::table
+                        .filter(canonical_entity_key::key_kind.eq(key_kind))
+                        .filter(canonical_entity_key::key_value.eq(key_value))
+                        .select(canonical_entity_key::canonical_entity_id)
+                        .first::<Uuid>(conn)?;
+
+                    if canonical_entity_id != canonical_entity.id {
+                        diesel::delete(
+                            canonical_entity::table
+                                .filter(canonical_entity::id.eq(canonical_entity.id)),
+                        )
+                        .execute(conn)?;
+                    }
+                    canonical_entity_id
+                }
+                None => canonical_entity.id,
+            }
+        }
+    };
+
+    let keys: Vec<CanonicalEntityKey> = keys
+        .into_iter()
+        .map(|(key_kind, key_value)| CanonicalEntityKey {
+            key_kind: key_kind.to_string(),
+            key_value,
+            canonical_entity_id,
+        })
+        .collect();
+    if !keys.is_empty() {
+        insert_into(canonical_entity_key::table)
+            .values(&keys)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+    }
+
+    Ok(canonical_entity_id)
+}
+
+// Relationships are unique per parent, child and kind, re-inserting one updates
+// its dates, and its provenance, to the latest seen
+fn upsert_relationship(
+    relationship: &Relationship,
+    source_fetch_id: Option<Uuid>,
+) -> impl RunQueryDsl<PgConnection> + ExecuteDsl<PgConnection> + QueryFragment<Pg> + '_ {
+    insert_into(relationship::table)
+        .values((
+            relationship,
+            relationship::source_fetch_id.eq(source_fetch_id),
+        ))
+        .on_conflict((
+            relationship::parent_id,
+            relationship::child_id,
+            relationship::kind,
+        ))
+        .do_update()
+        .set((
+            relationship::started_on.eq(excluded(relationship::started_on)),
+            relationship::ended_on.eq(excluded(relationship::ended_on)),
+            relationship::ownership_band.eq(excluded(relationship::ownership_band)),
+            relationship::source_fetch_id.eq(excluded(relationship::source_fetch_id)),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+
+    fn frontier_row(remaining_depth: i32, fetched: bool) -> CheckFrontier {
+        CheckFrontier {
+            check_id: Uuid::new_v4(),
+            entity_id: Uuid::new_v4(),
+            relation_job_kind: "Shareholders".to_string(),
+            remaining_depth,
+            enqueued_at: NaiveDateTime::default(),
+            fetched_at: fetched.then(NaiveDateTime::default),
+        }
+    }
+
+    #[test]
+    fn relationships_are_upserted_on_parent_child_and_kind() {
+        let relationship = Relationship {
+            parent_id: Uuid::new_v4(),
+            child_id: Uuid::new_v4(),
+            kind: Relationshipkind::Officer,
+            started_on: None,
+            ended_on: None,
+            ownership_band: None,
+        };
+        let query = upsert_relationship(&relationship, None);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+
+        // found again, e.g. from the other side, the relationship's dates are updated
+        // rather than it being recorded twice
+        assert!(sql.contains(
+            r#"ON CONFLICT ("parent_id", "child_id", "kind") DO UPDATE SET "started_on" = excluded."started_on", "ended_on" = excluded."ended_on""#
+        ));
+    }
+
+    #[test]
+    fn shorter_paths_expand_entities_further() {
+        assert_eq!(frontier_action(None, 1), FrontierAction::Queue);
+        assert_eq!(
+            frontier_action(Some(&frontier_row(2, false)), 1),
+            FrontierAction::Skip
+        );
+        assert_eq!(
+            frontier_action(Some(&frontier_row(1, false)), 2),
+            FrontierAction::RaiseDepth
+        );
+        assert_eq!(
+            frontier_action(Some(&frontier_row(1, true)), 1),
+            FrontierAction::Skip
+        );
+        // fetched, but found again with more depth left
+        assert_eq!(
+            frontier_action(Some(&frontier_row(1, true)), 2),
+            FrontierAction::Queue
+        );
+    }
+
+    #[test]
+    fn pending_jobs_are_visited_until_fetched() {
+        assert_eq!(visit_depth(None, 1), Some(1));
+        // a redelivered job whose fetch failed runs again
+        assert_eq!(visit_depth(Some(&frontier_row(1, false)), 1), Some(1));
+        assert_eq!(visit_depth(Some(&frontier_row(2, false)), 1), Some(2));
+        assert_eq!(visit_depth(Some(&frontier_row(1, true)), 1), None);
+        assert_eq!(visit_depth(Some(&frontier_row(1, true)), 2), Some(2));
+    }
+
+    #[test]
+    fn like_patterns_match_text_as_it_is() {
+        assert_eq!(escape_like("Acme Ltd"), "Acme Ltd");
+        assert_eq!(escape_like("100%_a\\b"), "100\\%\\_a\\\\b");
+    }
+
+    #[test]
+    fn api_key_use_is_recorded_at_most_once_per_interval() {
+        let now = Utc::now().naive_utc();
+
+        assert!(is_last_use_stale(None, now));
+        assert!(!is_last_use_stale(Some(now - TimeDelta::seconds(1)), now));
+        assert!(is_last_use_stale(
+            Some(now - TimeDelta::seconds(API_KEY_LAST_USED_SECS)),
+            now
+        ));
+    }
+
+    #[test]
+    fn summaries_are_refreshed_at_most_once_per_interval() {
+        let now = Utc::now().naive_utc();
+
+        assert!(!is_summary_stale(now - TimeDelta::seconds(1), now));
+        assert!(is_summary_stale(
+            now - TimeDelta::seconds(CHECK_SUMMARY_REFRESH_SECS),
+            now
+        ));
+    }
+
+    #[test]
+    fn latest_verdict_applies() {
+        let verdicts = vec![
+            (Flagkind::Crime, Verdict::FalsePositive),
+            (Flagkind::Fraud, Verdict::FalsePositive),
+            (Flagkind::Crime, Verdict::TrueMatch),
+            (Flagkind::Wanted, Verdict::TrueMatch),
+            (Flagkind::Wanted, Verdict::FalsePositive),
+        ];
+
+        assert_eq!(
+            false_positives(verdicts),
+            HashSet::from([Flagkind::Fraud, Flagkind::Wanted])
+        );
+    }
+
+    #[test]
+    fn flags_are_dismissed_per_sanctions_entity() {
+        let verdicts = vec![
+            (
+                (Some("Q1".to_string()), Flagkind::Crime),
+                Verdict::FalsePositive,
+            ),
+            ((None, Flagkind::Disqualified), Verdict::FalsePositive),
+        ];
+        let dismissed = false_positives(verdicts);
+
+        assert!(dismissed.contains(&(Some("Q1".to_string()), Flagkind::Crime)));
+        // the same kind raised from another open sanctions entity is still raised
+        assert!(!dismissed.contains(&(Some("Q2".to_string()), Flagkind::Crime)));
+        assert!(!dismissed.contains(&(None, Flagkind::Crime)));
+        assert!(dismissed.contains(&(None, Flagkind::Disqualified)));
     }
 }
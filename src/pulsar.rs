@@ -1,4 +1,4 @@
-use std::num::NonZero;
+use std::{collections::HashMap, num::NonZero, time::Duration};
 
 use governor::{
     clock::{QuantaClock, QuantaInstant},
@@ -15,12 +15,35 @@ use uuid::Uuid;
 
 use crate::{
     jobs::jobs::{Job, JobKind},
+    models::Priority,
     postgres::Database,
 };
 
 const PULSAR_ADDR: &str = "pulsar://localhost:6650";
 const MAX_JOB_RETRY: usize = 3;
 
+// Records a job so it can be sent, see PulsarProducer::send_job. Jobs of a check are queued
+// at the check's priority
+pub fn record_job(
+    database: &mut Database,
+    check_id: Option<Uuid>,
+    job_kind: JobKind,
+) -> Result<Job, failure::Error> {
+    let (id, priority) = match check_id {
+        Some(check_id) => (
+            database.add_job_with_check(check_id)?,
+            database.get_check_priority(&check_id)?,
+        ),
+        None => (database.add_job()?, job_kind.default_priority()),
+    };
+
+    Ok(Job {
+        id,
+        job_kind,
+        priority,
+    })
+}
+
 pub struct PulsarClient {
     internal_client: Pulsar<TokioExecutor>,
 }
@@ -35,6 +58,9 @@ impl PulsarClient {
         }
     }
 
+    // Jobs are sent to the topic of their priority, see Priority::topic. The rate limit is
+    // for all priorities together, so a priority with nothing queued leaves its share to the
+    // others. Workers weight the priorities as they take jobs, see Worker::next_message
     pub async fn create_producer(
         &self,
         topic: &str,
@@ -43,6 +69,28 @@ impl PulsarClient {
     ) -> PulsarProducer {
         let id = Uuid::new_v4();
 
+        let mut internal_producers = HashMap::new();
+        for priority in Priority::ALL {
+            internal_producers.insert(
+                priority,
+                self.internal_client
+                    .producer()
+                    .with_topic(priority.topic(topic))
+                    .with_name(format!("PRODUCER_{}_{}", id, priority.as_str()))
+                    .with_options(producer::ProducerOptions {
+                        schema: Some(proto::Schema {
+                            r#type: proto::schema::Type::String as i32, // Or appropriate type for Job
+                            ..Default::default()
+                        }),
+
+                        ..Default::default()
+                    })
+                    .build()
+                    .await
+                    .expect("Should be able to create producer"),
+            );
+        }
+
         // TODO: This will limit each worker's producer to x per min, in reality we want all workers' producers
         // to be limited to x per min, i.e each producer limited to x / n per min, where n is number of workers
         // TODO: This could be done via an env var which describes num of workers
@@ -59,22 +107,7 @@ impl PulsarClient {
 
         PulsarProducer {
             id,
-            internal_producer: self
-                .internal_client
-                .producer()
-                .with_topic(topic)
-                .with_name("PRODUCER_".to_owned() + &id.to_string())
-                .with_options(producer::ProducerOptions {
-                    schema: Some(proto::Schema {
-                        r#type: proto::schema::Type::String as i32, // Or appropriate type for Job
-                        ..Default::default()
-                    }),
-
-                    ..Default::default()
-                })
-                .build()
-                .await
-                .expect("Should be able to create producer"),
+            internal_producers,
             rate_limiter,
             max_jobs_per_check,
         }
@@ -82,7 +115,7 @@ impl PulsarClient {
 
     pub async fn create_consumer(
         &self,
-        topics: Vec<&str>,
+        topics: Vec<String>,
         subscription_type: SubType,
         subscription: &str,
     ) -> PulsarConsumer {
@@ -111,7 +144,7 @@ impl PulsarClient {
 
 pub struct PulsarProducer {
     id: Uuid,
-    internal_producer: Producer<TokioExecutor>,
+    internal_producers: HashMap<Priority, Producer<TokioExecutor>>,
     rate_limiter:
         Option<RateLimiter<NotKeyed, InMemoryState, QuantaClock, NoOpMiddleware<QuantaInstant>>>,
     max_jobs_per_check: Option<usize>,
@@ -119,43 +152,63 @@ pub struct PulsarProducer {
 
 impl PulsarProducer {
     async fn produce_message(&mut self, job: Job) -> Result<(), failure::Error> {
-        self.internal_producer.send(job).await?;
+        self.internal_producers
+            .get_mut(&job.priority)
+            .expect("There should be a producer for every priority")
+            .send(job)
+            .await?;
         Ok(())
     }
 
+    // Sends a job that's already been recorded, by record_job or when it was first sent, such
+    // as one parked while its check was paused
+    pub async fn send_job(&mut self, job: Job) -> Result<(), failure::Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.until_ready().await;
+        }
+
+        self.produce_message(job).await
+    }
+
+    // Sends a recorded job to be delivered once the delay has passed. Only subscriptions
+    // of the shared type wait for it, others are given the job straight away
+    pub async fn send_job_after(
+        &mut self,
+        job: Job,
+        delay: Duration,
+    ) -> Result<(), failure::Error> {
+        self.internal_producers
+            .get_mut(&job.priority)
+            .expect("There should be a producer for every priority")
+            .create_message()
+            .with_content(job)
+            .delay(delay)?
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    // Whether the job was enqueued, jobs past the check's job limit are dropped
     pub async fn enqueue_job(
         &mut self,
         database: &mut Database,
         check_id: Option<Uuid>,
         job_kind: JobKind,
-    ) -> Result<(), failure::Error> {
+    ) -> Result<bool, failure::Error> {
         // if max jobs per check reached, gracefully terminate
         // TODO: this is a bit inefficient was we are executing sql query each
         // time we enqueue rather than per job, but fine for now
-        if let Some(rate_limiter) = &self.rate_limiter {
-            rate_limiter.until_ready().await;
-        }
-
-        let job_id = match check_id {
-            Some(check_id) => {
-                if let Some(max_jobs) = self.max_jobs_per_check {
-                    if database.get_num_of_jobs(&check_id)? >= max_jobs {
-                        println!("Check with ID: {:?} reached job limit", check_id);
-                        info!("Check with ID: {:?} reached job limit", check_id);
-                        return Ok(());
-                    }
-                }
-                database.add_job_with_check(check_id)?
+        if let (Some(check_id), Some(max_jobs)) = (check_id, self.max_jobs_per_check) {
+            if database.get_num_of_jobs(&check_id)? >= max_jobs {
+                println!("Check with ID: {:?} reached job limit", check_id);
+                info!("Check with ID: {:?} reached job limit", check_id);
+                return Ok(false);
             }
-            None => database.add_job()?,
-        };
+        }
 
-        self.produce_message(Job {
-            id: job_id,
-            job_kind,
-        })
-        .await?;
-        Ok(())
+        let job = record_job(database, check_id, job_kind)?;
+        self.send_job(job).await?;
+        Ok(true)
     }
 }
 
@@ -0,0 +1,79 @@
+use chrono::{NaiveDateTime, Utc};
+use serde::de::DeserializeOwned;
+
+use crate::report::content_hash;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataSource {
+    CompaniesHouse,
+    OpenSanctions,
+}
+
+impl DataSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSource::CompaniesHouse => "companies_house",
+            DataSource::OpenSanctions => "open_sanctions",
+        }
+    }
+}
+
+// A response along with the payload it was parsed from, so what's recorded from it can
+// be traced back to exactly what the source returned
+pub struct Fetched<T> {
+    pub response: T,
+    pub source: DataSource,
+    // the path and query the response was fetched from, without credentials
+    pub endpoint: String,
+    pub fetched_at: NaiveDateTime,
+    // hex encoded sha256 of the body as received
+    pub response_hash: String,
+    // the body as received, archived so it can be checked against its hash
+    pub body: Vec<u8>,
+    pub payload: serde_json::Value,
+}
+
+impl<T: DeserializeOwned> Fetched<T> {
+    pub fn parse(
+        source: DataSource,
+        endpoint: String,
+        body: &[u8],
+    ) -> Result<Self, failure::Error> {
+        let payload: serde_json::Value = serde_json::from_slice(body)?;
+
+        Ok(Self {
+            response: serde_json::from_value(payload.clone())?,
+            source,
+            endpoint,
+            fetched_at: Utc::now().naive_utc(),
+            response_hash: content_hash(body),
+            body: body.to_vec(),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Officer {
+        name: String,
+    }
+
+    #[test]
+    fn keeps_fields_the_response_drops() {
+        let body = br#"{"name": "JANE DOE", "etag": "abc"}"#;
+        let fetched =
+            Fetched::<Officer>::parse(DataSource::CompaniesHouse, "/officers".to_string(), body)
+                .unwrap();
+
+        assert_eq!(fetched.response.name, "JANE DOE");
+        assert_eq!(fetched.payload["etag"], "abc");
+        assert_eq!(fetched.response_hash, content_hash(body));
+        // whitespace and key order are kept, so the archived body hashes the same
+        assert_eq!(content_hash(&fetched.body), fetched.response_hash);
+    }
+}
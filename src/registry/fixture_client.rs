@@ -0,0 +1,448 @@
+use std::path::{Component, Path, PathBuf};
+
+use failure::format_err;
+use serde::de::DeserializeOwned;
+
+use crate::company_house::{
+    company_house_streaming_types::CompanyData,
+    company_house_types::{
+        AppointmentsResponse, ChargeList, CompanyInsolvency, CompanySearchResponse,
+        DisqualifiedOfficer, DisqualifiedOfficerSearch, FilingHistoryResponse, OfficerListResponse,
+        ShareholderList,
+    },
+};
+
+use super::{
+    fetched::{DataSource, Fetched},
+    registry_client::RegistryClient,
+};
+
+// When set, workers serve registry data from this directory instead of the live api
+pub const REGISTRY_FIXTURE_DIR: &str = "REGISTRY_FIXTURE_DIR";
+// When set, workers record every live registry response into this directory
+pub const REGISTRY_RECORD_DIR: &str = "REGISTRY_RECORD_DIR";
+
+// Fixtures are laid out on disk mirroring the api path of the resource they were recorded from,
+// e.g. <root>/company/00000001/officers.json. A resource the api didn't have is recorded as null.
+// Searched names are hex encoded, names such as "A/S" aren't valid file names
+
+fn search_path(name: &str) -> String {
+    format!("search/companies/{}.json", hex_encode(name))
+}
+
+fn hex_encode(name: &str) -> String {
+    name.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Paths are built from registry responses, so are checked to stay within the fixture directory
+fn fixture_file(root: &Path, path: &str) -> Result<PathBuf, failure::Error> {
+    let is_relative = Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !is_relative {
+        return Err(format_err!(
+            "Fixture path {:?} leaves the fixture directory",
+            path
+        ));
+    }
+
+    Ok(root.join(path))
+}
+
+fn company_path(company_number: &str, resource: &str) -> String {
+    format!("company/{}/{}.json", company_number, resource)
+}
+
+fn appointments_path(officer_id: &Option<String>) -> Result<String, failure::Error> {
+    match officer_id {
+        Some(officer_id) => Ok(format!("officers/{}/appointments.json", officer_id)),
+        None => Err(format_err!("Officer id doesn't exist")),
+    }
+}
+
+fn disqualified_officer_search_path(name: &str) -> String {
+    format!("search/disqualified-officers/{}.json", hex_encode(name))
+}
+
+// links are api paths, e.g. /disqualified-officers/natural/{id}
+fn disqualifications_path(disqualified_officer_link: &str) -> String {
+    format!("{}.json", disqualified_officer_link.trim_start_matches('/'))
+}
+
+// Serves previously recorded registry responses from disk, allows a whole check
+// to be replayed offline without an api key
+pub struct FixtureRegistryClient {
+    root: PathBuf,
+}
+
+impl FixtureRegistryClient {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // fixtures are recorded from Companies House, so are archived as its responses
+    async fn read<T: DeserializeOwned>(
+        &self,
+        path: String,
+    ) -> Result<Option<Fetched<T>>, failure::Error> {
+        let file = fixture_file(&self.root, &path)?;
+        let bytes = tokio::fs::read(&file)
+            .await
+            .map_err(|e| format_err!("No fixture recorded at {:?}, error: {}", file, e))?;
+        if serde_json::from_slice::<serde_json::Value>(&bytes)?.is_null() {
+            return Ok(None);
+        }
+
+        Fetched::parse(DataSource::CompaniesHouse, path, &bytes).map(Some)
+    }
+}
+
+impl RegistryClient for FixtureRegistryClient {
+    async fn get_company(
+        &self,
+        name: &str,
+    ) -> Result<Option<Fetched<CompanySearchResponse>>, failure::Error> {
+        self.read(search_path(name)).await
+    }
+
+    async fn get_company_profile(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyData>>, failure::Error> {
+        self.read(company_path(company_number, "profile")).await
+    }
+
+    async fn get_officers(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<OfficerListResponse>>, failure::Error> {
+        self.read(company_path(company_number, "officers")).await
+    }
+
+    async fn get_shareholders(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<ShareholderList>>, failure::Error> {
+        self.read(company_path(
+            company_number,
+            "persons-with-significant-control",
+        ))
+        .await
+    }
+
+    async fn get_appointments(
+        &self,
+        officer_id: &Option<String>,
+    ) -> Result<Option<Fetched<AppointmentsResponse>>, failure::Error> {
+        self.read(appointments_path(officer_id)?).await
+    }
+
+    async fn get_filing_history(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<FilingHistoryResponse>>, failure::Error> {
+        self.read(company_path(company_number, "filing-history"))
+            .await
+    }
+
+    async fn get_charges(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<ChargeList>>, failure::Error> {
+        self.read(company_path(company_number, "charges")).await
+    }
+
+    async fn get_insolvency(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyInsolvency>>, failure::Error> {
+        self.read(company_path(company_number, "insolvency")).await
+    }
+
+    async fn search_disqualified_officers(
+        &self,
+        name: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficerSearch>>, failure::Error> {
+        self.read(disqualified_officer_search_path(name)).await
+    }
+
+    async fn get_disqualifications(
+        &self,
+        disqualified_officer_link: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficer>>, failure::Error> {
+        self.read(disqualifications_path(disqualified_officer_link))
+            .await
+    }
+}
+
+// Wraps another registry client and writes every response it serves to disk,
+// in the layout expected by FixtureRegistryClient
+pub struct RecordingRegistryClient<C: RegistryClient> {
+    inner: C,
+    root: PathBuf,
+}
+
+impl<C: RegistryClient> RecordingRegistryClient<C> {
+    pub fn new(inner: C, root: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            root: root.into(),
+        }
+    }
+
+    // the payload is recorded as received, including fields the response types drop
+    async fn record<T: Send>(
+        &self,
+        path: String,
+        response: Option<Fetched<T>>,
+    ) -> Result<Option<Fetched<T>>, failure::Error> {
+        let file = fixture_file(&self.root, &path)?;
+        if let Some(parent) = file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let payload = response.as_ref().map(|response| &response.payload);
+        tokio::fs::write(&file, serde_json::to_vec_pretty(&payload)?).await?;
+
+        Ok(response)
+    }
+}
+
+impl<C: RegistryClient> RegistryClient for RecordingRegistryClient<C> {
+    async fn get_company(
+        &self,
+        name: &str,
+    ) -> Result<Option<Fetched<CompanySearchResponse>>, failure::Error> {
+        let response = self.inner.get_company(name).await?;
+        self.record(search_path(name), response).await
+    }
+
+    async fn get_company_profile(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyData>>, failure::Error> {
+        let response = self.inner.get_company_profile(company_number).await?;
+        self.record(company_path(company_number, "profile"), response)
+            .await
+    }
+
+    async fn get_officers(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<OfficerListResponse>>, failure::Error> {
+        let response = self.inner.get_officers(company_number).await?;
+        self.record(company_path(company_number, "officers"), response)
+            .await
+    }
+
+    async fn get_shareholders(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<ShareholderList>>, failure::Error> {
+        let response = self.inner.get_shareholders(company_number).await?;
+        self.record(
+            company_path(company_number, "persons-with-significant-control"),
+            response,
+        )
+        .await
+    }
+
+    async fn get_appointments(
+        &self,
+        officer_id: &Option<String>,
+    ) -> Result<Option<Fetched<AppointmentsResponse>>, failure::Error> {
+        let response = self.inner.get_appointments(officer_id).await?;
+        self.record(appointments_path(officer_id)?, response).await
+    }
+
+    async fn get_filing_history(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<FilingHistoryResponse>>, failure::Error> {
+        let response = self.inner.get_filing_history(company_number).await?;
+        self.record(company_path(company_number, "filing-history"), response)
+            .await
+    }
+
+    async fn get_charges(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<ChargeList>>, failure::Error> {
+        let response = self.inner.get_charges(company_number).await?;
+        self.record(company_path(company_number, "charges"), response)
+            .await
+    }
+
+    async fn get_insolvency(
+        &self,
+        company_number: &str,
+    ) -> Result<Option<Fetched<CompanyInsolvency>>, failure::Error> {
+        let response = self.inner.get_insolvency(company_number).await?;
+        self.record(company_path(company_number, "insolvency"), response)
+            .await
+    }
+
+    async fn search_disqualified_officers(
+        &self,
+        name: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficerSearch>>, failure::Error> {
+        let response = self.inner.search_disqualified_officers(name).await?;
+        self.record(disqualified_officer_search_path(name), response)
+            .await
+    }
+
+    async fn get_disqualifications(
+        &self,
+        disqualified_officer_link: &str,
+    ) -> Result<Option<Fetched<DisqualifiedOfficer>>, failure::Error> {
+        let response = self
+            .inner
+            .get_disqualifications(disqualified_officer_link)
+            .await?;
+        self.record(disqualifications_path(disqualified_officer_link), response)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CompanyProfile, Disqualification, EntityDetails, EntityRelation, Entitykind, PartialDate,
+        PreviousName, LENDER_NAME_KEY,
+    };
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    fn fixture_client() -> FixtureRegistryClient {
+        FixtureRegistryClient::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/registry"))
+    }
+
+    #[tokio::test]
+    async fn can_replay_officers() {
+        let officers = fixture_client()
+            .get_officers("00000001")
+            .await
+            .unwrap()
+            .unwrap();
+        let entity_relations: Vec<EntityRelation> = officers.response.into();
+
+        assert_eq!(entity_relations.len(), 2);
+        assert_eq!(entity_relations[0].entity.kind, Entitykind::Individual);
+        assert_eq!(
+            entity_relations[0].entity.officer_id,
+            Some("Abc123OfficerId".to_string())
+        );
+        assert_eq!(
+            entity_relations[0].entity.partial_date_of_origin(),
+            PartialDate::new(Some(1970), Some(6), None)
+        );
+    }
+
+    #[tokio::test]
+    async fn can_replay_appointments() {
+        let appointments = fixture_client()
+            .get_appointments(&Some("Abc123OfficerId".to_string()))
+            .await
+            .unwrap()
+            .unwrap();
+        let entity_relations: Vec<EntityRelation> = appointments.response.into();
+
+        assert_eq!(entity_relations.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn can_replay_charges() {
+        let charges = fixture_client()
+            .get_charges("00000001")
+            .await
+            .unwrap()
+            .unwrap();
+        let entity_relations: Vec<EntityRelation> = charges.response.into();
+
+        assert_eq!(entity_relations.len(), 1);
+        assert_eq!(
+            entity_relations[0].entity.canonical_keys(),
+            vec![(LENDER_NAME_KEY, "BANK EXAMPLE PLC".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn can_replay_disqualifications() {
+        let disqualified_officer = fixture_client()
+            .get_disqualifications("/disqualified-officers/natural/Dq456Natural")
+            .await
+            .unwrap()
+            .unwrap();
+        let disqualifications = Disqualification::from_disqualified_officer(
+            Uuid::new_v4(),
+            &disqualified_officer.response,
+        );
+
+        assert_eq!(disqualifications.len(), 1);
+        assert_eq!(disqualifications[0].section, Some("7".to_string()));
+    }
+
+    #[tokio::test]
+    async fn can_replay_company_profile() {
+        let company_data = fixture_client()
+            .get_company_profile("00000001")
+            .await
+            .unwrap()
+            .unwrap()
+            .response;
+        let entity_id = Uuid::new_v4();
+        let profile = CompanyProfile::from((entity_id, &company_data));
+        let previous_names = PreviousName::from_company_data(entity_id, &company_data);
+        let details = EntityDetails::from(&company_data);
+
+        assert_eq!(profile.entity_id, entity_id);
+        assert_eq!(profile.company_status, Some("active".to_string()));
+        assert_eq!(profile.company_type, Some("ltd".to_string()));
+        assert_eq!(profile.sic_codes, vec!["64209".to_string()]);
+        assert_eq!(
+            profile.date_of_creation,
+            NaiveDate::from_ymd_opt(2010, 3, 4)
+        );
+        assert_eq!(profile.date_of_cessation, None);
+        assert_eq!(profile.address_line_1, Some("1 Example Street".to_string()));
+        assert_eq!(profile.postal_code, Some("EC1A 1AA".to_string()));
+        assert_eq!(profile.has_charges, Some(true));
+        assert_eq!(profile.has_insolvency_history, Some(false));
+
+        assert_eq!(previous_names.len(), 1);
+        assert_eq!(previous_names[0].name, "EXAMPLE TRADING LIMITED");
+        assert_eq!(
+            previous_names[0].ceased_on,
+            NaiveDate::from_ymd_opt(2015, 8, 20)
+        );
+
+        assert_eq!(details.name, Some("EXAMPLE HOLDINGS LIMITED".to_string()));
+        assert_eq!(details.date_of_origin, NaiveDate::from_ymd_opt(2010, 3, 4));
+    }
+
+    #[tokio::test]
+    async fn resource_recorded_as_not_found_is_none() {
+        let charges = fixture_client().get_charges("00000002").await.unwrap();
+
+        assert!(charges.is_none());
+    }
+
+    #[test]
+    fn fixture_paths_stay_within_the_fixture_directory() {
+        let root = Path::new("fixtures");
+
+        assert_eq!(
+            fixture_file(root, &search_path("A/S")).unwrap(),
+            root.join("search/companies/412f53.json")
+        );
+        assert!(fixture_file(root, &search_path("../../etc")).is_ok());
+        assert!(fixture_file(root, &disqualifications_path("/../../etc/passwd")).is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_is_an_error() {
+        let result = fixture_client().get_officers("99999999").await;
+
+        assert!(result.is_err());
+    }
+}
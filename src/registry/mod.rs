@@ -0,0 +1,3 @@
+pub mod fetched;
+pub mod fixture_client;
+pub mod registry_client;
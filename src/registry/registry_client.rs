@@ -0,0 +1,71 @@
+use std::future::Future;
+
+use super::fetched::Fetched;
+
+use crate::company_house::{
+    company_house_streaming_types::CompanyData,
+    company_house_types::{
+        AppointmentsResponse, ChargeList, CompanyInsolvency, CompanySearchResponse,
+        DisqualifiedOfficer, DisqualifiedOfficerSearch, FilingHistoryResponse, OfficerListResponse,
+        ShareholderList,
+    },
+};
+
+// Source of company registry data used by relation and risk jobs
+//
+// Responses are shaped like the Companies House api, which is the canonical
+// implementation, other backends (i.e. recorded fixtures) must serve the same shape.
+// Every response comes with the payload it was parsed from, for archiving. A resource the
+// registry doesn't have, e.g. a company without charges, is None
+pub trait RegistryClient: Send + Sync {
+    fn get_company(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<CompanySearchResponse>>, failure::Error>> + Send;
+
+    fn get_company_profile(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<CompanyData>>, failure::Error>> + Send;
+
+    fn get_officers(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<OfficerListResponse>>, failure::Error>> + Send;
+
+    fn get_shareholders(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<ShareholderList>>, failure::Error>> + Send;
+
+    fn get_appointments(
+        &self,
+        officer_id: &Option<String>,
+    ) -> impl Future<Output = Result<Option<Fetched<AppointmentsResponse>>, failure::Error>> + Send;
+
+    fn get_filing_history(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<FilingHistoryResponse>>, failure::Error>> + Send;
+
+    fn get_charges(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<ChargeList>>, failure::Error>> + Send;
+
+    fn get_insolvency(
+        &self,
+        company_number: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<CompanyInsolvency>>, failure::Error>> + Send;
+
+    fn search_disqualified_officers(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<DisqualifiedOfficerSearch>>, failure::Error>> + Send;
+
+    // by the disqualified officer's link from a search
+    fn get_disqualifications(
+        &self,
+        disqualified_officer_link: &str,
+    ) -> impl Future<Output = Result<Option<Fetched<DisqualifiedOfficer>>, failure::Error>> + Send;
+}
@@ -0,0 +1,64 @@
+use std::fmt::Write;
+
+use super::ScreeningReport;
+use crate::graph::export::escape_xml;
+
+const STYLE: &str = "body { font-family: Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; }
+h2 { font-size: 1.2em; margin-top: 1.6em; border-bottom: 1px solid #ccc; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 4px 8px; border-bottom: 1px solid #eee; white-space: pre-wrap; }
+th { background: #f4f4f4; }
+.meta { color: #666; }";
+
+// A single page with no external resources, so the stored file is the whole report
+pub fn to_html(report: &ScreeningReport) -> String {
+    let title = escape_xml(&report.title());
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", title);
+    let _ = writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(out, "<h1>{}</h1>", title);
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\">Check {} started {}, completed {}. Report generated {} UTC.</p>",
+        report.check_id,
+        report.check.started_at,
+        report
+            .check
+            .completed_at
+            .map(|completed_at| completed_at.to_string())
+            .unwrap_or_else(|| "not yet".to_string()),
+        report.generated_at
+    );
+    let _ = writeln!(
+        out,
+        "<p><strong>Risk level: {}</strong> (score {:.1})</p>",
+        report.risk.level, report.risk.score
+    );
+
+    for section in report.sections() {
+        let _ = writeln!(out, "<h2>{}</h2>", section.title);
+        if section.rows.is_empty() {
+            out.push_str("<p class=\"meta\">None found.</p>\n");
+            continue;
+        }
+
+        out.push_str("<table>\n<tr>");
+        for column in &section.columns {
+            let _ = write!(out, "<th>{}</th>", column);
+        }
+        out.push_str("</tr>\n");
+        for row in &section.rows {
+            out.push_str("<tr>");
+            for value in row {
+                let _ = write!(out, "<td>{}</td>", escape_xml(value));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,558 @@
+pub mod html;
+pub mod pdf;
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    check_response::{get_entity_response, EntityCheckResponse, EntityWithRelations},
+    models::{Entitykind, ADVERSE_FLAGS},
+    postgres::Database,
+    risk::RiskBreakdown,
+};
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    // self contained, styles are inlined
+    Html,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "text/html; charset=utf-8",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+
+    pub fn render(&self, report: &ScreeningReport) -> Vec<u8> {
+        match self {
+            ReportFormat::Html => html::to_html(report).into_bytes(),
+            ReportFormat::Pdf => pdf::to_pdf(report),
+        }
+    }
+}
+
+// Hex encoded sha256, stored with each report so its content can later be verified
+pub fn content_hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Scoring a loaded check needs check_response, which the risk module sits below
+impl RiskBreakdown {
+    pub fn new(check: &EntityCheckResponse) -> Self {
+        let flags = check.entities.iter().flat_map(|entity| &entity.flags);
+        let adverse_flags = flags
+            .clone()
+            .filter(|flag| ADVERSE_FLAGS.contains(flag))
+            .count();
+        let other_flags = flags.count() - adverse_flags;
+        let disqualifications: usize = check
+            .entities
+            .iter()
+            .map(|entity| entity.disqualifications.len())
+            .sum();
+        let insolvencies: usize = check
+            .entities
+            .iter()
+            .map(|entity| entity.insolvencies.len())
+            .sum();
+        let root_exposures: Vec<f64> = match check.entities.iter().find(|e| e.entity.is_root) {
+            Some(root) => check
+                .exposures
+                .iter()
+                .filter(|exposure| exposure.entity_id == root.entity.id)
+                .map(|exposure| exposure.score)
+                .collect(),
+            None => vec![],
+        };
+
+        Self::from_counts(
+            adverse_flags,
+            other_flags,
+            disqualifications,
+            insolvencies,
+            &root_exposures,
+        )
+    }
+}
+
+pub struct MonitoringPeriod {
+    pub started_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    pub last_update_at: Option<NaiveDateTime>,
+}
+
+// A titled table, rendered the same way by every format
+pub struct ReportSection {
+    pub title: &'static str,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+}
+
+// Everything shown in a check's report, built on the same data as the check api
+pub struct ScreeningReport {
+    pub check_id: Uuid,
+    pub generated_at: NaiveDateTime,
+    pub check: EntityCheckResponse,
+    pub risk: RiskBreakdown,
+    pub monitoring: Vec<MonitoringPeriod>,
+}
+
+impl ScreeningReport {
+    pub fn load(database: &mut Database, check_id: Uuid) -> Result<Self, failure::Error> {
+        let check = get_entity_response(database, check_id, None)?;
+        let monitoring = match check.entities.iter().find(|entity| entity.entity.is_root) {
+            Some(root) => database
+                .get_monitoring_history(&root.entity.company_house_number)?
+                .into_iter()
+                .map(|(started_at, ended_at, last_update_at)| MonitoringPeriod {
+                    started_at,
+                    ended_at,
+                    last_update_at,
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(Self {
+            check_id,
+            generated_at: Utc::now().naive_utc(),
+            risk: RiskBreakdown::new(&check),
+            check,
+            monitoring,
+        })
+    }
+
+    pub fn root(&self) -> Option<&EntityWithRelations> {
+        self.check
+            .entities
+            .iter()
+            .find(|entity| entity.entity.is_root)
+    }
+
+    pub fn title(&self) -> String {
+        match self.root() {
+            Some(root) => format!("Screening report: {}", entity_label(root)),
+            None => format!("Screening report: check {}", self.check_id),
+        }
+    }
+
+    pub fn sections(&self) -> Vec<ReportSection> {
+        let entities: HashMap<Uuid, &EntityWithRelations> = self
+            .check
+            .entities
+            .iter()
+            .map(|entity| (entity.entity.id, entity))
+            .collect();
+        let name = |entity_id: &Uuid| match entities.get(entity_id) {
+            Some(entity) => entity_label(entity),
+            None => entity_id.to_string(),
+        };
+
+        vec![
+            self.profile_section(),
+            self.risk_section(),
+            self.flags_section(),
+            self.ownership_section(&entities, &name),
+            self.officers_section(&name),
+            self.beneficial_owners_section(&name),
+            self.findings_section(&name),
+            self.monitoring_section(),
+        ]
+    }
+
+    fn profile_section(&self) -> ReportSection {
+        let mut rows = Vec::new();
+        if let Some(root) = self.root() {
+            let entity = &root.entity;
+            rows.push(field("Name", entity.name.clone()));
+            rows.push(field(
+                "Company number",
+                Some(entity.company_house_number.clone()),
+            ));
+            rows.push(field("Kind", Some(entity.kind.as_str().to_string())));
+            if let Some(profile) = &root.profile {
+                rows.push(field("Status", profile.company_status.clone()));
+                rows.push(field("Type", profile.company_type.clone()));
+                rows.push(field(
+                    "Incorporated on",
+                    profile.date_of_creation.map(|date| date.to_string()),
+                ));
+                rows.push(field(
+                    "Ceased on",
+                    profile.date_of_cessation.map(|date| date.to_string()),
+                ));
+                rows.push(field("Jurisdiction", profile.jurisdiction.clone()));
+                let address: Vec<String> = [
+                    &profile.premises,
+                    &profile.address_line_1,
+                    &profile.address_line_2,
+                    &profile.locality,
+                    &profile.region,
+                    &profile.postal_code,
+                    &profile.country,
+                ]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect();
+                rows.push(field("Address", Some(address.join(", "))));
+                rows.push(field("SIC codes", Some(profile.sic_codes.join(", "))));
+            } else {
+                rows.push(field(
+                    "Date of origin",
+                    entity.date_of_origin.map(|date| date.to_string()),
+                ));
+                rows.push(field("Country", entity.country.clone()));
+            }
+            let previous_names: Vec<&str> = root
+                .previous_names
+                .iter()
+                .map(|previous_name| previous_name.name.as_str())
+                .collect();
+            rows.push(field("Previous names", Some(previous_names.join(", "))));
+        }
+
+        ReportSection {
+            title: "Root entity",
+            columns: vec!["Field", "Value"],
+            rows,
+        }
+    }
+
+    fn risk_section(&self) -> ReportSection {
+        let mut rows: Vec<Vec<String>> = self
+            .risk
+            .components
+            .iter()
+            .map(|component| {
+                vec![
+                    component.name.to_string(),
+                    component.count.to_string(),
+                    format!("{:.1}", component.points),
+                ]
+            })
+            .collect();
+        rows.push(vec![
+            format!("Total ({} risk)", self.risk.level),
+            String::new(),
+            format!("{:.1}", self.risk.score),
+        ]);
+
+        ReportSection {
+            title: "Risk score",
+            columns: vec!["Component", "Count", "Points"],
+            rows,
+        }
+    }
+
+    fn flags_section(&self) -> ReportSection {
+        let rows = self
+            .check
+            .entities
+            .iter()
+            .filter(|entity| !entity.flags.is_empty())
+            .map(|entity| {
+                let flags: Vec<String> = entity
+                    .flags
+                    .iter()
+                    .map(|flag| format!("{:?}", flag))
+                    .collect();
+                vec![
+                    entity_label(entity),
+                    flags.join(", "),
+                    entity.datasets.join(", "),
+                    entity.positions.join(", "),
+                ]
+            })
+            .collect();
+
+        ReportSection {
+            title: "Flags",
+            columns: vec!["Entity", "Flags", "Datasets", "Positions"],
+            rows,
+        }
+    }
+
+    // Shareholders of the root entity, and of each shareholding company in turn
+    fn ownership_section(
+        &self,
+        entities: &HashMap<Uuid, &EntityWithRelations>,
+        name: &impl Fn(&Uuid) -> String,
+    ) -> ReportSection {
+        let mut rows = Vec::new();
+        if let Some(root) = self.root() {
+            let mut stack = vec![(root.entity.id, vec![root.entity.id])];
+            while let Some((entity_id, path)) = stack.pop() {
+                let Some(entity) = entities.get(&entity_id) else {
+                    continue;
+                };
+                // pushed in reverse so shareholders are listed in their original order
+                for shareholder in entity.shareholders.iter().rev() {
+                    if path.contains(&shareholder.entity_id) {
+                        continue;
+                    }
+                    let mut shareholder_path = path.clone();
+                    shareholder_path.push(shareholder.entity_id);
+                    stack.push((shareholder.entity_id, shareholder_path));
+                }
+
+                if entity_id != root.entity.id {
+                    let relation = entities.get(&path[path.len() - 2]).and_then(|child| {
+                        child
+                            .shareholders
+                            .iter()
+                            .find(|shareholder| shareholder.entity_id == entity_id)
+                    });
+                    rows.push(vec![
+                        format!("{}{}", "    ".repeat(path.len() - 2), name(&entity_id)),
+                        optional_date(relation.and_then(|relation| relation.started_on)),
+                        optional_date(relation.and_then(|relation| relation.ended_on)),
+                    ]);
+                }
+            }
+        }
+
+        ReportSection {
+            title: "Ownership",
+            columns: vec!["Shareholder", "Since", "Ceased"],
+            rows,
+        }
+    }
+
+    fn officers_section(&self, name: &impl Fn(&Uuid) -> String) -> ReportSection {
+        let rows = match self.root() {
+            Some(root) => root
+                .officers
+                .iter()
+                .map(|officer| {
+                    vec![
+                        name(&officer.entity_id),
+                        optional_date(officer.started_on),
+                        optional_date(officer.ended_on),
+                    ]
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        ReportSection {
+            title: "Officers",
+            columns: vec!["Officer", "Appointed", "Resigned"],
+            rows,
+        }
+    }
+
+    fn beneficial_owners_section(&self, name: &impl Fn(&Uuid) -> String) -> ReportSection {
+        let rows = self
+            .check
+            .ultimate_beneficial_owners
+            .iter()
+            .chain(&self.check.other_beneficial_owners)
+            .map(|owner| {
+                vec![
+                    name(&owner.entity_id),
+                    format!(
+                        "{:.0}-{:.0}%",
+                        owner.min_ownership * 100.0,
+                        owner.max_ownership * 100.0
+                    ),
+                    if owner.is_ultimate { "Yes" } else { "No" }.to_string(),
+                ]
+            })
+            .collect();
+
+        ReportSection {
+            title: "Beneficial owners",
+            columns: vec!["Owner", "Ownership", "Ultimate"],
+            rows,
+        }
+    }
+
+    // Exposures, disqualifications and insolvencies across every entity in the check
+    fn findings_section(&self, name: &impl Fn(&Uuid) -> String) -> ReportSection {
+        let mut rows = Vec::new();
+        for exposure in &self.check.exposures {
+            let path: Vec<String> = exposure.path.iter().map(name).collect();
+            rows.push(vec![
+                name(&exposure.entity_id),
+                format!("Exposure to {:?}", exposure.flag_kind),
+                format!("score {:.2} via {}", exposure.score, path.join(" > ")),
+            ]);
+        }
+        for entity in &self.check.entities {
+            for disqualification in &entity.disqualifications {
+                rows.push(vec![
+                    entity_label(entity),
+                    "Disqualification".to_string(),
+                    format!(
+                        "{} to {}, {}",
+                        optional_date(disqualification.disqualified_from),
+                        optional_date(disqualification.disqualified_until),
+                        disqualification.reason.clone().unwrap_or_default()
+                    ),
+                ]);
+            }
+            for insolvency in &entity.insolvencies {
+                rows.push(vec![
+                    entity_label(entity),
+                    "Insolvency".to_string(),
+                    format!(
+                        "case {} {}",
+                        insolvency.case_number.clone().unwrap_or_default(),
+                        insolvency.case_type.clone().unwrap_or_default()
+                    ),
+                ]);
+            }
+        }
+
+        ReportSection {
+            title: "Findings",
+            columns: vec!["Entity", "Finding", "Detail"],
+            rows,
+        }
+    }
+
+    fn monitoring_section(&self) -> ReportSection {
+        let rows = self
+            .monitoring
+            .iter()
+            .map(|period| {
+                vec![
+                    period.started_at.to_string(),
+                    period
+                        .ended_at
+                        .map(|ended_at| ended_at.to_string())
+                        .unwrap_or_default(),
+                    period
+                        .last_update_at
+                        .map(|last_update_at| last_update_at.to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        ReportSection {
+            title: "Monitoring history",
+            columns: vec!["Started", "Ended", "Last update"],
+            rows,
+        }
+    }
+}
+
+fn entity_label(entity: &EntityWithRelations) -> String {
+    let name = entity
+        .entity
+        .name
+        .clone()
+        .unwrap_or_else(|| entity.entity.company_house_number.clone());
+    match entity.entity.kind {
+        Entitykind::Company => format!("{} ({})", name, entity.entity.company_house_number),
+        Entitykind::Individual => name,
+    }
+}
+
+fn field(name: &str, value: Option<String>) -> Vec<String> {
+    vec![name.to_string(), value.unwrap_or_default()]
+}
+
+fn optional_date(date: Option<chrono::NaiveDate>) -> String {
+    date.map(|date| date.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_response::Relation;
+    use crate::models::{CheckStatus, Entity, Flagkind};
+    use crate::risk::ADVERSE_FLAG_POINTS;
+
+    fn entity(name: &str, is_root: bool, flags: Vec<Flagkind>) -> EntityWithRelations {
+        EntityWithRelations {
+            entity: Entity {
+                id: Uuid::new_v4(),
+                company_house_number: "00000001".to_string(),
+                name: Some(name.to_string()),
+                is_root,
+                ..Default::default()
+            },
+            officers: vec![],
+            shareholders: vec![],
+            charge_holders: vec![],
+            flags,
+            positions: vec![],
+            datasets: vec![],
+            profile: None,
+            previous_names: vec![],
+            charges: vec![],
+            insolvencies: vec![],
+            disqualifications: vec![],
+        }
+    }
+
+    fn report() -> ScreeningReport {
+        let mut root = entity("Root & Co", true, vec![]);
+        let owner = entity(
+            "Sanctioned Holdings",
+            false,
+            vec![Flagkind::SanctionedEntity],
+        );
+        root.shareholders.push(Relation {
+            entity_id: owner.entity.id,
+            started_on: None,
+            ended_on: None,
+        });
+        let check = EntityCheckResponse {
+            entities: vec![root, owner],
+            ultimate_beneficial_owners: vec![],
+            other_beneficial_owners: vec![],
+            exposures: vec![],
+            traversal_policy: None,
+            as_of: None,
+            started_at: NaiveDateTime::default(),
+            completed_at: None,
+            status: CheckStatus::Running,
+        };
+
+        ScreeningReport {
+            check_id: Uuid::new_v4(),
+            generated_at: NaiveDateTime::default(),
+            risk: RiskBreakdown::new(&check),
+            check,
+            monitoring: vec![],
+        }
+    }
+
+    #[test]
+    fn report_scores_and_renders_check() {
+        let report = report();
+        assert_eq!(report.risk.score, ADVERSE_FLAG_POINTS);
+        assert_eq!(report.risk.level, "Medium");
+
+        let html = html::to_html(&report);
+        assert!(html.contains("Root &amp; Co"));
+        assert!(html.contains("SanctionedEntity"));
+
+        let pdf = pdf::to_pdf(&report);
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert_eq!(content_hash(&pdf).len(), 64);
+    }
+}
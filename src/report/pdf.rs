@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+use super::ScreeningReport;
+
+// A4, in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+const FONT_SIZE: f64 = 9.0;
+const HEADING_SIZE: f64 = 12.0;
+const TITLE_SIZE: f64 = 16.0;
+// Helvetica averages roughly half an em per character
+const CHARS_PER_LINE: usize = ((PAGE_WIDTH - 2.0 * MARGIN) / (FONT_SIZE * 0.5)) as usize;
+
+struct Line {
+    text: String,
+    size: f64,
+    bold: bool,
+}
+
+impl Line {
+    fn new(text: impl Into<String>, size: f64, bold: bool) -> Self {
+        Self {
+            text: text.into(),
+            size,
+            bold,
+        }
+    }
+}
+
+// Renders the same sections as the html report as plain text lines, each table row on
+// one or more lines with its values separated by bars
+pub fn to_pdf(report: &ScreeningReport) -> Vec<u8> {
+    let mut lines = vec![
+        Line::new(report.title(), TITLE_SIZE, true),
+        Line::new(
+            format!(
+                "Check {} started {}. Report generated {} UTC.",
+                report.check_id, report.check.started_at, report.generated_at
+            ),
+            FONT_SIZE,
+            false,
+        ),
+        Line::new(
+            format!(
+                "Risk level: {} (score {:.1})",
+                report.risk.level, report.risk.score
+            ),
+            FONT_SIZE,
+            true,
+        ),
+    ];
+
+    for section in report.sections() {
+        lines.push(Line::new("", FONT_SIZE, false));
+        lines.push(Line::new(section.title, HEADING_SIZE, true));
+        if section.rows.is_empty() {
+            lines.push(Line::new("None found.", FONT_SIZE, false));
+            continue;
+        }
+
+        lines.push(Line::new(section.columns.join(" | "), FONT_SIZE, true));
+        for row in &section.rows {
+            for wrapped in wrap(&row.join(" | "), CHARS_PER_LINE) {
+                lines.push(Line::new(wrapped, FONT_SIZE, false));
+            }
+        }
+    }
+
+    write_pdf(&lines)
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        if !line.is_empty() && line.chars().count() + word.chars().count() + 1 > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+// The code of a character in WinAnsiEncoding, the encoding of the standard fonts. It's
+// latin-1 but for the printable characters it puts in latin-1's control codes
+fn win_ansi_code(c: char) -> Option<u8> {
+    let code = match c {
+        '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8a,
+        '‹' => 0x8b,
+        'Œ' => 0x8c,
+        'Ž' => 0x8e,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9a,
+        '›' => 0x9b,
+        'œ' => 0x9c,
+        'ž' => 0x9e,
+        'Ÿ' => 0x9f,
+        _ => return None,
+    };
+    Some(code)
+}
+
+// Characters outside ascii are written as octal escapes of their WinAnsiEncoding code, so
+// the document stays ascii. Characters the encoding doesn't have are replaced
+fn escape_pdf_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => match win_ansi_code(c) {
+                Some(code) => format!("\\{:03o}", code),
+                None => "?".to_string(),
+            },
+        })
+        .collect()
+}
+
+// A minimal PDF 1.4 document, using the standard Helvetica fonts so nothing needs
+// to be embedded
+fn write_pdf(lines: &[Line]) -> Vec<u8> {
+    let mut pages: Vec<String> = Vec::new();
+    let mut content = String::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for line in lines {
+        let height = line.size * 1.4;
+        if y - height < MARGIN {
+            pages.push(std::mem::take(&mut content));
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        y -= height;
+        let _ = writeln!(
+            content,
+            "BT /{} {} Tf {} {:.1} Td ({}) Tj ET",
+            if line.bold { "F2" } else { "F1" },
+            line.size,
+            MARGIN,
+            y,
+            escape_pdf_text(&line.text)
+        );
+    }
+    pages.push(content);
+
+    // objects 1 and 2 are the catalog and page tree, 3 and 4 the fonts, then a page
+    // and its content stream for each page
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut page_ids = Vec::new();
+    for page in &pages {
+        let page_id = objects.len() + 1;
+        page_ids.push(format!("{} 0 R", page_id));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.len(),
+            page
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        page_ids.join(" "),
+        pages.len()
+    );
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{}\nendobj\n", index + 1, object);
+    }
+    let xref_offset = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    );
+
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_encoded_for_the_standard_fonts() {
+        assert_eq!(escape_pdf_text("Müller (UK)"), "M\\374ller \\(UK\\)");
+        assert_eq!(escape_pdf_text("O’Brien – €5"), "O\\222Brien \\226 \\2005");
+        assert_eq!(escape_pdf_text("李"), "?");
+    }
+}
@@ -0,0 +1,77 @@
+// Scores a check by its findings, shared by the screening report and the check summaries
+
+// Points each finding adds to a check's risk score
+pub(crate) const ADVERSE_FLAG_POINTS: f64 = 10.0;
+const OTHER_FLAG_POINTS: f64 = 1.0;
+const DISQUALIFICATION_POINTS: f64 = 10.0;
+const INSOLVENCY_POINTS: f64 = 5.0;
+// scaled by the score of each exposure of the root entity
+const EXPOSURE_POINTS: f64 = 10.0;
+
+const MEDIUM_RISK_SCORE: f64 = 5.0;
+const HIGH_RISK_SCORE: f64 = 20.0;
+
+pub struct RiskComponent {
+    pub name: &'static str,
+    pub count: usize,
+    pub points: f64,
+}
+
+pub struct RiskBreakdown {
+    pub components: Vec<RiskComponent>,
+    pub score: f64,
+    pub level: &'static str,
+}
+
+impl RiskBreakdown {
+    // Scores findings counted without loading the whole check, as for the check summaries
+    pub fn from_counts(
+        adverse_flags: usize,
+        other_flags: usize,
+        disqualifications: usize,
+        insolvencies: usize,
+        root_exposures: &[f64],
+    ) -> Self {
+        let components = vec![
+            RiskComponent {
+                name: "Adverse flags",
+                count: adverse_flags,
+                points: adverse_flags as f64 * ADVERSE_FLAG_POINTS,
+            },
+            RiskComponent {
+                name: "Other flags",
+                count: other_flags,
+                points: other_flags as f64 * OTHER_FLAG_POINTS,
+            },
+            RiskComponent {
+                name: "Disqualifications",
+                count: disqualifications,
+                points: disqualifications as f64 * DISQUALIFICATION_POINTS,
+            },
+            RiskComponent {
+                name: "Insolvencies",
+                count: insolvencies,
+                points: insolvencies as f64 * INSOLVENCY_POINTS,
+            },
+            RiskComponent {
+                name: "Exposures of the root entity",
+                count: root_exposures.len(),
+                points: root_exposures.iter().sum::<f64>() * EXPOSURE_POINTS,
+            },
+        ];
+        let score = components.iter().map(|component| component.points).sum();
+        let level = if score >= HIGH_RISK_SCORE {
+            "High"
+        } else if score >= MEDIUM_RISK_SCORE {
+            "Medium"
+        } else {
+            "Low"
+        };
+
+        Self {
+            components,
+            score,
+            level,
+        }
+    }
+}
@@ -1,10 +1,18 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "casestatus"))]
+    pub struct Casestatus;
+
     #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "checkkind"))]
     pub struct Checkkind;
 
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "checkstatus"))]
+    pub struct Checkstatus;
+
     #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "entitykind"))]
     pub struct Entitykind;
@@ -13,6 +21,14 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "flagkind"))]
     pub struct Flagkind;
 
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ownershipband"))]
+    pub struct Ownershipband;
+
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "priority"))]
+    pub struct Priority;
+
     #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "relationshipkind"))]
     pub struct Relationshipkind;
@@ -20,16 +36,146 @@ pub mod sql_types {
     #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "updatekind"))]
     pub struct Updatekind;
+
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "verdict"))]
+    pub struct Verdict;
+}
+
+diesel::table! {
+    api_key (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        name -> Text,
+        key_hash -> Text,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    audit_log (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        api_key_id -> Nullable<Uuid>,
+        method -> Text,
+        path -> Text,
+        target_id -> Nullable<Uuid>,
+        status -> Int4,
+        occurred_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    batch (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        name -> Nullable<Text>,
+        submitted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    batch_row (batch_id, row_number) {
+        batch_id -> Uuid,
+        row_number -> Int4,
+        company_number -> Nullable<Text>,
+        name -> Nullable<Text>,
+        postcode -> Nullable<Text>,
+        traversal_policy -> Jsonb,
+        check_id -> Nullable<Uuid>,
+        error -> Nullable<Text>,
+        started_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    beneficial_owner (check_id, entity_id) {
+        check_id -> Uuid,
+        entity_id -> Uuid,
+        min_ownership -> Float8,
+        max_ownership -> Float8,
+        is_ultimate -> Bool,
+    }
+}
+
+diesel::table! {
+    beneficial_owner_path (id) {
+        id -> Uuid,
+        check_id -> Uuid,
+        entity_id -> Uuid,
+        path -> Array<Uuid>,
+        min_ownership -> Float8,
+        max_ownership -> Float8,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Entitykind;
+
+    canonical_entity (id) {
+        id -> Uuid,
+        kind -> Entitykind,
+        name -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    canonical_entity_key (key_kind, key_value) {
+        key_kind -> Text,
+        key_value -> Text,
+        canonical_entity_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    charge (id) {
+        id -> Uuid,
+        entity_id -> Uuid,
+        charge_code -> Nullable<Text>,
+        classification -> Nullable<Text>,
+        status -> Nullable<Text>,
+        created_on -> Nullable<Date>,
+        delivered_on -> Nullable<Date>,
+        satisfied_on -> Nullable<Date>,
+    }
 }
 
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Checkkind;
+    use super::sql_types::Priority;
+    use super::sql_types::Checkstatus;
 
     check (id) {
         id -> Uuid,
         started_at -> Timestamp,
         kind -> Checkkind,
+        tenant_id -> Uuid,
+        priority -> Priority,
+        status -> Checkstatus,
+        status_updated_at -> Nullable<Timestamp>,
+        global_risk_queued_at -> Nullable<Timestamp>,
+        exposure_requested_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Casestatus;
+
+    check_case (check_id) {
+        check_id -> Uuid,
+        status -> Casestatus,
+        assignee_id -> Nullable<Uuid>,
+        opened_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -37,6 +183,18 @@ diesel::table! {
     check_entity_map (check_id, entity_id) {
         check_id -> Uuid,
         entity_id -> Uuid,
+        canonical_entity_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    check_frontier (check_id, entity_id, relation_job_kind) {
+        check_id -> Uuid,
+        entity_id -> Uuid,
+        relation_job_kind -> Text,
+        remaining_depth -> Int4,
+        enqueued_at -> Timestamp,
+        fetched_at -> Nullable<Timestamp>,
     }
 }
 
@@ -54,6 +212,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    check_propagation_policy (check_id) {
+        check_id -> Uuid,
+        policy -> Jsonb,
+    }
+}
+
+diesel::table! {
+    check_report (id) {
+        id -> Uuid,
+        check_id -> Uuid,
+        format -> Text,
+        content -> Bytea,
+        content_hash -> Text,
+        generated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     check_snapshot (check_id, snapshot_id) {
         check_id -> Uuid,
@@ -61,6 +237,61 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Checkkind;
+    use super::sql_types::Flagkind;
+    use super::sql_types::Checkstatus;
+
+    check_summary (check_id) {
+        check_id -> Uuid,
+        root_entity_id -> Nullable<Uuid>,
+        company_house_number -> Nullable<Text>,
+        name -> Nullable<Text>,
+        started_at -> Timestamp,
+        completed_at -> Nullable<Timestamp>,
+        has_error -> Bool,
+        entity_count -> Int8,
+        distinct_flags -> Array<Flagkind>,
+        updated_at -> Timestamp,
+        kind -> Checkkind,
+        risk_score -> Float8,
+        risk_level -> Text,
+        tenant_id -> Uuid,
+        status -> Checkstatus,
+    }
+}
+
+diesel::table! {
+    check_traversal_policy (check_id) {
+        check_id -> Uuid,
+        policy -> Jsonb,
+    }
+}
+
+diesel::table! {
+    company_profile (entity_id) {
+        entity_id -> Uuid,
+        company_status -> Nullable<Text>,
+        company_type -> Nullable<Text>,
+        sic_codes -> Array<Text>,
+        date_of_creation -> Nullable<Date>,
+        date_of_cessation -> Nullable<Date>,
+        jurisdiction -> Nullable<Text>,
+        premises -> Nullable<Text>,
+        address_line_1 -> Nullable<Text>,
+        address_line_2 -> Nullable<Text>,
+        locality -> Nullable<Text>,
+        region -> Nullable<Text>,
+        postal_code -> Nullable<Text>,
+        country -> Nullable<Text>,
+        has_charges -> Nullable<Bool>,
+        has_insolvency_history -> Nullable<Bool>,
+        has_been_liquidated -> Nullable<Bool>,
+        fetched_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     dataset (id) {
         id -> Uuid,
@@ -75,6 +306,40 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Verdict;
+
+    disposition (id) {
+        id -> Uuid,
+        check_id -> Uuid,
+        flag_id -> Nullable<Uuid>,
+        sanctions_match_id -> Nullable<Uuid>,
+        verdict -> Verdict,
+        comment -> Nullable<Text>,
+        user_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    disqualification (id) {
+        id -> Uuid,
+        entity_id -> Uuid,
+        case_identifier -> Nullable<Text>,
+        disqualification_type -> Nullable<Text>,
+        disqualified_from -> Nullable<Date>,
+        disqualified_until -> Nullable<Date>,
+        heard_on -> Nullable<Date>,
+        undertaken_on -> Nullable<Date>,
+        reason -> Nullable<Text>,
+        act -> Nullable<Text>,
+        section -> Nullable<Text>,
+        court_name -> Nullable<Text>,
+        company_names -> Array<Text>,
+    }
+}
+
 diesel::table! {
     dormant_company (entity_id) {
         entity_id -> Uuid,
@@ -93,9 +358,28 @@ diesel::table! {
         kind -> Entitykind,
         country -> Nullable<Text>,
         postal_code -> Nullable<Text>,
-        date_of_origin -> Nullable<Text>,
+        date_of_origin -> Nullable<Date>,
         is_root -> Bool,
         officer_id -> Nullable<Text>,
+        date_of_origin_day_known -> Bool,
+        canonical_entity_id -> Nullable<Uuid>,
+        source_fetch_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Flagkind;
+
+    exposure (id) {
+        id -> Uuid,
+        check_id -> Uuid,
+        entity_id -> Uuid,
+        origin_entity_id -> Uuid,
+        origin_flag_id -> Uuid,
+        flag_kind -> Flagkind,
+        path -> Array<Uuid>,
+        score -> Float8,
     }
 }
 
@@ -106,6 +390,8 @@ diesel::table! {
     flag (id) {
         id -> Uuid,
         kind -> Flagkind,
+        source_fetch_id -> Nullable<Uuid>,
+        sanctions_match_id -> Nullable<Uuid>,
     }
 }
 
@@ -116,6 +402,37 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    insolvency (id) {
+        id -> Uuid,
+        entity_id -> Uuid,
+        case_number -> Nullable<Text>,
+        case_type -> Nullable<Text>,
+        notes -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    insolvency_date (insolvency_id, kind, date) {
+        insolvency_id -> Uuid,
+        kind -> Text,
+        date -> Date,
+    }
+}
+
+diesel::table! {
+    insolvency_practitioner (id) {
+        id -> Uuid,
+        insolvency_id -> Uuid,
+        name -> Text,
+        role -> Nullable<Text>,
+        appointed_on -> Nullable<Date>,
+        ceased_to_act_on -> Nullable<Date>,
+        postal_code -> Nullable<Text>,
+        country -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     job (id) {
         id -> Uuid,
@@ -130,6 +447,7 @@ diesel::table! {
         id -> Uuid,
         company_house_id -> Text,
         monitoring_span_id -> Uuid,
+        tenant_id -> Uuid,
     }
 }
 
@@ -148,6 +466,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    parked_job (job_id) {
+        job_id -> Uuid,
+        check_id -> Uuid,
+        topic -> Text,
+        job -> Jsonb,
+        parked_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     position (id) {
         id -> Uuid,
@@ -162,6 +490,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    previous_name (id) {
+        id -> Uuid,
+        entity_id -> Uuid,
+        name -> Text,
+        effective_from -> Nullable<Date>,
+        ceased_on -> Nullable<Date>,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Updatekind;
@@ -174,16 +512,43 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    raw_payload (response_hash) {
+        response_hash -> Text,
+        body -> Bytea,
+    }
+}
+
+diesel::table! {
+    recent_insolvency (entity_id) {
+        entity_id -> Uuid,
+        recent -> Bool,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Relationshipkind;
+    use super::sql_types::Ownershipband;
 
-    relationship (parent_id, child_id) {
+    relationship (parent_id, child_id, kind) {
         parent_id -> Uuid,
         child_id -> Uuid,
         kind -> Relationshipkind,
         started_on -> Nullable<Date>,
         ended_on -> Nullable<Date>,
+        ownership_band -> Nullable<Ownershipband>,
+        source_fetch_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    sanctions_match (id) {
+        id -> Uuid,
+        entity_id -> Uuid,
+        open_sanctions_id -> Text,
+        caption -> Text,
+        source_fetch_id -> Nullable<Uuid>,
     }
 }
 
@@ -195,25 +560,92 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    source_fetch (id) {
+        id -> Uuid,
+        check_id -> Uuid,
+        source -> Text,
+        endpoint -> Text,
+        fetched_at -> Timestamp,
+        response_hash -> Text,
+    }
+}
+
+diesel::table! {
+    tenant (id) {
+        id -> Uuid,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tenant_user (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        email -> Text,
+        name -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    unusual_lender (entity_id) {
+        entity_id -> Uuid,
+        unusual -> Bool,
+    }
+}
+
 diesel::allow_tables_to_appear_in_same_query!(
+    api_key,
+    audit_log,
+    batch,
+    batch_row,
+    beneficial_owner,
+    beneficial_owner_path,
+    canonical_entity,
+    canonical_entity_key,
+    charge,
     check,
+    check_case,
     check_entity_map,
+    check_frontier,
     check_job_map,
     check_monitored_entity,
+    check_propagation_policy,
+    check_report,
     check_snapshot,
+    check_summary,
+    check_traversal_policy,
+    company_profile,
     dataset,
     datasets,
+    disposition,
+    disqualification,
     dormant_company,
     entity,
+    exposure,
     flag,
     flags,
+    insolvency,
+    insolvency_date,
+    insolvency_practitioner,
     job,
     monitored_entity,
     monitoring_span,
     outlier_age,
+    parked_job,
     position,
     positions,
+    previous_name,
     processed_update,
+    raw_payload,
+    recent_insolvency,
     relationship,
+    sanctions_match,
     snapshot,
+    source_fetch,
+    tenant,
+    tenant_user,
+    unusual_lender,
 );
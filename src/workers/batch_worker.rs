@@ -0,0 +1,84 @@
+use std::env;
+
+use failure::format_err;
+use pulsar::SubType;
+
+use crate::{
+    jobs::{
+        batch_jobs::{RowStart, DEFERRED_ROW_DELAY},
+        jobs::{Job, JobKind},
+    },
+    postgres::Database,
+    pulsar::{PulsarClient, PulsarProducer},
+    registry::registry_client::RegistryClient,
+};
+
+use super::{
+    entity_relation_worker::ENTITY_RELATION_TOPIC,
+    worker::{Work, Worker},
+};
+
+// Persistent, as batch jobs can wait a long time for their turn
+pub const BATCH_TOPIC: &str = "persistent://public/default/batch";
+const SUBSCRIPTION: &str = "Batch-Sub";
+// rows are started in about the order they were submitted. Shared, as only shared
+// subscriptions wait for rows deferred until their tenant has room for another check
+const SUB_TYPE: SubType = SubType::Shared;
+// batch checks' first jobs are enqueued no faster than the batch share of this
+const RATE_LIMIT_PER_MIN: u32 = 60;
+const DEFAULT_MAX_RUNNING_CHECKS: i64 = 10;
+
+pub struct BatchWorker<C: RegistryClient> {
+    pub database: Database,
+    pub registry_client: C,
+    pub entity_relation_producer: PulsarProducer,
+    // deferred rows are sent back to the batch topic
+    pub batch_producer: PulsarProducer,
+    // set by BATCH_MAX_RUNNING_CHECKS
+    pub max_running_checks: i64,
+}
+
+impl<C: RegistryClient> BatchWorker<C> {
+    pub async fn new_worker(registry_client: C) -> Result<Worker<BatchWorker<C>>, failure::Error> {
+        let pulsar_client = PulsarClient::new().await;
+        let max_running_checks = match env::var("BATCH_MAX_RUNNING_CHECKS") {
+            Ok(max_running_checks) => max_running_checks.parse()?,
+            Err(_) => DEFAULT_MAX_RUNNING_CHECKS,
+        };
+
+        let batch_worker = Self {
+            database: Database::connect()?,
+            registry_client,
+            entity_relation_producer: pulsar_client
+                .create_producer(ENTITY_RELATION_TOPIC, Some(RATE_LIMIT_PER_MIN), None)
+                .await,
+            batch_producer: pulsar_client.create_producer(BATCH_TOPIC, None, None).await,
+            max_running_checks,
+        };
+        Ok(Worker::new(vec![BATCH_TOPIC], SUBSCRIPTION, SUB_TYPE, batch_worker).await?)
+    }
+}
+
+impl<C: RegistryClient> Work for BatchWorker<C> {
+    async fn work(&mut self, job: Job) -> Result<(), failure::Error> {
+        let job_result = match &job.job_kind {
+            JobKind::BatchJob(batch_job) => batch_job.do_job(self).await,
+            job_kind => Err(format_err!("Batch service can't do {:?}", job_kind)),
+        };
+
+        // the job isn't completed until it's delivered again and its row is started
+        if let Ok(RowStart::Deferred) = job_result {
+            return self
+                .batch_producer
+                .send_job_after(job, DEFERRED_ROW_DELAY)
+                .await;
+        }
+
+        self.database.complete_job(job.id)?;
+        if let Err(_) = job_result {
+            self.database.update_job_with_error(&job.id)?
+        }
+        job_result?;
+        Ok(())
+    }
+}
@@ -1,15 +1,15 @@
 use pulsar::SubType;
 
 use crate::{
-    company_house::company_house_apis::CompanyHouseClient,
     jobs::jobs::{Job, JobKind},
     postgres::Database,
     pulsar::{PulsarClient, PulsarProducer},
+    registry::registry_client::RegistryClient,
 };
 
 use super::{
     risk_worker::RISK_TOPIC,
-    worker::{Work, Worker},
+    worker::{admit_job, Work, Worker},
 };
 
 pub const ENTITY_RELATION_TOPIC: &str = "non-persistent://public/default/entity-relation";
@@ -18,20 +18,22 @@ const RATE_LIMIT_PER_MIN: u32 = 120;
 const MAX_JOB_PER_CHECK: usize = 2000;
 const SUB_TYPE: SubType = SubType::Exclusive;
 
-pub struct EntityRelationWorker {
+pub struct EntityRelationWorker<C: RegistryClient> {
     pub database: Database,
-    pub company_house_client: CompanyHouseClient,
+    pub registry_client: C,
     pub entity_relation_producer: PulsarProducer,
     pub risk_producer: PulsarProducer,
 }
 
-impl EntityRelationWorker {
-    pub async fn new_worker() -> Result<Worker<EntityRelationWorker>, failure::Error> {
+impl<C: RegistryClient> EntityRelationWorker<C> {
+    pub async fn new_worker(
+        registry_client: C,
+    ) -> Result<Worker<EntityRelationWorker<C>>, failure::Error> {
         let pulsar_client = PulsarClient::new().await;
 
         let entity_relation_worker = Self {
             database: Database::connect()?,
-            company_house_client: CompanyHouseClient::new(),
+            registry_client,
             entity_relation_producer: pulsar_client
                 .create_producer(
                     ENTITY_RELATION_TOPIC,
@@ -51,8 +53,12 @@ impl EntityRelationWorker {
     }
 }
 
-impl Work for EntityRelationWorker {
+impl<C: RegistryClient> Work for EntityRelationWorker<C> {
     async fn work(&mut self, job: Job) -> Result<(), failure::Error> {
+        if !admit_job(&mut self.database, &job, ENTITY_RELATION_TOPIC)? {
+            return Ok(());
+        }
+
         let job_result = match job.job_kind {
             JobKind::RelationJob(relation_job) => relation_job.do_work(self).await,
             _ => unimplemented!(),
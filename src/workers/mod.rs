@@ -1,3 +1,4 @@
+pub mod batch_worker;
 pub mod entity_relation_worker;
 pub mod monitored_update_worker;
 pub mod risk_worker;
@@ -1,37 +1,47 @@
 use pulsar::SubType;
 
 use crate::{
-    company_house::company_house_apis::CompanyHouseClient,
     jobs::jobs::{Job, JobKind},
     open_sanctions::api::OpenSanctionsClient,
     postgres::Database,
+    pulsar::{PulsarClient, PulsarProducer},
+    registry::registry_client::RegistryClient,
 };
 
-use super::worker::{Work, Worker};
+use super::worker::{admit_job, Work, Worker};
 
 pub const RISK_TOPIC: &str = "non-persistent://public/default/risk";
 const SUBSCRIPTION: &str = "Risk-Sub";
 const SUB_TYPE: SubType = SubType::Shared;
 
-pub struct RiskWorker {
+pub struct RiskWorker<C: RegistryClient> {
     pub database: Database,
     pub open_sanctions_client: OpenSanctionsClient,
-    pub company_house_client: CompanyHouseClient,
+    pub registry_client: C,
+    // queues exposure recomputes for flags found after a check's relations are all known
+    pub risk_producer: PulsarProducer,
 }
 
-impl RiskWorker {
-    pub async fn new_worker() -> Result<Worker<RiskWorker>, failure::Error> {
+impl<C: RegistryClient> RiskWorker<C> {
+    pub async fn new_worker(registry_client: C) -> Result<Worker<RiskWorker<C>>, failure::Error> {
+        let pulsar_client = PulsarClient::new().await;
+
         let risk_worker = RiskWorker {
             database: Database::connect()?,
             open_sanctions_client: OpenSanctionsClient::new(),
-            company_house_client: CompanyHouseClient::new(),
+            registry_client,
+            risk_producer: pulsar_client.create_producer(RISK_TOPIC, None, None).await,
         };
         Ok(Worker::new(vec![RISK_TOPIC], SUBSCRIPTION, SUB_TYPE, risk_worker).await?)
     }
 }
 
-impl Work for RiskWorker {
+impl<C: RegistryClient> Work for RiskWorker<C> {
     async fn work(&mut self, job: Job) -> Result<(), failure::Error> {
+        if !admit_job(&mut self.database, &job, RISK_TOPIC)? {
+            return Ok(());
+        }
+
         let job_result = match job.job_kind {
             JobKind::RiskJob(risk_job) => risk_job.do_job(self).await,
             _ => unimplemented!(),
@@ -1,9 +1,12 @@
-use futures::TryStreamExt;
+use chrono::Utc;
+use futures::{future::select_all, FutureExt, TryStreamExt};
 use log::{info, warn};
-use pulsar::SubType;
+use pulsar::{consumer::Message, SubType};
 
 use crate::{
     jobs::jobs::Job,
+    models::{CheckStatus, ParkedJob, Priority},
+    postgres::Database,
     pulsar::{PulsarClient, PulsarConsumer},
 };
 
@@ -14,12 +17,93 @@ pub trait Work {
     ) -> impl std::future::Future<Output = Result<(), failure::Error>> + Send;
 }
 
+// What's done with a job given the status of its check
+#[derive(Debug, PartialEq)]
+enum Admission {
+    Do,
+    // completed without being done
+    Complete,
+    // to be sent to the topic again when the check is resumed
+    Park,
+}
+
+fn admission(status: CheckStatus) -> Admission {
+    match status {
+        CheckStatus::Running => Admission::Do,
+        CheckStatus::Paused => Admission::Park,
+        CheckStatus::Cancelled => Admission::Complete,
+    }
+}
+
+// Whether the job's check lets it be done now, see Admission. Jobs without a check are done
+pub fn admit_job(database: &mut Database, job: &Job, topic: &str) -> Result<bool, failure::Error> {
+    let (check_id, status) = match database.get_job_check_status(&job.id)? {
+        Some(check_status) => check_status,
+        None => return Ok(true),
+    };
+
+    match admission(status) {
+        Admission::Do => Ok(true),
+        Admission::Complete => {
+            database.complete_job(job.id)?;
+            Ok(false)
+        }
+        Admission::Park => {
+            database.park_job(&ParkedJob {
+                job_id: job.id,
+                check_id,
+                topic: topic.to_string(),
+                job: serde_json::to_value(job)?,
+                parked_at: Utc::now().naive_utc(),
+            })?;
+
+            // the check may have been resumed or cancelled while the job was being parked,
+            // the job is taken back unless that already took it
+            match admission(database.get_check_status(&check_id)?) {
+                Admission::Park => Ok(false),
+                _ if !database.unpark_job(&job.id)? => Ok(false),
+                Admission::Complete => {
+                    database.complete_job(job.id)?;
+                    Ok(false)
+                }
+                Admission::Do => Ok(true),
+            }
+        }
+    }
+}
+
 pub struct Worker<T: Work> {
-    pub consumer: PulsarConsumer,
+    // one per priority, in the order of Priority::ALL
+    pub consumers: Vec<(Priority, PulsarConsumer)>,
+    schedule: Vec<usize>,
+    next_turn: usize,
     pub internal_worker: T,
 }
 
+// The turns of the priorities, as indexes of Priority::ALL, each having as many as its weight.
+// Turns are spread out (smooth weighted round robin) so lower priorities aren't left waiting
+// for a run of higher priority turns
+fn schedule() -> Vec<usize> {
+    let total_weight: i64 = Priority::ALL.iter().map(|p| p.weight() as i64).sum();
+    let mut current = [0i64; Priority::ALL.len()];
+
+    (0..total_weight)
+        .map(|_| {
+            for (i, priority) in Priority::ALL.iter().enumerate() {
+                current[i] += priority.weight() as i64;
+            }
+            let turn = (0..current.len())
+                .rev()
+                .max_by_key(|&i| current[i])
+                .unwrap();
+            current[turn] -= total_weight;
+            turn
+        })
+        .collect()
+}
+
 impl<T: Work> Worker<T> {
+    // Consumes each topic's priority topics, see Priority::topic
     pub async fn new(
         topics: Vec<&str>,
         sub: &str,
@@ -28,22 +112,59 @@ impl<T: Work> Worker<T> {
     ) -> Result<Self, failure::Error> {
         let pulsar_client = PulsarClient::new().await;
 
+        let mut consumers = vec![];
+        for priority in Priority::ALL {
+            let priority_topics = topics.iter().map(|topic| priority.topic(topic)).collect();
+            consumers.push((
+                priority,
+                pulsar_client
+                    .create_consumer(priority_topics, sub_type, sub)
+                    .await,
+            ));
+        }
+
         Ok(Self {
-            consumer: pulsar_client.create_consumer(topics, sub_type, sub).await,
+            consumers,
+            schedule: schedule(),
+            next_turn: 0,
             internal_worker,
         })
     }
 }
 
 impl<T: Work> Worker<T> {
+    // The next message and the index of the consumer it's from. Priorities take their turns,
+    // a priority with nothing waiting passing its turn on. When none have anything waiting
+    // the first message to arrive is taken
+    async fn next_message(&mut self) -> Option<(usize, Message<Job>)> {
+        for offset in 0..self.schedule.len() {
+            let turn = (self.next_turn + offset) % self.schedule.len();
+            let i = self.schedule[turn];
+            if let Some(msg) = self.consumers[i]
+                .1
+                .internal_consumer
+                .try_next()
+                .now_or_never()
+            {
+                self.next_turn = (turn + 1) % self.schedule.len();
+                return msg
+                    .expect("Should be able to wait for new message.")
+                    .map(|msg| (i, msg));
+            }
+        }
+
+        let (msg, i, _) = select_all(
+            self.consumers
+                .iter_mut()
+                .map(|(_, consumer)| consumer.internal_consumer.try_next()),
+        )
+        .await;
+        msg.expect("Should be able to wait for new message.")
+            .map(|msg| (i, msg))
+    }
+
     pub async fn do_work(&mut self) {
-        while let Some(msg) = self
-            .consumer
-            .internal_consumer
-            .try_next()
-            .await
-            .expect("Should be able to wait for new message.")
-        {
+        while let Some((i, msg)) = self.next_message().await {
             let job = match msg.deserialize() {
                 Ok(data) => data,
                 Err(e) => {
@@ -53,20 +174,21 @@ impl<T: Work> Worker<T> {
             };
 
             let job_id = job.id;
+            let consumer = &mut self.consumers[i].1;
             match self.internal_worker.work(job).await {
                 Ok(_) => {
-                    self.consumer.ack(&msg).await;
+                    consumer.ack(&msg).await;
                     println!("Job completed successfully, id: {:?}", job_id);
                     info!("Job completed successfully, id: {:?}", job_id)
                 }
                 Err(e) => {
-                    self.consumer.nack(&msg).await;
+                    consumer.nack(&msg).await;
                     println!("Job had an error, id: {:?}, error:{:?}", job_id, e);
                     warn!("Job had an error, id: {:?}, error: {:?}", job_id, e)
                 }
             }
 
-            match self.consumer.internal_consumer.ack(&msg).await {
+            match consumer.internal_consumer.ack(&msg).await {
                 Ok(_) => {}
                 Err(e) => {
                     warn!("Couldn't acknowledge message, error: {:?}", e)
@@ -75,3 +197,28 @@ impl<T: Work> Worker<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_jobs_of_running_checks_are_done() {
+        assert_eq!(admission(CheckStatus::Running), Admission::Do);
+        assert_eq!(admission(CheckStatus::Paused), Admission::Park);
+        assert_eq!(admission(CheckStatus::Cancelled), Admission::Complete);
+    }
+
+    #[test]
+    fn schedule_gives_turns_by_weight() {
+        let schedule = schedule();
+        let turns = |i| schedule.iter().filter(|&&turn| turn == i).count();
+
+        assert_eq!(schedule[0], 0);
+        for (i, priority) in Priority::ALL.iter().enumerate() {
+            assert_eq!(turns(i), priority.weight() as usize);
+        }
+        // batch jobs aren't held back until every interactive turn is taken
+        assert!(schedule[..4].contains(&1));
+    }
+}